@@ -10,7 +10,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_server(true)
         .build_client(true)
         .file_descriptor_set_path(out_dir.join("mandos_auth_descriptor.bin"))
-        .out_dir("./src")
         .compile(&[proto_file], &["proto"])?;
 
     Ok(())
@@ -0,0 +1,7 @@
+create table users_mfa (
+    id uuid PRIMARY KEY REFERENCES users_auth(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    totp_secret TEXT NOT NULL,
+    totp_enabled BOOLEAN NOT NULL
+);
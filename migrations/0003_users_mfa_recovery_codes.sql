@@ -0,0 +1,20 @@
+create table users_mfa_recovery_codes (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users_auth(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    code_hash VARCHAR(255) NOT NULL
+);
+
+create index users_mfa_recovery_codes_user_id_idx on users_mfa_recovery_codes(user_id);
+
+-- the audit log is kept also after the user is deleted
+create table audit_log (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    user_id uuid NOT NULL,
+    event VARCHAR(255) NOT NULL,
+    ip TEXT NOT NULL,
+    user_agent TEXT NOT NULL
+);
+
+create index audit_log_user_id_idx on audit_log(user_id);
@@ -0,0 +1,17 @@
+-- usernames and emails are unique regardless of their case and unicode form
+-- (existing accounts that only differ by case have to be merged before running this migration)
+update users_auth set username = normalize(trim(username), NFKC), email = normalize(trim(email), NFKC);
+
+alter table users_auth add column username_normalized VARCHAR(255);
+alter table users_auth add column email_normalized VARCHAR(255);
+
+update users_auth set username_normalized = lower(username), email_normalized = lower(email);
+
+alter table users_auth alter column username_normalized set NOT NULL;
+alter table users_auth alter column email_normalized set NOT NULL;
+
+drop index users_auth_username_idx;
+drop index users_auth_email_idx;
+
+create unique index users_auth_username_normalized_idx on users_auth(username_normalized);
+create unique index users_auth_email_normalized_idx on users_auth(email_normalized);
@@ -0,0 +1,35 @@
+create table roles (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL
+);
+
+create unique index roles_name_idx on roles(name);
+
+create table permissions (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    name VARCHAR(255) NOT NULL
+);
+
+create unique index permissions_name_idx on permissions(name);
+
+create table role_permissions (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    role_id uuid NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id uuid NOT NULL REFERENCES permissions(id) ON DELETE CASCADE
+);
+
+create unique index role_permissions_role_id_permission_id_idx on role_permissions(role_id, permission_id);
+
+create table user_roles (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    user_id uuid NOT NULL REFERENCES users_auth(id) ON DELETE CASCADE,
+    role_id uuid NOT NULL REFERENCES roles(id) ON DELETE CASCADE
+);
+
+create unique index user_roles_user_id_role_id_idx on user_roles(user_id, role_id);
@@ -0,0 +1,17 @@
+create table organizations (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    name VARCHAR(255) NOT NULL
+);
+
+create table organization_members (
+    id uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    organization_id uuid NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users_auth(id) ON DELETE CASCADE,
+    role VARCHAR(255) NOT NULL
+);
+
+create unique index organization_members_organization_id_user_id_idx on organization_members(organization_id, user_id);
+create index organization_members_user_id_idx on organization_members(user_id);
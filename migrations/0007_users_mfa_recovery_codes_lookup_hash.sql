@@ -0,0 +1,5 @@
+-- keyed hash of the recovery codes, to verify only the code that was typed
+-- the codes created before it have no lookup hash and are verified one by one
+alter table users_mfa_recovery_codes add column lookup_hash VARCHAR(64);
+
+create index users_mfa_recovery_codes_lookup_hash_idx on users_mfa_recovery_codes(user_id, lookup_hash);
@@ -0,0 +1,3 @@
+-- the lookup hashes are now computed with a key derived from the MFA key instead of the MFA key
+-- itself, the ones computed before can't match, the codes without one are verified one by one
+update users_mfa_recovery_codes set lookup_hash = null;
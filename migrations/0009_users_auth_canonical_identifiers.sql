@@ -0,0 +1,32 @@
+-- the normalized usernames and emails of 0004 were lowercased by lower() with the collation of
+-- the database, which only lowercases ASCII with the C collation, recompute them with the unicode
+-- lowercase of ICU, the same mapping as identifiers::canonical
+-- (the values are already NFKC normalized and trimmed by 0004 and by the server)
+
+-- the accounts that have the same username or email once lowercased have to be merged by hand
+-- before running this migration, it fails listing them
+do $$
+declare
+    conflicts text;
+begin
+    select string_agg(c.ids, '; ') into conflicts
+    from (
+        select 'username ' || string_agg(id::text, ', ') as ids
+        from users_auth
+        group by lower(username collate "und-x-icu")
+        having count(*) > 1
+        union all
+        select 'email ' || string_agg(id::text, ', ') as ids
+        from users_auth
+        group by lower(email collate "und-x-icu")
+        having count(*) > 1
+    ) c;
+
+    if conflicts is not null then
+        raise exception 'users with the same username or email have to be merged first: %', conflicts;
+    end if;
+end $$;
+
+update users_auth set
+    username_normalized = lower(username collate "und-x-icu"),
+    email_normalized = lower(email collate "und-x-icu");
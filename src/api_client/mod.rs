@@ -0,0 +1,113 @@
+use std::fs;
+
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    error::{Error, Result},
+    utils,
+};
+
+/// Name of the client that authenticates with the legacy GRPC_AUTH_KEY and GRPC_AUTH_VALUE
+pub const LEGACY_API_CLIENT_NAME: &str = "legacy";
+
+/// Scope that allows a client to call every RPC, except the admin ones
+const ALL_SCOPES: &str = "*";
+/// Scope that allows a client to call the admin RPCs
+const ADMIN_SCOPE: &str = "admin";
+/// RPCs that only the clients with the admin scope can call
+const ADMIN_RPC_METHODS: &[&str] = &["ClearLoginLockout"];
+
+// region: ApiClient
+
+/// A service allowed to call the gRPC server, as listed in the client registry file
+#[derive(Clone, Debug, Deserialize)]
+pub struct ApiClient {
+    pub name: String,
+    // SHA-256 (hex encoded) of the active secrets, more than one while a secret is rotated
+    #[serde(default)]
+    pub secret_hashes: Vec<String>,
+    // names of the RPCs the client can call (e.g. Login), "*" for all of them, "admin" for the
+    // admin ones
+    pub scopes: Vec<String>,
+    // with mTLS on, a client certificate with one of these subject common names stands in for
+    // the secret
+    #[serde(default)]
+    pub certificate_common_names: Vec<String>,
+}
+
+impl ApiClient {
+    /// Returns whether the secret is one of the active secrets of the client
+    /// All the hashes are compared in constant time, without stopping at the first match
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        let secret_hash = utils::hash_token(secret);
+
+        self.secret_hashes.iter().fold(0u8, |valid, h| {
+            valid | secret_hash.as_bytes().ct_eq(h.as_bytes()).unwrap_u8()
+        }) == 1
+    }
+
+    /// Returns whether the client can call the RPC
+    /// The admin RPCs need the admin scope, "*" does not include them
+    pub fn allows(&self, rpc_method: &str) -> bool {
+        if ADMIN_RPC_METHODS.contains(&rpc_method) {
+            return self.scopes.iter().any(|s| s == ADMIN_SCOPE);
+        }
+
+        self.scopes
+            .iter()
+            .any(|s| s == ALL_SCOPES || s == rpc_method)
+    }
+}
+
+/// Loads the client registry from a JSON file with the list of clients
+pub fn load_api_clients(path: &str) -> Result<Vec<ApiClient>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| Error::ConfigInvalidApiClients(format!("{path}: {e}")))?;
+    let mut api_clients: Vec<ApiClient> = serde_json::from_str(&content)
+        .map_err(|e| Error::ConfigInvalidApiClients(format!("{path}: {e}")))?;
+
+    // the hashes are compared with the lowercase hex of utils::hash_token
+    for api_client in &mut api_clients {
+        for secret_hash in &mut api_client.secret_hashes {
+            *secret_hash = secret_hash.to_lowercase();
+        }
+    }
+
+    for api_client in &api_clients {
+        if api_client.name.is_empty()
+            || (api_client.secret_hashes.is_empty()
+                && api_client.certificate_common_names.is_empty())
+        {
+            return Err(Error::ConfigInvalidApiClients(format!(
+                "{path}: every client needs a name and at least one secret or certificate"
+            )));
+        }
+        if api_clients
+            .iter()
+            .filter(|c| c.name == api_client.name)
+            .count()
+            > 1
+        {
+            return Err(Error::ConfigInvalidApiClients(format!(
+                "{path}: duplicated client {}",
+                api_client.name
+            )));
+        }
+    }
+
+    Ok(api_clients)
+}
+
+/// Returns the client that stands for the callers sending the legacy GRPC_AUTH_VALUE secret, it
+/// can call every RPC except the admin ones
+pub fn legacy_api_client(secret: &str) -> ApiClient {
+    ApiClient {
+        name: LEGACY_API_CLIENT_NAME.to_string(),
+        secret_hashes: vec![utils::hash_token(secret)],
+        scopes: vec![ALL_SCOPES.to_string()],
+        certificate_common_names: Vec::new(),
+    }
+}
+
+// endregion: ApiClient
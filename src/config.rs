@@ -1,8 +1,23 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::Deserialize;
+use tonic::transport::ServerTlsConfig;
 use tracing::Level;
+use tracing_subscriber::EnvFilter;
 
+use crate::api_client::{self, ApiClient};
 use crate::error::{Error, Result};
-use std::{env, str::FromStr, sync::OnceLock};
+use crate::jwt::JwtKey;
+use crate::mailer::MailerKind;
+use crate::server::tls;
+use crate::tracing::LogFormat;
+use crate::utils::pepper::PasswordPepper;
+use std::{
+    collections::HashSet,
+    env, fs,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
 
 // region: Environment
 
@@ -28,6 +43,31 @@ impl FromStr for Environment {
 
 // endregion: Environment
 
+// region: Reloadable
+
+/// Config value that can be replaced while the server is running (see reload)
+pub struct Reloadable<T>(RwLock<Arc<T>>);
+
+impl<T> Reloadable<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(Arc::new(value)))
+    }
+
+    /// Returns the current value, it stays valid for the caller if it is replaced meanwhile
+    pub fn get(&self) -> Arc<T> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn set(&self, value: T) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(value);
+    }
+}
+
+// endregion: Reloadable
+
 // The config instance is initialized only once
 pub fn config() -> &'static Config {
     static INSTANCE: OnceLock<Config> = OnceLock::new();
@@ -38,14 +78,53 @@ pub fn config() -> &'static Config {
     })
 }
 
+/// Loads again the reloadable values (the API clients and the JWT signing keys) from their
+/// environment variables and files, the current values are kept if one of them is invalid
+pub fn reload() -> Result<()> {
+    let api_clients = get_api_clients()?;
+    let jwt_keys = get_jwt_keys()?;
+
+    config().API_CLIENTS.set(api_clients);
+    config().JWT_KEYS.set(jwt_keys);
+
+    Ok(())
+}
+
 #[allow(non_snake_case)]
 pub struct Config {
     // Tracing
     pub TRACING_MAX_LEVEL: tracing::Level,
+    // filter directives of the logged and exported spans and events (e.g. mandos=debug,sqlx=warn)
+    pub TRACING_FILTER: String,
+    // format of the log lines
+    pub LOG_FORMAT: LogFormat,
+    // OTLP gRPC endpoint of the collector the spans are exported to, None disables the export
+    pub OTLP_ENDPOINT: Option<String>,
+    // service.name of the exported spans
+    pub OTLP_SERVICE_NAME: String,
+
+    // gRPC server
+    // address the server listens on, port 0 picks a free port
+    pub GRPC_ADDR: SocketAddr,
+    // seconds the in-flight requests have to complete once the server is shutting down
+    pub SHUTDOWN_DRAIN_TIMEOUT: u64,
+    // seconds between the pings of the dependencies and how long a ping can take
+    pub HEALTH_CHECK_INTERVAL: u64,
+    pub HEALTH_CHECK_TIMEOUT: u64,
+    // address of the HTTP server with the Prometheus metrics
+    pub METRICS_ADDR: SocketAddr,
 
     // gRPC server auth credentials
-    pub GRPC_AUTH_KEY: String,
-    pub GRPC_AUTH_VALUE: String,
+    // registry of the services allowed to call the server, reloaded on SIGHUP
+    pub API_CLIENTS: Reloadable<Vec<ApiClient>>,
+    // metadata key of the legacy secret, sent by the callers authenticated as the legacy client
+    pub GRPC_AUTH_KEY: Option<String>,
+
+    // gRPC server TLS, None to serve plaintext
+    pub TLS: Option<ServerTlsConfig>,
+
+    // gRPC client info
+    pub TRUST_PROXY_HEADERS: bool,
 
     // Database
     pub DB_URL: String,
@@ -53,16 +132,263 @@ pub struct Config {
 
     // Session Database
     pub SESSION_DB_URL: String,
+
+    // Session lifetimes (seconds)
+    pub SESSION_LIFETIME: u64,
+    pub SESSION_REMEMBER_ME_LIFETIME: u64,
+    pub SESSION_IDLE_TIMEOUT: u64,
+
+    // Username policy
+    pub USERNAME_MIN_LENGTH: usize,
+    pub USERNAME_MAX_LENGTH: usize,
+
+    // Password policy
+    pub PASSWORD_MIN_LENGTH: usize,
+    pub PASSWORD_MAX_LENGTH: usize,
+    pub PASSWORD_REQUIRE_LOWERCASE: bool,
+    pub PASSWORD_REQUIRE_UPPERCASE: bool,
+    pub PASSWORD_REQUIRE_DIGIT: bool,
+    pub PASSWORD_REQUIRE_SYMBOL: bool,
+    // lowercase passwords loaded from PASSWORD_DENYLIST_FILE
+    pub PASSWORD_DENYLIST: HashSet<String>,
+
+    // Password hashing
+    // Argon2 variant and costs of the new hashes, the weaker hashes are upgraded on login
+    pub PASSWORD_HASH_ALGORITHM: argon2::Algorithm,
+    pub PASSWORD_HASH_PARAMS: argon2::Params,
+    // HMAC keys applied to the passwords before hashing them, the first one peppers the new hashes
+    // and the others still verify the older ones
+    pub PASSWORD_PEPPERS: Vec<PasswordPepper>,
+
+    // Login throttling
+    pub LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS: u64,
+    pub LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS: u64,
+    pub LOGIN_THROTTLE_IP_FREE_ATTEMPTS: u64,
+    pub LOGIN_THROTTLE_IP_LOCKOUT_ATTEMPTS: u64,
+    pub LOGIN_THROTTLE_BASE_DELAY: u64,
+    pub LOGIN_THROTTLE_MAX_DELAY: u64,
+    pub LOGIN_LOCKOUT_DURATION: u64,
+    pub LOGIN_THROTTLE_WINDOW: u64,
+
+    // Email verification
+    pub EMAIL_VERIFICATION_EXPIRATION: u64,
+    // tell the users that log in with the right password that they have to verify their email,
+    // otherwise they get the same error as a wrong password
+    pub LOGIN_REVEAL_UNVERIFIED: bool,
+
+    // Password reset
+    pub PASSWORD_RESET_EXPIRATION: u64,
+
+    // Organizations
+    pub ORGANIZATION_INVITATION_EXPIRATION: u64,
+
+    // JWT access tokens
+    // signing keys, the first one signs the new tokens, all of them are published in the JWKS
+    // reloaded on SIGHUP
+    pub JWT_KEYS: Reloadable<Vec<JwtKey>>,
+    pub JWT_ISSUER: String,
+    pub JWT_ACCESS_TOKEN_LIFETIME: u64,
+
+    // Refresh tokens
+    // lifetime of a token family, rotating the tokens does not extend it
+    pub REFRESH_TOKEN_LIFETIME: u64,
+
+    // MFA
+    // key of the TOTP secrets, without it the MFA RPCs fail with FAILED_PRECONDITION
+    pub MFA_ENCRYPTION_KEY: Option<[u8; 32]>,
+    pub MFA_ISSUER: String,
+    pub MFA_CHALLENGE_EXPIRATION: u64,
+    pub MFA_RECOVERY_CODES_COUNT: usize,
+
+    // Mailer
+    pub MAILER_KIND: MailerKind,
+    pub MAILER_FROM: String,
+    pub MAILER_OUTBOX_DIR: String,
+}
+
+fn default_tracing_filter(env: &Environment) -> String {
+    match env {
+        Environment::Production => "info".to_string(),
+        Environment::Development | Environment::Test => "mandos=trace".to_string(),
+    }
+}
+
+fn default_log_format(env: &Environment) -> LogFormat {
+    match env {
+        Environment::Production => LogFormat::Json,
+        Environment::Development | Environment::Test => LogFormat::Text,
+    }
+}
+
+fn default_otlp_service_name() -> String {
+    "mandos".to_string()
 }
 
 fn default_environment() -> Environment {
     Environment::Development
 }
 
+fn default_grpc_addr() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 50051))
+}
+
+fn default_shutdown_drain_timeout() -> u64 {
+    30
+}
+
+fn default_health_check_interval() -> u64 {
+    5
+}
+
+fn default_health_check_timeout() -> u64 {
+    2
+}
+
+fn default_metrics_addr() -> SocketAddr {
+    // the metrics are not authenticated, they are reachable from the host only unless set
+    SocketAddr::from(([127, 0, 0, 1], 9090))
+}
+
+fn default_trust_proxy_headers() -> bool {
+    false
+}
+
 fn default_db_max_connections() -> u32 {
     5
 }
 
+fn default_session_lifetime() -> u64 {
+    60 * 60 * 12
+}
+
+fn default_session_remember_me_lifetime() -> u64 {
+    60 * 60 * 24 * 30
+}
+
+fn default_session_idle_timeout() -> u64 {
+    60 * 60 * 24 * 7
+}
+
+fn default_username_min_length() -> usize {
+    3
+}
+
+fn default_username_max_length() -> usize {
+    32
+}
+
+fn default_password_min_length() -> usize {
+    8
+}
+
+fn default_password_max_length() -> usize {
+    128
+}
+
+fn default_password_require_character_class() -> bool {
+    false
+}
+
+fn default_password_hash_algorithm() -> argon2::Algorithm {
+    argon2::Algorithm::Argon2id
+}
+
+fn default_password_hash_m_cost() -> u32 {
+    argon2::Params::DEFAULT_M_COST
+}
+
+fn default_password_hash_t_cost() -> u32 {
+    argon2::Params::DEFAULT_T_COST
+}
+
+fn default_password_hash_p_cost() -> u32 {
+    argon2::Params::DEFAULT_P_COST
+}
+
+fn default_login_throttle_account_free_attempts() -> u64 {
+    3
+}
+
+fn default_login_throttle_account_lockout_attempts() -> u64 {
+    10
+}
+
+fn default_login_throttle_ip_free_attempts() -> u64 {
+    20
+}
+
+fn default_login_throttle_ip_lockout_attempts() -> u64 {
+    100
+}
+
+fn default_login_throttle_base_delay() -> u64 {
+    1
+}
+
+fn default_login_throttle_max_delay() -> u64 {
+    60
+}
+
+fn default_login_lockout_duration() -> u64 {
+    60 * 15
+}
+
+fn default_login_throttle_window() -> u64 {
+    60 * 15
+}
+
+fn default_email_verification_expiration() -> u64 {
+    60 * 60 * 24
+}
+
+fn default_login_reveal_unverified() -> bool {
+    false
+}
+
+fn default_password_reset_expiration() -> u64 {
+    60 * 15
+}
+
+fn default_organization_invitation_expiration() -> u64 {
+    60 * 60 * 24 * 7
+}
+
+fn default_jwt_issuer() -> String {
+    "mandos".to_string()
+}
+
+fn default_jwt_access_token_lifetime() -> u64 {
+    60 * 5
+}
+
+fn default_refresh_token_lifetime() -> u64 {
+    60 * 60 * 24 * 30
+}
+
+fn default_mfa_issuer() -> String {
+    "Mandos".to_string()
+}
+
+fn default_mfa_challenge_expiration() -> u64 {
+    60 * 5
+}
+
+fn default_mfa_recovery_codes_count() -> usize {
+    10
+}
+
+fn default_mailer_kind() -> MailerKind {
+    MailerKind::Log
+}
+
+fn default_mailer_from() -> String {
+    "no-reply@mandos.local".to_string()
+}
+
+fn default_mailer_outbox_dir() -> String {
+    "./outbox".to_string()
+}
+
 impl Config {
     fn load_from_env() -> Result<Config> {
         let environment = get_env("ENVIRONMENT").map_or_else(
@@ -71,9 +397,50 @@ impl Config {
         );
 
         let tracing_max_level = get_tracing_max_level(&environment)?;
+        // the RUST_LOG variable is used when TRACING_FILTER is not set
+        let tracing_filter = get_env("TRACING_FILTER")
+            .or_else(|_| get_env("RUST_LOG"))
+            .unwrap_or_else(|_| default_tracing_filter(&environment));
+        EnvFilter::try_new(&tracing_filter)
+            .map_err(|e| Error::ConfigInvalidTracingFilter(e.to_string()))?;
+        let log_format = get_env("LOG_FORMAT").map_or_else(
+            |_| Ok(default_log_format(&environment)),
+            |f| f.parse::<LogFormat>(),
+        )?;
+        let otlp_endpoint = get_env("OTLP_ENDPOINT").ok();
+        let otlp_service_name =
+            get_env("OTLP_SERVICE_NAME").unwrap_or_else(|_| default_otlp_service_name());
+
+        let grpc_addr = get_env("GRPC_ADDR").map_or_else(
+            |_| default_grpc_addr(),
+            |a| a.parse::<SocketAddr>().unwrap(),
+        );
+        let shutdown_drain_timeout = get_env("SHUTDOWN_DRAIN_TIMEOUT").map_or_else(
+            |_| default_shutdown_drain_timeout(),
+            |t| t.parse::<u64>().unwrap(),
+        );
+        let health_check_interval = get_env("HEALTH_CHECK_INTERVAL").map_or_else(
+            |_| default_health_check_interval(),
+            |i| i.parse::<u64>().unwrap(),
+        );
+        let health_check_timeout = get_env("HEALTH_CHECK_TIMEOUT").map_or_else(
+            |_| default_health_check_timeout(),
+            |t| t.parse::<u64>().unwrap(),
+        );
+        let metrics_addr = get_env("METRICS_ADDR").map_or_else(
+            |_| default_metrics_addr(),
+            |a| a.parse::<SocketAddr>().unwrap(),
+        );
 
-        let grpc_auth_key = get_env("GRPC_AUTH_KEY")?;
-        let grpc_auth_value = get_env("GRPC_AUTH_VALUE")?;
+        let api_clients = get_api_clients()?;
+        let grpc_auth_key = get_env("GRPC_AUTH_KEY").ok();
+
+        let tls = get_tls()?;
+
+        let trust_proxy_headers = get_env("TRUST_PROXY_HEADERS").map_or_else(
+            |_| default_trust_proxy_headers(),
+            |t| t.parse::<bool>().unwrap(),
+        );
 
         let db_url = get_db_url()?;
         let db_max_connections = get_env("DB_MAX_CONNECTIONS").map_or_else(
@@ -83,16 +450,223 @@ impl Config {
 
         let session_db_url = get_session_db_url()?;
 
+        let session_lifetime = get_env("SESSION_LIFETIME").map_or_else(
+            |_| default_session_lifetime(),
+            |l| l.parse::<u64>().unwrap(),
+        );
+        let session_remember_me_lifetime = get_env("SESSION_REMEMBER_ME_LIFETIME").map_or_else(
+            |_| default_session_remember_me_lifetime(),
+            |l| l.parse::<u64>().unwrap(),
+        );
+        let session_idle_timeout = get_env("SESSION_IDLE_TIMEOUT").map_or_else(
+            |_| default_session_idle_timeout(),
+            |t| t.parse::<u64>().unwrap(),
+        );
+
+        let username_min_length = get_env("USERNAME_MIN_LENGTH").map_or_else(
+            |_| default_username_min_length(),
+            |l| l.parse::<usize>().unwrap(),
+        );
+        let username_max_length = get_env("USERNAME_MAX_LENGTH").map_or_else(
+            |_| default_username_max_length(),
+            |l| l.parse::<usize>().unwrap(),
+        );
+
+        let password_min_length = get_env("PASSWORD_MIN_LENGTH").map_or_else(
+            |_| default_password_min_length(),
+            |l| l.parse::<usize>().unwrap(),
+        );
+        let password_max_length = get_env("PASSWORD_MAX_LENGTH").map_or_else(
+            |_| default_password_max_length(),
+            |l| l.parse::<usize>().unwrap(),
+        );
+        let password_require_lowercase = get_env("PASSWORD_REQUIRE_LOWERCASE").map_or_else(
+            |_| default_password_require_character_class(),
+            |r| r.parse::<bool>().unwrap(),
+        );
+        let password_require_uppercase = get_env("PASSWORD_REQUIRE_UPPERCASE").map_or_else(
+            |_| default_password_require_character_class(),
+            |r| r.parse::<bool>().unwrap(),
+        );
+        let password_require_digit = get_env("PASSWORD_REQUIRE_DIGIT").map_or_else(
+            |_| default_password_require_character_class(),
+            |r| r.parse::<bool>().unwrap(),
+        );
+        let password_require_symbol = get_env("PASSWORD_REQUIRE_SYMBOL").map_or_else(
+            |_| default_password_require_character_class(),
+            |r| r.parse::<bool>().unwrap(),
+        );
+        let password_denylist = get_password_denylist()?;
+
+        let password_hash_algorithm = get_env("PASSWORD_HASH_ALGORITHM").map_or_else(
+            |_| Ok(default_password_hash_algorithm()),
+            |a| {
+                a.parse::<argon2::Algorithm>()
+                    .map_err(|_| Error::ConfigInvalidPasswordHash(format!("algorithm {a}")))
+            },
+        )?;
+        let password_hash_params = get_password_hash_params()?;
+        let password_peppers = get_password_peppers()?;
+
+        let login_throttle_account_free_attempts = get_env("LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS")
+            .map_or_else(
+                |_| default_login_throttle_account_free_attempts(),
+                |a| a.parse::<u64>().unwrap(),
+            );
+        let login_throttle_account_lockout_attempts =
+            get_env("LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS").map_or_else(
+                |_| default_login_throttle_account_lockout_attempts(),
+                |a| a.parse::<u64>().unwrap(),
+            );
+        let login_throttle_ip_free_attempts = get_env("LOGIN_THROTTLE_IP_FREE_ATTEMPTS")
+            .map_or_else(
+                |_| default_login_throttle_ip_free_attempts(),
+                |a| a.parse::<u64>().unwrap(),
+            );
+        let login_throttle_ip_lockout_attempts = get_env("LOGIN_THROTTLE_IP_LOCKOUT_ATTEMPTS")
+            .map_or_else(
+                |_| default_login_throttle_ip_lockout_attempts(),
+                |a| a.parse::<u64>().unwrap(),
+            );
+        let login_throttle_base_delay = get_env("LOGIN_THROTTLE_BASE_DELAY").map_or_else(
+            |_| default_login_throttle_base_delay(),
+            |d| d.parse::<u64>().unwrap(),
+        );
+        let login_throttle_max_delay = get_env("LOGIN_THROTTLE_MAX_DELAY").map_or_else(
+            |_| default_login_throttle_max_delay(),
+            |d| d.parse::<u64>().unwrap(),
+        );
+        let login_lockout_duration = get_env("LOGIN_LOCKOUT_DURATION").map_or_else(
+            |_| default_login_lockout_duration(),
+            |d| d.parse::<u64>().unwrap(),
+        );
+        let login_throttle_window = get_env("LOGIN_THROTTLE_WINDOW").map_or_else(
+            |_| default_login_throttle_window(),
+            |w| w.parse::<u64>().unwrap(),
+        );
+
+        let email_verification_expiration = get_env("EMAIL_VERIFICATION_EXPIRATION").map_or_else(
+            |_| default_email_verification_expiration(),
+            |e| e.parse::<u64>().unwrap(),
+        );
+        let login_reveal_unverified = get_env("LOGIN_REVEAL_UNVERIFIED").map_or_else(
+            |_| default_login_reveal_unverified(),
+            |r| r.parse::<bool>().unwrap(),
+        );
+
+        let password_reset_expiration = get_env("PASSWORD_RESET_EXPIRATION").map_or_else(
+            |_| default_password_reset_expiration(),
+            |e| e.parse::<u64>().unwrap(),
+        );
+
+        let organization_invitation_expiration = get_env("ORGANIZATION_INVITATION_EXPIRATION")
+            .map_or_else(
+                |_| default_organization_invitation_expiration(),
+                |e| e.parse::<u64>().unwrap(),
+            );
+
+        let jwt_keys = get_jwt_keys()?;
+        let jwt_issuer = get_env("JWT_ISSUER").unwrap_or_else(|_| default_jwt_issuer());
+        let jwt_access_token_lifetime = get_env("JWT_ACCESS_TOKEN_LIFETIME").map_or_else(
+            |_| default_jwt_access_token_lifetime(),
+            |l| l.parse::<u64>().unwrap(),
+        );
+
+        let refresh_token_lifetime = get_env("REFRESH_TOKEN_LIFETIME").map_or_else(
+            |_| default_refresh_token_lifetime(),
+            |l| l.parse::<u64>().unwrap(),
+        );
+
+        let mfa_encryption_key = get_mfa_encryption_key()?;
+        let mfa_issuer = get_env("MFA_ISSUER").unwrap_or_else(|_| default_mfa_issuer());
+        let mfa_challenge_expiration = get_env("MFA_CHALLENGE_EXPIRATION").map_or_else(
+            |_| default_mfa_challenge_expiration(),
+            |e| e.parse::<u64>().unwrap(),
+        );
+        let mfa_recovery_codes_count = get_env("MFA_RECOVERY_CODES_COUNT").map_or_else(
+            |_| default_mfa_recovery_codes_count(),
+            |c| c.parse::<usize>().unwrap(),
+        );
+
+        let mailer_kind = get_env("MAILER_KIND")
+            .map_or_else(|_| Ok(default_mailer_kind()), |m| m.parse::<MailerKind>())?;
+        let mailer_from = get_env("MAILER_FROM").unwrap_or_else(|_| default_mailer_from());
+        let mailer_outbox_dir =
+            get_env("MAILER_OUTBOX_DIR").unwrap_or_else(|_| default_mailer_outbox_dir());
+
         Ok(Config {
             TRACING_MAX_LEVEL: tracing_max_level,
+            TRACING_FILTER: tracing_filter,
+            LOG_FORMAT: log_format,
+            OTLP_ENDPOINT: otlp_endpoint,
+            OTLP_SERVICE_NAME: otlp_service_name,
+
+            GRPC_ADDR: grpc_addr,
+            SHUTDOWN_DRAIN_TIMEOUT: shutdown_drain_timeout,
+            HEALTH_CHECK_INTERVAL: health_check_interval,
+            HEALTH_CHECK_TIMEOUT: health_check_timeout,
+            METRICS_ADDR: metrics_addr,
 
+            API_CLIENTS: Reloadable::new(api_clients),
             GRPC_AUTH_KEY: grpc_auth_key,
-            GRPC_AUTH_VALUE: grpc_auth_value,
+
+            TLS: tls,
+
+            TRUST_PROXY_HEADERS: trust_proxy_headers,
 
             DB_URL: db_url,
             DB_MAX_CONNECTIONS: db_max_connections,
 
             SESSION_DB_URL: session_db_url,
+
+            SESSION_LIFETIME: session_lifetime,
+            SESSION_REMEMBER_ME_LIFETIME: session_remember_me_lifetime,
+            SESSION_IDLE_TIMEOUT: session_idle_timeout,
+
+            USERNAME_MIN_LENGTH: username_min_length,
+            USERNAME_MAX_LENGTH: username_max_length,
+            PASSWORD_MIN_LENGTH: password_min_length,
+            PASSWORD_MAX_LENGTH: password_max_length,
+            PASSWORD_REQUIRE_LOWERCASE: password_require_lowercase,
+            PASSWORD_REQUIRE_UPPERCASE: password_require_uppercase,
+            PASSWORD_REQUIRE_DIGIT: password_require_digit,
+            PASSWORD_REQUIRE_SYMBOL: password_require_symbol,
+            PASSWORD_DENYLIST: password_denylist,
+
+            PASSWORD_HASH_ALGORITHM: password_hash_algorithm,
+            PASSWORD_HASH_PARAMS: password_hash_params,
+            PASSWORD_PEPPERS: password_peppers,
+
+            LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS: login_throttle_account_free_attempts,
+            LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS: login_throttle_account_lockout_attempts,
+            LOGIN_THROTTLE_IP_FREE_ATTEMPTS: login_throttle_ip_free_attempts,
+            LOGIN_THROTTLE_IP_LOCKOUT_ATTEMPTS: login_throttle_ip_lockout_attempts,
+            LOGIN_THROTTLE_BASE_DELAY: login_throttle_base_delay,
+            LOGIN_THROTTLE_MAX_DELAY: login_throttle_max_delay,
+            LOGIN_LOCKOUT_DURATION: login_lockout_duration,
+            LOGIN_THROTTLE_WINDOW: login_throttle_window,
+
+            EMAIL_VERIFICATION_EXPIRATION: email_verification_expiration,
+            LOGIN_REVEAL_UNVERIFIED: login_reveal_unverified,
+
+            PASSWORD_RESET_EXPIRATION: password_reset_expiration,
+
+            ORGANIZATION_INVITATION_EXPIRATION: organization_invitation_expiration,
+
+            JWT_KEYS: Reloadable::new(jwt_keys),
+            JWT_ISSUER: jwt_issuer,
+            JWT_ACCESS_TOKEN_LIFETIME: jwt_access_token_lifetime,
+
+            REFRESH_TOKEN_LIFETIME: refresh_token_lifetime,
+
+            MFA_ENCRYPTION_KEY: mfa_encryption_key,
+            MFA_ISSUER: mfa_issuer,
+            MFA_CHALLENGE_EXPIRATION: mfa_challenge_expiration,
+            MFA_RECOVERY_CODES_COUNT: mfa_recovery_codes_count,
+
+            MAILER_KIND: mailer_kind,
+            MAILER_FROM: mailer_from,
+            MAILER_OUTBOX_DIR: mailer_outbox_dir,
         })
     }
 }
@@ -101,6 +675,32 @@ fn get_env(name: &'static str) -> Result<String> {
     env::var(name).map_err(|_| Error::ConfigMissingEnv(name))
 }
 
+fn get_api_clients() -> Result<Vec<ApiClient>> {
+    // the legacy GRPC_AUTH_KEY and GRPC_AUTH_VALUE are mapped to a client, without them the
+    // registry file is required
+    let legacy_api_client = match (get_env("GRPC_AUTH_KEY"), get_env("GRPC_AUTH_VALUE")) {
+        (Ok(_), Ok(secret)) => Some(api_client::legacy_api_client(&secret)),
+        _ => None,
+    };
+    let mut api_clients = match get_env("API_CLIENTS_FILE") {
+        Ok(path) => api_client::load_api_clients(&path)?,
+        Err(_) if legacy_api_client.is_some() => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    if let Some(legacy_api_client) = legacy_api_client {
+        if api_clients.iter().any(|c| c.name == legacy_api_client.name) {
+            return Err(Error::ConfigInvalidApiClients(format!(
+                "{}: the name is reserved to the GRPC_AUTH_KEY client",
+                legacy_api_client.name
+            )));
+        }
+        api_clients.push(legacy_api_client);
+    }
+
+    Ok(api_clients)
+}
+
 fn get_tracing_max_level(env: &Environment) -> Result<Level> {
     match env {
         Environment::Test => Ok(tracing::Level::TRACE),
@@ -121,6 +721,139 @@ fn get_db_url() -> Result<String> {
     ))
 }
 
+fn get_password_denylist() -> Result<HashSet<String>> {
+    // the denylist is optional
+    let Ok(path) = get_env("PASSWORD_DENYLIST_FILE") else {
+        return Ok(HashSet::new());
+    };
+
+    // one password per line, empty lines and lines starting with # are ignored
+    let content = fs::read_to_string(&path)
+        .map_err(|e| Error::ConfigInvalidPasswordDenylist(format!("{path}: {e}")))?;
+    let denylist = content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.to_lowercase())
+        .collect();
+
+    Ok(denylist)
+}
+
+fn get_password_hash_params() -> Result<argon2::Params> {
+    // memory in KiB, number of passes and degree of parallelism
+    let m_cost = get_env("PASSWORD_HASH_M_COST").map_or_else(
+        |_| default_password_hash_m_cost(),
+        |m| m.parse::<u32>().unwrap(),
+    );
+    let t_cost = get_env("PASSWORD_HASH_T_COST").map_or_else(
+        |_| default_password_hash_t_cost(),
+        |t| t.parse::<u32>().unwrap(),
+    );
+    let p_cost = get_env("PASSWORD_HASH_P_COST").map_or_else(
+        |_| default_password_hash_p_cost(),
+        |p| p.parse::<u32>().unwrap(),
+    );
+
+    argon2::Params::new(m_cost, t_cost, p_cost, None)
+        .map_err(|e| Error::ConfigInvalidPasswordHash(e.to_string()))
+}
+
+fn get_password_peppers() -> Result<Vec<PasswordPepper>> {
+    // the peppers are optional, without them the passwords are hashed as they are
+    // comma separated list of version=key, followed by the lines of the key file
+    let mut entries: Vec<String> = get_env("PASSWORD_PEPPERS")
+        .map(|p| p.split(',').map(|e| e.to_string()).collect())
+        .unwrap_or_default();
+    if let Ok(path) = get_env("PASSWORD_PEPPERS_FILE") {
+        // one version=key per line, empty lines and lines starting with # are ignored
+        let content = fs::read_to_string(&path)
+            .map_err(|e| Error::ConfigInvalidPasswordPepper(format!("{path}: {e}")))?;
+        entries.extend(content.lines().map(|l| l.to_string()));
+    }
+
+    let mut peppers: Vec<PasswordPepper> = Vec::new();
+    for entry in entries
+        .iter()
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty() && !e.starts_with('#'))
+    {
+        let pepper = PasswordPepper::parse(entry)?;
+        if peppers.iter().any(|p| p.version == pepper.version) {
+            return Err(Error::ConfigInvalidPasswordPepper(format!(
+                "{}: duplicate version",
+                pepper.version
+            )));
+        }
+        peppers.push(pepper);
+    }
+
+    Ok(peppers)
+}
+
+fn get_jwt_keys() -> Result<Vec<JwtKey>> {
+    // the keys are optional, without them no access token is issued
+    // comma separated list of kid=path, followed by the lines of the keys file
+    let mut entries: Vec<String> = get_env("JWT_KEYS")
+        .map(|k| k.split(',').map(|e| e.to_string()).collect())
+        .unwrap_or_default();
+    if let Ok(path) = get_env("JWT_KEYS_FILE") {
+        // one kid=path per line, empty lines and lines starting with # are ignored
+        let content = fs::read_to_string(&path)
+            .map_err(|e| Error::ConfigInvalidJwtKey(format!("{path}: {e}")))?;
+        entries.extend(content.lines().map(|l| l.to_string()));
+    }
+
+    entries
+        .iter()
+        .map(|k| k.trim())
+        .filter(|k| !k.is_empty() && !k.starts_with('#'))
+        .map(|k| {
+            let (kid, path) = k
+                .split_once('=')
+                .ok_or_else(|| Error::ConfigInvalidJwtKey(format!("{k}: expected kid=path")))?;
+            let pem = fs::read_to_string(path)
+                .map_err(|e| Error::ConfigInvalidJwtKey(format!("{path}: {e}")))?;
+
+            JwtKey::from_pem(kid.to_string(), &pem)
+        })
+        .collect()
+}
+
+fn get_tls() -> Result<Option<ServerTlsConfig>> {
+    // TLS is optional, without a certificate the server is plaintext
+    let cert_path = get_env("TLS_CERT_PATH").ok();
+    let key_path = get_env("TLS_KEY_PATH").ok();
+    let client_ca_path = get_env("TLS_CLIENT_CA_PATH").ok();
+
+    match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => Ok(Some(tls::load_server_tls_config(
+            &cert_path,
+            &key_path,
+            client_ca_path.as_deref(),
+        )?)),
+        (None, None) if client_ca_path.is_none() => Ok(None),
+        _ => Err(Error::ConfigInvalidTls(
+            "TLS_CERT_PATH and TLS_KEY_PATH are both required to enable TLS".to_string(),
+        )),
+    }
+}
+
+fn get_mfa_encryption_key() -> Result<Option<[u8; 32]>> {
+    // the key is optional, without it MFA can't be used
+    let Ok(key) = get_env("MFA_ENCRYPTION_KEY") else {
+        return Ok(None);
+    };
+
+    // the key is 32 random bytes base64 encoded
+    BASE64
+        .decode(key)
+        .ok()
+        .and_then(|k| k.try_into().ok())
+        .map(Some)
+        .ok_or(Error::ConfigInvalidMfaEncryptionKey)
+}
+
 fn get_session_db_url() -> Result<String> {
     let session_db_user = get_env("SESSION_DB_USER")?;
     let session_db_password = get_env("SESSION_DB_PASSWORD")?;
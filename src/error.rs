@@ -8,6 +8,8 @@ use serde_with::{serde_as, DisplayFromStr};
 use sqlx::migrate::MigrateError;
 use strum_macros::AsRefStr;
 
+use crate::model::user_auth::password_policy::PasswordViolation;
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[serde_as]
@@ -20,6 +22,16 @@ pub enum Error {
     // Config errors
     ConfigMissingEnv(&'static str),
     ConfigInvalidEnvironment(String),
+    ConfigInvalidMailer(String),
+    ConfigInvalidMfaEncryptionKey,
+    ConfigInvalidPasswordDenylist(String),
+    ConfigInvalidPasswordHash(String),
+    ConfigInvalidPasswordPepper(String),
+    ConfigInvalidJwtKey(String),
+    ConfigInvalidApiClients(String),
+    ConfigInvalidTls(String),
+    ConfigInvalidTracingFilter(String),
+    ConfigInvalidLogFormat(String),
 
     // SQLx errors
     Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
@@ -31,14 +43,89 @@ pub enum Error {
     RedisCreatePool(#[serde_as(as = "DisplayFromStr")] CreatePoolError),
     RedisPool(#[serde_as(as = "DisplayFromStr")] PoolError),
 
+    // Serde errors
+    SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
+
     // Argon2 errors
     Argon2Error(#[serde_as(as = "DisplayFromStr")] argon2::Error),
     Argon2ErrorPasswordHash(#[serde_as(as = "DisplayFromStr")] password_hash::Error),
+    PasswordPepperNotFound(String),
+
+    // Request errors
+    FieldsEmpty,
+    InvalidId(&'static str),
+    UserIdMismatch,
+    EntityNotFound(&'static str),
+
+    // API client errors
+    ApiClientUnauthenticated,
+    ApiClientNotAllowed { client: String, rpc_method: String },
 
     // UserAuth errors
     UsernameNotSet,
     EmailNotSet,
     PasswordNotSet,
+    UsernameInvalid(String),
+    EmailInvalid(String),
+    PasswordPolicy(Vec<PasswordViolation>),
+
+    // Session errors
+    SessionNotFound,
+
+    // Login errors
+    InvalidCredentials,
+    EmailNotVerified,
+    AccountInactive,
+
+    // Token errors
+    InvalidToken,
+    RefreshTokenReused,
+    RefreshTokenInvalid,
+
+    // Throttle errors
+    LoginThrottled { retry_after: u64 },
+
+    // Rbac errors
+    RoleNameInvalid,
+    PermissionNameInvalid,
+    PermissionNotGranted,
+    RoleNotAssigned,
+
+    // Organization errors
+    OrganizationNameInvalid,
+    OrganizationRoleInvalid,
+    NotOrganizationMember,
+    OrganizationRoleNotAllowed,
+    InvitationEmailMismatch,
+    LastOrganizationOwner,
+
+    // JWT errors
+    JwtNotConfigured,
+    Jwt(String),
+
+    // Encryption errors
+    Encryption(String),
+
+    // MFA errors
+    MfaNotConfigured,
+    Totp(String),
+    MfaChallengeInvalid,
+    MfaCodeInvalid,
+    MfaNotEnabled,
+    MfaAlreadyEnabled,
+    MfaEnrollmentNotStarted,
+
+    // Mailer errors
+    Mailer(String),
+
+    // Metrics errors
+    Metrics(String),
+
+    // Tracing errors
+    Tracing(String),
+
+    // Command errors
+    UnknownCommand(String),
 
     // Generic errors
     Service(String),
@@ -55,6 +142,30 @@ impl From<AddrParseError> for Error {
     }
 }
 
+impl From<uuid::Error> for Error {
+    fn from(e: uuid::Error) -> Self {
+        Self::Service(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Service(e.to_string())
+    }
+}
+
+impl From<prometheus::Error> for Error {
+    fn from(e: prometheus::Error) -> Self {
+        Self::Metrics(e.to_string())
+    }
+}
+
+impl From<opentelemetry::trace::TraceError> for Error {
+    fn from(e: opentelemetry::trace::TraceError) -> Self {
+        Self::Tracing(e.to_string())
+    }
+}
+
 impl From<tonic::transport::Error> for Error {
     fn from(e: tonic::transport::Error) -> Self {
         Self::TonicTransport(e)
@@ -91,6 +202,12 @@ impl From<PoolError> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::SerdeJson(e)
+    }
+}
+
 impl From<argon2::Error> for Error {
     fn from(e: argon2::Error) -> Self {
         Self::Argon2Error(e)
@@ -107,7 +224,20 @@ impl From<password_hash::Error> for Error {
 
 impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(fmt, "{self:?}")
+        match self {
+            // the errors of the libraries can contain queries, keys and values of the rows or
+            // connection strings, only their kind is shown (Debug keeps the details)
+            Self::TonicTransport(_)
+            | Self::Sqlx(_)
+            | Self::SqlxMigrate(_)
+            | Self::Redis(_)
+            | Self::RedisCreatePool(_)
+            | Self::RedisPool(_)
+            | Self::SerdeJson(_)
+            | Self::Argon2Error(_)
+            | Self::Argon2ErrorPasswordHash(_) => write!(fmt, "{}", self.as_ref()),
+            _ => write!(fmt, "{self:?}"),
+        }
     }
 }
 
@@ -0,0 +1,178 @@
+//! Signed JWT access tokens, issued alongside the opaque sessions so that services can verify
+//! requests without calling Mandos
+//! Tokens are signed with the first configured key, all the configured keys are published with
+//! GetJwks so that tokens signed with a retired key stay valid until they expire
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use chrono::Utc;
+use ed25519_dalek::SigningKey;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey, pkcs8::DecodePrivateKey, traits::PublicKeyParts, RsaPrivateKey,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+    utils,
+};
+
+// region: JwtKey
+
+/// The public part of a key, as published in the JWKS (RFC 7517)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: String,
+    pub alg: String,
+    // Ed25519 keys
+    pub crv: String,
+    pub x: String,
+    // RSA keys
+    pub n: String,
+    pub e: String,
+}
+
+/// A signing key loaded from a PEM file (PKCS#8 Ed25519 or RSA, or PKCS#1 RSA)
+#[derive(Clone)]
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub jwk: Jwk,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl JwtKey {
+    pub fn from_pem(kid: String, pem: &str) -> Result<JwtKey> {
+        let invalid_key = |e: String| Error::ConfigInvalidJwtKey(format!("{kid}: {e}"));
+
+        if let Ok(signing_key) = SigningKey::from_pkcs8_pem(pem) {
+            let x = BASE64_URL.encode(signing_key.verifying_key().as_bytes());
+
+            return Ok(JwtKey {
+                algorithm: Algorithm::EdDSA,
+                encoding_key: EncodingKey::from_ed_pem(pem.as_bytes())
+                    .map_err(|e| invalid_key(e.to_string()))?,
+                decoding_key: DecodingKey::from_ed_components(&x)
+                    .map_err(|e| invalid_key(e.to_string()))?,
+                jwk: Jwk {
+                    kty: "OKP".to_string(),
+                    kid: kid.clone(),
+                    alg: "EdDSA".to_string(),
+                    crv: "Ed25519".to_string(),
+                    x,
+                    n: "".to_string(),
+                    e: "".to_string(),
+                },
+                kid,
+            });
+        }
+
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|_| invalid_key("not an Ed25519 or RSA private key".to_string()))?;
+        let n = BASE64_URL.encode(private_key.n().to_bytes_be());
+        let e = BASE64_URL.encode(private_key.e().to_bytes_be());
+
+        Ok(JwtKey {
+            algorithm: Algorithm::RS256,
+            encoding_key: EncodingKey::from_rsa_pem(pem.as_bytes())
+                .map_err(|e| invalid_key(e.to_string()))?,
+            decoding_key: DecodingKey::from_rsa_components(&n, &e)
+                .map_err(|e| invalid_key(e.to_string()))?,
+            jwk: Jwk {
+                kty: "RSA".to_string(),
+                kid: kid.clone(),
+                alg: "RS256".to_string(),
+                crv: "".to_string(),
+                x: "".to_string(),
+                n,
+                e,
+            },
+            kid,
+        })
+    }
+}
+
+// endregion: JwtKey
+
+// region: AccessToken
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccessTokenClaims {
+    pub iss: String,
+    // user id
+    pub sub: String,
+    // session_sid of the session id, the token must not leak the session it was issued for
+    pub sid: String,
+    pub roles: Vec<String>,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Returns the sid claim of the access tokens issued for a session (SHA-256 hex of its id), the
+/// sessions listed by ListSessions carry it too
+pub fn session_sid(session_id: &str) -> String {
+    utils::hash_token(session_id)
+}
+
+/// Returns an access token for the session, signed with the current key, and its expiration
+pub fn issue_access_token(
+    user_id: String,
+    session_id: &str,
+    roles: Vec<String>,
+) -> Result<(String, i64)> {
+    let keys = config().JWT_KEYS.get();
+    let key = keys.first().ok_or(Error::JwtNotConfigured)?;
+
+    let now = Utc::now().timestamp();
+    let claims = AccessTokenClaims {
+        iss: config().JWT_ISSUER.clone(),
+        sub: user_id,
+        sid: session_sid(session_id),
+        roles,
+        iat: now,
+        exp: now + config().JWT_ACCESS_TOKEN_LIFETIME as i64,
+    };
+
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+
+    let token = jsonwebtoken::encode(&header, &claims, &key.encoding_key)
+        .map_err(|e| Error::Jwt(e.to_string()))?;
+
+    Ok((token, claims.exp))
+}
+
+/// Verifies the signature (with the key of its kid), the issuer and the expiration of a token
+pub fn verify_access_token(token: &str) -> Result<AccessTokenClaims> {
+    let header = jsonwebtoken::decode_header(token).map_err(|e| Error::Jwt(e.to_string()))?;
+    let keys = config().JWT_KEYS.get();
+    let key = keys
+        .iter()
+        .find(|k| header.kid.as_ref() == Some(&k.kid))
+        .ok_or_else(|| Error::Jwt("unknown kid".to_string()))?;
+
+    let mut validation = Validation::new(key.algorithm);
+    validation.set_issuer(&[&config().JWT_ISSUER]);
+
+    let token_data =
+        jsonwebtoken::decode::<AccessTokenClaims>(token, &key.decoding_key, &validation)
+            .map_err(|e| Error::Jwt(e.to_string()))?;
+
+    Ok(token_data.claims)
+}
+
+/// Returns the public keys that can verify the access tokens
+pub fn jwks() -> Vec<Jwk> {
+    config()
+        .JWT_KEYS
+        .get()
+        .iter()
+        .map(|k| k.jwk.clone())
+        .collect()
+}
+
+// endregion: AccessToken
@@ -1,35 +1,67 @@
+pub mod api_client;
 pub mod config;
 pub mod error;
-pub mod mandos_auth;
+pub mod jwt;
+pub mod mailer;
+pub mod metrics;
 pub mod model;
 pub mod server;
 pub mod tracing;
 pub mod utils;
 pub mod utils_tests;
 
-use model::ModelManager;
+use ::tracing::info;
+use model::{session, ModelManager};
 
 use crate::error::Result;
 
-mod mandos_auth_proto {
+// the gRPC code is generated in OUT_DIR by the build script, it is included only once so that
+// there is a single version of each type
+pub mod mandos_auth {
     #![allow(non_snake_case)]
-    include!("mandos_auth.rs");
+    tonic::include_proto!("mandos_auth");
 
     pub(crate) const FILE_DESCRIPTOR_SET: &[u8] =
         tonic::include_file_descriptor_set!("mandos_auth_descriptor");
 }
 
+pub(crate) use mandos_auth as mandos_auth_proto;
+
 pub async fn run() -> Result<()> {
     utils::print_app_name("Mandos", 30, 2);
 
     // Initialize tracing
-    tracing::initialize();
+    tracing::initialize()?;
 
     // Initialize ModelManager
     let model_manager = ModelManager::new().await?;
 
     // start gRPC server
-    server::start(model_manager).await?;
+    let res = server::start(model_manager).await;
+
+    // the spans of the last requests are exported before exiting, off the runtime threads since
+    // the export runs on them
+    let _ = tokio::task::spawn_blocking(tracing::shutdown).await;
+
+    res
+}
+
+/// Adds the sessions created before the user's session indexes to the index of their user
+/// It walks all the keys of the session db, so it is run once by hand after the upgrade
+/// (```mandos index-legacy-sessions```) instead of at every startup
+pub async fn index_legacy_sessions() -> Result<()> {
+    // Initialize tracing
+    tracing::initialize()?;
+
+    // Initialize ModelManager
+    let model_manager = ModelManager::new().await?;
+
+    let res = session::crud::index_legacy_sessions(model_manager.session_db().clone()).await;
+    if let Ok(indexed) = res {
+        info!("Indexed {indexed} legacy sessions");
+    }
+
+    let _ = tokio::task::spawn_blocking(tracing::shutdown).await;
 
-    Ok(())
+    res.map(|_| ())
 }
@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+// region: Mail
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    pub fn new(to: String, subject: String, body: String) -> Self {
+        Self {
+            from: config().MAILER_FROM.clone(),
+            to,
+            subject,
+            body,
+        }
+    }
+
+    /// Mail sent after registration (or on request) to confirm the email address
+    /// The token is on the last line of the body
+    pub fn email_verification(to: String, token: String) -> Self {
+        Self::new(
+            to,
+            "Confirm your email".to_string(),
+            format!("Use the following token to confirm your email address:\n{token}"),
+        )
+    }
+
+    /// Mail sent when the user asks to reset the password
+    /// The token is on the last line of the body
+    pub fn password_reset(to: String, token: String) -> Self {
+        Self::new(
+            to,
+            "Reset your password".to_string(),
+            format!("Use the following token to reset your password:\n{token}"),
+        )
+    }
+
+    /// Mail sent to invite someone to join an organization
+    /// The token is on the last line of the body
+    pub fn organization_invitation(to: String, organization_name: String, token: String) -> Self {
+        Self::new(
+            to,
+            format!("Join {organization_name}"),
+            format!(
+                "You have been invited to join {organization_name}, use the following token to accept the invitation:\n{token}"
+            ),
+        )
+    }
+}
+
+// endregion: Mail
+
+// region: Mailer
+
+#[derive(Debug, PartialEq)]
+pub enum MailerKind {
+    Log,
+    File,
+}
+
+impl FromStr for MailerKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "log" => Ok(MailerKind::Log),
+            "file" => Ok(MailerKind::File),
+            _ => Err(Error::ConfigInvalidMailer(s.to_string())),
+        }
+    }
+}
+
+#[tonic::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// Returns the mailer selected in the config
+pub fn new_mailer() -> Arc<dyn Mailer> {
+    match config().MAILER_KIND {
+        MailerKind::Log => Arc::new(LogMailer),
+        MailerKind::File => Arc::new(FileMailer::new(config().MAILER_OUTBOX_DIR.clone())),
+    }
+}
+
+/// Only logs the mails, useful for development
+pub struct LogMailer;
+
+#[tonic::async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        info!("Mail to: {} - Subject: {}", mail.to, mail.subject);
+        debug!("Mail body: {}", mail.body);
+
+        Ok(())
+    }
+}
+
+/// Writes every mail as a json file inside the outbox directory
+pub struct FileMailer {
+    outbox_dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(outbox_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            outbox_dir: outbox_dir.into(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        fs::create_dir_all(&self.outbox_dir).map_err(|e| Error::Mailer(e.to_string()))?;
+
+        let content =
+            serde_json::to_string_pretty(&mail).map_err(|e| Error::Mailer(e.to_string()))?;
+        let path = self.outbox_dir.join(format!("{}.json", Uuid::new_v4()));
+        fs::write(path, content).map_err(|e| Error::Mailer(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Keeps every mail in memory, used by the tests to read the outbox
+#[derive(Clone, Default)]
+pub struct MemoryMailer {
+    outbox: Arc<Mutex<Vec<Mail>>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all the mails sent to the given address (oldest first)
+    pub fn mails_to(&self, to: &str) -> Vec<Mail> {
+        self.outbox
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.to == to)
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.outbox.lock().unwrap().clear();
+    }
+}
+
+#[tonic::async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        self.outbox.lock().unwrap().push(mail);
+
+        Ok(())
+    }
+}
+
+// endregion: Mailer
@@ -1,7 +1,12 @@
-use mandos::error::Result;
-use mandos::run;
+use mandos::error::{Error, Result};
+use mandos::{index_legacy_sessions, run};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    run().await
+    // the one-shot admin commands run instead of the server
+    match std::env::args().nth(1).as_deref() {
+        None => run().await,
+        Some("index-legacy-sessions") => index_legacy_sessions().await,
+        Some(command) => Err(Error::UnknownCommand(command.to_string())),
+    }
 }
@@ -0,0 +1,278 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Response, StatusCode,
+};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use strum_macros::AsRefStr;
+use tokio::net::TcpListener;
+use tracing::warn;
+
+use crate::{
+    error::{Error, Result},
+    model::{session, ModelManager},
+};
+
+/// Why a login attempt failed, used as label of the failed logins
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum LoginFailure {
+    Throttled,
+    UnknownUser,
+    Blocked,
+    InvalidPassword,
+    InvalidChallenge,
+    InvalidCode,
+}
+
+/// How a login succeeded, used as label of the successful logins
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum LoginMethod {
+    Password,
+    Mfa,
+}
+
+/// What a password hash has been computed for, used as label of the hashing time
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum PasswordHashOperation {
+    Hash,
+    Verify,
+}
+
+pub fn metrics() -> &'static Metrics {
+    static INSTANCE: OnceLock<Metrics> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        Metrics::new().unwrap_or_else(|ex| panic!("Failed to register the metrics: {ex:?}"))
+    })
+}
+
+// region: Metrics
+
+pub struct Metrics {
+    registry: Registry,
+
+    rpc_requests: IntCounterVec,
+    rpc_duration: HistogramVec,
+    login_successes: IntCounterVec,
+    login_failures: IntCounterVec,
+    password_hash_duration: HistogramVec,
+
+    // gauges refreshed at every scrape
+    active_sessions: IntGauge,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("mandos".to_string()), None)?;
+
+        let rpc_requests = IntCounterVec::new(
+            Opts::new("rpc_requests_total", "Number of gRPC requests"),
+            &["service", "method", "code"],
+        )?;
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_duration_seconds", "Latency of the gRPC requests"),
+            &["service", "method", "code"],
+        )?;
+        let login_successes = IntCounterVec::new(
+            Opts::new("login_successes_total", "Number of successful logins"),
+            &["method"],
+        )?;
+        let login_failures = IntCounterVec::new(
+            Opts::new("login_failures_total", "Number of failed logins"),
+            &["reason"],
+        )?;
+        let password_hash_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "password_hash_duration_seconds",
+                "Time spent computing argon2 password hashes",
+            )
+            .buckets(exponential_buckets(0.005, 2.0, 10)?),
+            &["operation"],
+        )?;
+        let active_sessions = IntGauge::new("active_sessions", "Number of active sessions")?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("pool_connections", "Connections of the DB and Redis pools"),
+            &["pool", "state"],
+        )?;
+        let pool_max_connections = IntGaugeVec::new(
+            Opts::new(
+                "pool_max_connections",
+                "Maximum connections of the DB and Redis pools",
+            ),
+            &["pool"],
+        )?;
+
+        registry.register(Box::new(rpc_requests.clone()))?;
+        registry.register(Box::new(rpc_duration.clone()))?;
+        registry.register(Box::new(login_successes.clone()))?;
+        registry.register(Box::new(login_failures.clone()))?;
+        registry.register(Box::new(password_hash_duration.clone()))?;
+        registry.register(Box::new(active_sessions.clone()))?;
+        registry.register(Box::new(pool_connections.clone()))?;
+        registry.register(Box::new(pool_max_connections.clone()))?;
+
+        Ok(Self {
+            registry,
+            rpc_requests,
+            rpc_duration,
+            login_successes,
+            login_failures,
+            password_hash_duration,
+            active_sessions,
+            pool_connections,
+            pool_max_connections,
+        })
+    }
+
+    /// Records a completed gRPC request
+    pub fn observe_rpc(&self, service: &str, method: &str, code: &str, duration: Duration) {
+        let labels = [service, method, code];
+
+        self.rpc_requests.with_label_values(&labels).inc();
+        self.rpc_duration
+            .with_label_values(&labels)
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn login_succeeded(&self, method: LoginMethod) {
+        self.login_successes
+            .with_label_values(&[method.as_ref()])
+            .inc();
+    }
+
+    pub fn login_failed(&self, reason: LoginFailure) {
+        self.login_failures
+            .with_label_values(&[reason.as_ref()])
+            .inc();
+    }
+
+    /// Runs the argon2 computation and records how long it took
+    pub fn time_password_hash<T>(
+        &self,
+        operation: PasswordHashOperation,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let res = f();
+        self.password_hash_duration
+            .with_label_values(&[operation.as_ref()])
+            .observe(start.elapsed().as_secs_f64());
+
+        res
+    }
+
+    /// Refreshes the gauges read from the databases
+    async fn refresh_gauges(&self, model_manager: &ModelManager) -> Result<()> {
+        // postgres pool
+        let db = model_manager.db();
+        let db_idle = db.num_idle() as i64;
+        self.set_pool_connections("postgres", db.size() as i64 - db_idle, db_idle);
+        self.pool_max_connections
+            .with_label_values(&["postgres"])
+            .set(db.options().get_max_connections() as i64);
+
+        // redis pool, more users than connections makes the available ones negative
+        let session_db_status = model_manager.session_db().status();
+        let session_db_idle = session_db_status.available.max(0) as i64;
+        self.set_pool_connections(
+            "redis",
+            session_db_status.size as i64 - session_db_idle,
+            session_db_idle,
+        );
+        self.pool_max_connections
+            .with_label_values(&["redis"])
+            .set(session_db_status.max_size as i64);
+
+        let active_sessions =
+            session::crud::count_active(model_manager.session_db().clone()).await?;
+        self.active_sessions.set(active_sessions as i64);
+
+        Ok(())
+    }
+
+    fn set_pool_connections(&self, pool: &str, in_use: i64, idle: i64) {
+        self.pool_connections
+            .with_label_values(&[pool, "in_use"])
+            .set(in_use);
+        self.pool_connections
+            .with_label_values(&[pool, "idle"])
+            .set(idle);
+    }
+
+    /// Returns all the metrics in the Prometheus text format
+    pub async fn encode(&self, model_manager: &ModelManager) -> Result<String> {
+        // the last values are still reported when the databases can't be read
+        if let Err(e) = self.refresh_gauges(model_manager).await {
+            warn!("Failed to refresh the metrics gauges: {e:?}");
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        String::from_utf8(buffer).map_err(|e| Error::Metrics(e.to_string()))
+    }
+}
+
+// endregion: Metrics
+
+/// Serves the metrics on GET /metrics until `shutdown` completes
+/// # Arguments
+/// * `listener` - The listener of the metrics HTTP port
+/// * `model_manager` - The model manager to read the gauges from
+/// * `shutdown` - The future that stops the server when it completes
+pub async fn serve(
+    listener: TcpListener,
+    model_manager: ModelManager,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let model_manager = model_manager.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let model_manager = model_manager.clone();
+                async move {
+                    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+                        return Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(StatusCode::NOT_FOUND)
+                                .body(Body::empty())
+                                .unwrap_or_default(),
+                        );
+                    }
+
+                    let response = match metrics().encode(&model_manager).await {
+                        Ok(body) => Response::builder()
+                            .header(CONTENT_TYPE, TextEncoder::new().format_type())
+                            .body(Body::from(body)),
+                        Err(e) => Response::builder()
+                            .status(StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(Body::from(format!("{e:?}"))),
+                    };
+                    Ok(response.unwrap_or_default())
+                }
+            }))
+        }
+    });
+
+    hyper::Server::from_tcp(listener.into_std()?)
+        .map_err(|e| Error::Metrics(e.to_string()))?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| Error::Metrics(e.to_string()))
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use strum_macros::AsRefStr;
+use uuid::Uuid;
+
+use super::iterable::{Iterable, IterableType};
+
+pub mod model_controller;
+
+/// The security relevant events recorded in the audit log
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum AuditEvent {
+    RecoveryCodeUsed,
+    RefreshTokenReused,
+}
+
+// region: AuditLog
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub user_id: Uuid,
+    pub event: String,
+    pub ip: String,
+    pub user_agent: String,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            user_id: Uuid::nil(),
+            event: "".to_string(),
+            ip: "".to_string(),
+            user_agent: "".to_string(),
+        }
+    }
+}
+
+impl AuditLog {
+    pub fn new(
+        user_id: Uuid,
+        event: AuditEvent,
+        ip: Option<String>,
+        user_agent: Option<String>,
+    ) -> AuditLog {
+        AuditLog {
+            user_id,
+            event: event.as_ref().to_string(),
+            ip: ip.unwrap_or_default(),
+            user_agent: user_agent.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Iterable for AuditLog {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "user_id".to_string(),
+            "event".to_string(),
+            "ip".to_string(),
+            "user_agent".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::Uuid(self.user_id),
+            IterableType::String(self.event.to_string()),
+            IterableType::String(self.ip.to_string()),
+            IterableType::String(self.user_agent.to_string()),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: AuditLog
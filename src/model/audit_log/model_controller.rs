@@ -0,0 +1,53 @@
+use sqlx::FromRow;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::model::iterable::IterableType;
+use crate::model::{db, ModelManager};
+
+use super::AuditLog;
+
+const TABLE_NAME: &str = "audit_log";
+
+pub struct AuditLogBmc;
+
+impl AuditLogBmc {
+    // region: Db CRUD operations
+
+    pub async fn create(model_manager: &ModelManager, audit_log: AuditLog) -> Result<Uuid> {
+        info!(
+            "Audit: user {} - event {} - ip {}",
+            audit_log.user_id, audit_log.event, audit_log.ip
+        );
+
+        let res = db::crud::create(model_manager.db().clone(), TABLE_NAME, audit_log).await?;
+
+        let audit_log_created = AuditLog::from_row(&res)?;
+
+        Ok(audit_log_created.id)
+    }
+
+    pub async fn get_all_for_user(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+    ) -> Result<Vec<AuditLog>> {
+        let res = db::crud::get_all_by_field(
+            model_manager.db().clone(),
+            TABLE_NAME,
+            "user_id",
+            IterableType::Uuid(user_id),
+        )
+        .await?;
+
+        let mut audit_logs = Vec::new();
+        for audit_log in res {
+            let al = AuditLog::from_row(&audit_log)?;
+            audit_logs.push(al);
+        }
+
+        Ok(audit_logs)
+    }
+
+    // endregion: Db CRUD operations
+}
@@ -1,14 +1,32 @@
-use sqlx::{Execute, Postgres, QueryBuilder};
-use tracing::debug;
+use sqlx::{Postgres, QueryBuilder};
+use tracing::{debug, instrument};
 use uuid::Uuid;
 
 use crate::error::{Error, Result};
 use crate::model::iterable::{Iterable, IterableType};
 
-use super::{Db, DbRow};
+use super::{Db, DbRow, DbTx};
 
-// returns the created row
-pub async fn create<T>(db: Db, table_name: &str, struct_to_create: T) -> Result<DbRow>
+/// Binds the value to the query with the type of its variant
+fn push_bind_iterable(query_builder: &mut QueryBuilder<Postgres>, value: IterableType) {
+    match value {
+        IterableType::Uuid(v) => {
+            query_builder.push_bind(v);
+        }
+        IterableType::DateTime(v) => {
+            query_builder.push_bind(v);
+        }
+        IterableType::Bool(v) => {
+            query_builder.push_bind(v);
+        }
+        IterableType::String(v) => {
+            query_builder.push_bind(v);
+        }
+    }
+}
+
+/// Builds the query that inserts the struct, without its returning clause
+fn create_query<T>(table_name: &str, struct_to_create: T) -> QueryBuilder<'static, Postgres>
 where
     T: Default + Iterable,
 {
@@ -25,33 +43,78 @@ where
         ),
     );
 
-    // TODO: extract the mathch into a function
-    let mut separated = query_builder.separated(", ");
-    fields_values.iter().for_each(|v| match v {
-        IterableType::Uuid(ev) => {
-            separated.push_bind(ev);
-        }
-        IterableType::DateTime(ev) => {
-            separated.push_bind(ev);
+    for (i, field_value) in fields_values.into_iter().enumerate() {
+        if i > 0 {
+            query_builder.push(", ");
         }
-        IterableType::Bool(ev) => {
-            separated.push_bind(ev);
-        }
-        IterableType::String(ev) => {
-            separated.push_bind(ev);
-        }
-    });
-    separated.push_unseparated(") returning *");
+        push_bind_iterable(&mut query_builder, field_value);
+    }
+    query_builder.push(")");
 
+    query_builder
+}
+
+// returns the created row
+#[instrument(name = "db::crud::create", skip_all, fields(db.system = "postgresql", db.operation = "insert", db.sql.table = table_name))]
+pub async fn create<T>(db: Db, table_name: &str, struct_to_create: T) -> Result<DbRow>
+where
+    T: Default + Iterable,
+{
+    let mut query_builder = create_query(table_name, struct_to_create);
+    query_builder.push(" returning *");
     let query = query_builder.build();
 
-    debug!("FN: model::db::crud::create - Query: {}", query.sql());
+    debug!("FN: model::db::crud::create - Table: {table_name}");
 
     let row = query.fetch_one(&db).await.map_err(Error::Sqlx)?;
 
     Ok(row)
 }
 
+// returns the created row, None if a row with the same values in the conflict fields (that
+// have a unique index) already exists
+#[instrument(name = "db::crud::create_if_absent", skip_all, fields(db.system = "postgresql", db.operation = "insert", db.sql.table = table_name))]
+pub async fn create_if_absent<T>(
+    db: Db,
+    table_name: &str,
+    struct_to_create: T,
+    conflict_fields: &[&str],
+) -> Result<Option<DbRow>>
+where
+    T: Default + Iterable,
+{
+    let mut query_builder = create_query(table_name, struct_to_create);
+    query_builder.push(format!(
+        " on conflict ({}) do nothing returning *",
+        conflict_fields.join(", ")
+    ));
+    let query = query_builder.build();
+
+    debug!("FN: model::db::crud::create_if_absent - Table: {table_name}");
+
+    let row = query.fetch_optional(&db).await.map_err(Error::Sqlx)?;
+
+    Ok(row)
+}
+
+// same as create, inside a transaction
+#[instrument(name = "db::crud::create_in_tx", skip_all, fields(db.system = "postgresql", db.operation = "insert", db.sql.table = table_name))]
+pub async fn create_in_tx<T>(tx: &mut DbTx, table_name: &str, struct_to_create: T) -> Result<DbRow>
+where
+    T: Default + Iterable,
+{
+    let mut query_builder = create_query(table_name, struct_to_create);
+    query_builder.push(" returning *");
+    let query = query_builder.build();
+
+    debug!("FN: model::db::crud::create_in_tx - Table: {table_name}");
+
+    let row = query.fetch_one(&mut **tx).await.map_err(Error::Sqlx)?;
+
+    Ok(row)
+}
+
+#[instrument(name = "db::crud::get_one_by_id", skip_all, fields(db.system = "postgresql", db.operation = "select", db.sql.table = table_name))]
 pub async fn get_one_by_id(db: Db, table_name: &str, id: Uuid) -> Result<DbRow> {
     let query = format!("select * from {} where id = $1", table_name);
 
@@ -64,6 +127,7 @@ pub async fn get_one_by_id(db: Db, table_name: &str, id: Uuid) -> Result<DbRow>
     Ok(row)
 }
 
+#[instrument(name = "db::crud::get_one_by_field", skip_all, fields(db.system = "postgresql", db.operation = "select", db.sql.table = table_name))]
 pub async fn get_one_by_field(
     db: Db,
     table_name: &str,
@@ -77,34 +141,41 @@ pub async fn get_one_by_field(
         format!("select * from {} where {} = ", table_name, field_name,),
     );
 
-    // TODO: extract the mathch into a function
-    match field_value {
-        IterableType::Uuid(fv) => {
-            query_builder.push_bind(fv);
-        }
-        IterableType::DateTime(fv) => {
-            query_builder.push_bind(fv);
-        }
-        IterableType::Bool(fv) => {
-            query_builder.push_bind(fv);
-        }
-        IterableType::String(fv) => {
-            query_builder.push_bind(fv);
-        }
-    };
+    push_bind_iterable(&mut query_builder, field_value);
 
     let query = query_builder.build();
 
-    debug!(
-        "FN: model::db::crud::get_one_by_field - Query: {}",
-        query.sql()
-    );
+    debug!("FN: model::db::crud::get_one_by_field - Table: {table_name}");
 
     let row = query.fetch_one(&db).await.map_err(Error::Sqlx)?;
 
     Ok(row)
 }
 
+#[instrument(name = "db::crud::get_all_by_field", skip_all, fields(db.system = "postgresql", db.operation = "select", db.sql.table = table_name))]
+pub async fn get_all_by_field(
+    db: Db,
+    table_name: &str,
+    field_name: &str,
+    field_value: IterableType,
+) -> Result<Vec<DbRow>> {
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+        "select * from {} where {} = ",
+        table_name, field_name,
+    ));
+
+    push_bind_iterable(&mut query_builder, field_value);
+
+    let query = query_builder.build();
+
+    debug!("FN: model::db::crud::get_all_by_field - Table: {table_name}");
+
+    let rows = query.fetch_all(&db).await.map_err(Error::Sqlx)?;
+
+    Ok(rows)
+}
+
+#[instrument(name = "db::crud::get_all", skip_all, fields(db.system = "postgresql", db.operation = "select", db.sql.table = table_name))]
 pub async fn get_all(db: Db, table_name: &str) -> Result<Vec<DbRow>> {
     let query = format!("select * from {}", table_name);
 
@@ -117,6 +188,7 @@ pub async fn get_all(db: Db, table_name: &str) -> Result<Vec<DbRow>> {
 }
 
 // TODO: if row not found return dynamic entity
+#[instrument(name = "db::crud::update_by_id", skip_all, fields(db.system = "postgresql", db.operation = "update", db.sql.table = table_name))]
 pub async fn update_by_id<T>(db: Db, table_name: &str, struct_for_update: T, id: Uuid) -> Result<()>
 where
     T: Iterable,
@@ -130,36 +202,21 @@ where
         format!("update {} set ", table_name),
     );
 
-    let mut separated = query_builder.separated("");
-    for (i, field_name) in fields_names.iter().enumerate() {
-        separated.push_unseparated(field_name);
-        separated.push_unseparated(" = ");
-        match &fields_values[i] {
-            IterableType::Uuid(ev) => {
-                separated.push_bind(ev);
-            }
-            IterableType::DateTime(ev) => {
-                separated.push_bind(ev);
-            }
-            IterableType::Bool(ev) => {
-                separated.push_bind(ev);
-            }
-            IterableType::String(ev) => {
-                separated.push_bind(ev);
-            }
-        }
-
-        if i < fields_names.len() - 1 {
-            separated.push_unseparated(", ");
+    for (i, (field_name, field_value)) in fields_names.iter().zip(fields_values).enumerate() {
+        if i > 0 {
+            query_builder.push(", ");
         }
+        query_builder.push(field_name);
+        query_builder.push(" = ");
+        push_bind_iterable(&mut query_builder, field_value);
     }
 
-    separated.push_unseparated(" where id = ");
-    separated.push_bind(id);
+    query_builder.push(" where id = ");
+    query_builder.push_bind(id);
 
     let query = query_builder.build();
 
-    debug!("FN: model::db::crud::update - Query: {}", query.sql());
+    debug!("FN: model::db::crud::update - Table: {table_name}");
 
     let rows_affected = query
         .execute(&db)
@@ -178,6 +235,7 @@ where
 }
 
 // TODO: if row not found return dynamic entity
+#[instrument(name = "db::crud::delete_by_id", skip_all, fields(db.system = "postgresql", db.operation = "delete", db.sql.table = table_name))]
 pub async fn delete_by_id(db: Db, table_name: &str, id: Uuid) -> Result<()> {
     let query = format!("delete from {} where id = $1", table_name);
 
@@ -197,3 +255,63 @@ pub async fn delete_by_id(db: Db, table_name: &str, id: Uuid) -> Result<()> {
 
     Ok(())
 }
+
+/// Builds the query that deletes the rows with the value in the field
+fn delete_by_field_query(
+    table_name: &str,
+    field_name: &str,
+    field_value: IterableType,
+) -> QueryBuilder<'static, Postgres> {
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+        "delete from {} where {} = ",
+        table_name, field_name,
+    ));
+
+    push_bind_iterable(&mut query_builder, field_value);
+
+    query_builder
+}
+
+// returns the number of deleted rows
+#[instrument(name = "db::crud::delete_by_field", skip_all, fields(db.system = "postgresql", db.operation = "delete", db.sql.table = table_name))]
+pub async fn delete_by_field(
+    db: Db,
+    table_name: &str,
+    field_name: &str,
+    field_value: IterableType,
+) -> Result<u64> {
+    let mut query_builder = delete_by_field_query(table_name, field_name, field_value);
+    let query = query_builder.build();
+
+    debug!("FN: model::db::crud::delete_by_field - Table: {table_name}");
+
+    let rows_affected = query
+        .execute(&db)
+        .await
+        .map_err(Error::Sqlx)?
+        .rows_affected();
+
+    Ok(rows_affected)
+}
+
+// same as delete_by_field, inside a transaction
+#[instrument(name = "db::crud::delete_by_field_in_tx", skip_all, fields(db.system = "postgresql", db.operation = "delete", db.sql.table = table_name))]
+pub async fn delete_by_field_in_tx(
+    tx: &mut DbTx,
+    table_name: &str,
+    field_name: &str,
+    field_value: IterableType,
+) -> Result<u64> {
+    let mut query_builder = delete_by_field_query(table_name, field_name, field_value);
+    let query = query_builder.build();
+
+    debug!("FN: model::db::crud::delete_by_field_in_tx - Table: {table_name}");
+
+    let rows_affected = query
+        .execute(&mut **tx)
+        .await
+        .map_err(Error::Sqlx)?
+        .rows_affected();
+
+    Ok(rows_affected)
+}
@@ -2,9 +2,11 @@ use std::time::Duration;
 
 use sqlx::{
     postgres::{PgPoolOptions, PgRow},
-    Pool, Postgres,
+    Pool, Postgres, Transaction,
 };
 
+use tracing::instrument;
+
 use crate::{
     config::config,
     error::{Error, Result},
@@ -12,6 +14,7 @@ use crate::{
 
 pub type Db = Pool<Postgres>;
 pub type DbRow = PgRow;
+pub type DbTx = Transaction<'static, Postgres>;
 
 pub mod crud;
 
@@ -23,3 +26,11 @@ pub async fn new_db_pool() -> Result<Db> {
         .await
         .map_err(Error::Sqlx)
 }
+
+/// Checks that the database answers a trivial query
+#[instrument(name = "db::ping", skip_all, fields(db.system = "postgresql"))]
+pub async fn ping(db: &Db) -> Result<()> {
+    sqlx::query("SELECT 1").execute(db).await?;
+
+    Ok(())
+}
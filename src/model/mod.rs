@@ -6,10 +6,17 @@ use crate::model::db::new_db_pool;
 use self::db::Db;
 use self::session::SessionDb;
 
+pub mod audit_log;
 pub mod db;
 mod iterable;
+pub mod organization;
+pub mod rbac;
+pub mod refresh_token;
 pub mod session;
+pub mod throttle;
+pub mod token;
 pub mod user_auth;
+pub mod user_mfa;
 
 #[derive(Clone)]
 pub struct ModelManager {
@@ -36,6 +43,13 @@ impl ModelManager {
         &self.db
     }
 
+    /// Closes the connection pools, waiting for the connections in use to be returned
+    pub async fn close(&self) {
+        self.db.close().await;
+        self.session_db.close();
+        info!("Closed DB and Session DB connections");
+    }
+
     /// Returns a reference to the session database pool
     pub fn session_db(&self) -> &SessionDb {
         &self.session_db
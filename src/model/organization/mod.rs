@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use strum_macros::AsRefStr;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::iterable::{Iterable, IterableType};
+
+pub mod model_controller;
+
+// length of the name column
+const MAX_NAME_LEN: usize = 255;
+
+/// The role of a member inside an organization
+/// Owners and admins manage the members, only owners can make other members owners
+#[derive(Clone, Copy, Debug, PartialEq, AsRefStr, Deserialize, Serialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl FromStr for OrganizationRole {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "owner" => Ok(OrganizationRole::Owner),
+            "admin" => Ok(OrganizationRole::Admin),
+            "member" => Ok(OrganizationRole::Member),
+            _ => Err(Error::OrganizationRoleInvalid),
+        }
+    }
+}
+
+impl OrganizationRole {
+    /// Returns true if a member with this role can invite and remove members with `role`
+    pub fn can_manage(&self, role: OrganizationRole) -> bool {
+        match self {
+            OrganizationRole::Owner => true,
+            OrganizationRole::Admin => role != OrganizationRole::Owner,
+            OrganizationRole::Member => false,
+        }
+    }
+}
+
+// region: Organization
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Organization {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub name: String,
+}
+
+impl Default for Organization {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            name: "".to_string(),
+        }
+    }
+}
+
+impl Organization {
+    pub fn new(name: String) -> Result<Organization> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(Error::OrganizationNameInvalid);
+        }
+
+        Ok(Organization {
+            name,
+            ..Default::default()
+        })
+    }
+}
+
+impl Iterable for Organization {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "updated_at".to_string(),
+            "name".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::DateTime(self.updated_at),
+            IterableType::String(self.name.to_string()),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: Organization
+
+// region: OrganizationMember
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct OrganizationMember {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+}
+
+impl Default for OrganizationMember {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            organization_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            role: OrganizationRole::Member.as_ref().to_string(),
+        }
+    }
+}
+
+impl OrganizationMember {
+    pub fn new(organization_id: Uuid, user_id: Uuid, role: OrganizationRole) -> OrganizationMember {
+        OrganizationMember {
+            organization_id,
+            user_id,
+            role: role.as_ref().to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn role(&self) -> Result<OrganizationRole> {
+        OrganizationRole::from_str(&self.role)
+    }
+}
+
+impl Iterable for OrganizationMember {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "organization_id".to_string(),
+            "user_id".to_string(),
+            "role".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::Uuid(self.organization_id),
+            IterableType::Uuid(self.user_id),
+            IterableType::String(self.role.to_string()),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: OrganizationMember
+
+// region: OrganizationInvitation
+
+/// Stored with the token sent to the invited email, until the invitation is accepted
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OrganizationInvitation {
+    pub organization_id: Uuid,
+    // canonical form of the invited email, only the user with this email can accept
+    pub email: String,
+    pub role: OrganizationRole,
+}
+
+// endregion: OrganizationInvitation
@@ -0,0 +1,241 @@
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::error::{Error, Result};
+use crate::model::iterable::IterableType;
+use crate::model::token::{self, TokenKind};
+use crate::model::{db, ModelManager};
+
+use super::{Organization, OrganizationInvitation, OrganizationMember, OrganizationRole};
+
+const TABLE_NAME: &str = "organizations";
+const MEMBERS_TABLE_NAME: &str = "organization_members";
+
+pub struct OrganizationBmc;
+
+impl OrganizationBmc {
+    // region: Db CRUD operations
+
+    /// Creates the organization with the user as its owner
+    pub async fn create(
+        model_manager: &ModelManager,
+        organization: Organization,
+        owner_id: Uuid,
+    ) -> Result<Uuid> {
+        // an organization is never left without its owner
+        let mut tx = model_manager.db().begin().await?;
+
+        let res = db::crud::create_in_tx(&mut tx, TABLE_NAME, organization).await?;
+
+        let organization_created = Organization::from_row(&res)?;
+
+        db::crud::create_in_tx(
+            &mut tx,
+            MEMBERS_TABLE_NAME,
+            OrganizationMember::new(organization_created.id, owner_id, OrganizationRole::Owner),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(organization_created.id)
+    }
+
+    pub async fn get(model_manager: &ModelManager, id: Uuid) -> Result<Organization> {
+        let res = db::crud::get_one_by_id(model_manager.db().clone(), TABLE_NAME, id).await?;
+
+        let organization = Organization::from_row(&res)?;
+
+        Ok(organization)
+    }
+
+    /// Deletes the organization and its memberships
+    pub async fn delete(model_manager: &ModelManager, id: Uuid) -> Result<()> {
+        db::crud::delete_by_id(model_manager.db().clone(), TABLE_NAME, id).await?;
+
+        Ok(())
+    }
+
+    // endregion: Db CRUD operations
+
+    // region: Members Db operations
+
+    pub async fn add_member(
+        model_manager: &ModelManager,
+        member: OrganizationMember,
+    ) -> Result<Uuid> {
+        let res = db::crud::create(model_manager.db().clone(), MEMBERS_TABLE_NAME, member).await?;
+
+        let member_created = OrganizationMember::from_row(&res)?;
+
+        Ok(member_created.id)
+    }
+
+    /// Returns the membership of the user in the organization, None if the user is not a member
+    pub async fn get_member(
+        model_manager: &ModelManager,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<OrganizationMember>> {
+        let query = format!(
+            "select * from {} where organization_id = $1 and user_id = $2",
+            MEMBERS_TABLE_NAME
+        );
+        let member = sqlx::query_as::<_, OrganizationMember>(&query)
+            .bind(organization_id)
+            .bind(user_id)
+            .fetch_optional(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(member)
+    }
+
+    pub async fn get_members(
+        model_manager: &ModelManager,
+        organization_id: Uuid,
+    ) -> Result<Vec<OrganizationMember>> {
+        let res = db::crud::get_all_by_field(
+            model_manager.db().clone(),
+            MEMBERS_TABLE_NAME,
+            "organization_id",
+            IterableType::Uuid(organization_id),
+        )
+        .await?;
+
+        let mut members = Vec::new();
+        for member in res {
+            let m = OrganizationMember::from_row(&member)?;
+            members.push(m);
+        }
+
+        Ok(members)
+    }
+
+    /// Returns the organizations the user is a member of, with the membership
+    pub async fn get_all_for_user(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+    ) -> Result<Vec<(Organization, OrganizationMember)>> {
+        let query = format!(
+            "select m.*, o.created_at as organization_created_at, \
+            o.updated_at as organization_updated_at, o.name as organization_name \
+            from {} m join {} o on o.id = m.organization_id where m.user_id = $1",
+            MEMBERS_TABLE_NAME, TABLE_NAME
+        );
+        let res = sqlx::query(&query)
+            .bind(user_id)
+            .fetch_all(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        let mut organizations = Vec::new();
+        for row in res {
+            let m = OrganizationMember::from_row(&row)?;
+            let o = Organization {
+                id: m.organization_id,
+                created_at: row.try_get("organization_created_at")?,
+                updated_at: row.try_get("organization_updated_at")?,
+                name: row.try_get("organization_name")?,
+            };
+            organizations.push((o, m));
+        }
+
+        Ok(organizations)
+    }
+
+    /// Removes the user from the organization, returns false if the user was not a member
+    /// Returns LastOrganizationOwner if the user is the only owner
+    pub async fn remove_member(
+        model_manager: &ModelManager,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool> {
+        let mut tx = model_manager.db().begin().await?;
+
+        // the owners are locked until the member is removed, so that two owners removed at the
+        // same time can't both see the other one
+        let query = format!(
+            "select user_id from {} where organization_id = $1 and role = $2 for update",
+            MEMBERS_TABLE_NAME
+        );
+        let owners: Vec<Uuid> = sqlx::query_scalar(&query)
+            .bind(organization_id)
+            .bind(OrganizationRole::Owner.as_ref())
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(Error::Sqlx)?;
+        if owners == [user_id] {
+            return Err(Error::LastOrganizationOwner);
+        }
+
+        let query = format!(
+            "delete from {} where organization_id = $1 and user_id = $2",
+            MEMBERS_TABLE_NAME
+        );
+        let res = sqlx::query(&query)
+            .bind(organization_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Sqlx)?;
+
+        tx.commit().await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    // endregion: Members Db operations
+
+    // region: Session Db CRUD operations
+
+    /// Returns the token to send to the invited email
+    pub async fn create_invitation(
+        model_manager: &ModelManager,
+        invitation: OrganizationInvitation,
+    ) -> Result<String> {
+        let res = token::crud::create(
+            model_manager.session_db().clone(),
+            TokenKind::OrganizationInvitation,
+            serde_json::to_string(&invitation)?,
+            config().ORGANIZATION_INVITATION_EXPIRATION,
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Returns the invitation without consuming it, None if the token is not valid
+    pub async fn get_invitation(
+        model_manager: &ModelManager,
+        token: String,
+    ) -> Result<Option<OrganizationInvitation>> {
+        let res = token::crud::get(
+            model_manager.session_db().clone(),
+            TokenKind::OrganizationInvitation,
+            token,
+        )
+        .await?;
+
+        Ok(res.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    /// Returns the invitation and makes sure it can't be used again, None if the token is not
+    /// valid
+    pub async fn consume_invitation(
+        model_manager: &ModelManager,
+        token: String,
+    ) -> Result<Option<OrganizationInvitation>> {
+        let res = token::crud::consume(
+            model_manager.session_db().clone(),
+            TokenKind::OrganizationInvitation,
+            token,
+        )
+        .await?;
+
+        Ok(res.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    // endregion: Session Db CRUD operations
+}
@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+
+use super::iterable::{Iterable, IterableType};
+
+pub mod model_controller;
+
+// length of the name columns
+const MAX_NAME_LEN: usize = 255;
+
+/// Checks that a role or permission name is not empty, fits in its column and only contains
+/// ascii letters, digits and . _ : - (e.g. "admin" or "documents:read")
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['.', '_', ':', '-'].contains(&c))
+}
+
+// region: Role
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Role {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub name: String,
+    pub description: String,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            name: "".to_string(),
+            description: "".to_string(),
+        }
+    }
+}
+
+impl Role {
+    pub fn new(name: String, description: String) -> Result<Role> {
+        if !is_valid_name(&name) {
+            return Err(Error::RoleNameInvalid);
+        }
+
+        Ok(Role {
+            name,
+            description,
+            ..Default::default()
+        })
+    }
+}
+
+impl Iterable for Role {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "updated_at".to_string(),
+            "name".to_string(),
+            "description".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::DateTime(self.updated_at),
+            IterableType::String(self.name.to_string()),
+            IterableType::String(self.description.to_string()),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: Role
+
+// region: Permission
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Permission {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub name: String,
+}
+
+impl Default for Permission {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            name: "".to_string(),
+        }
+    }
+}
+
+impl Permission {
+    pub fn new(name: String) -> Result<Permission> {
+        if !is_valid_name(&name) {
+            return Err(Error::PermissionNameInvalid);
+        }
+
+        Ok(Permission {
+            name,
+            ..Default::default()
+        })
+    }
+}
+
+impl Iterable for Permission {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "name".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::String(self.name.to_string()),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: Permission
+
+// region: RolePermission
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct RolePermission {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub role_id: Uuid,
+    pub permission_id: Uuid,
+}
+
+impl Default for RolePermission {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            role_id: Uuid::nil(),
+            permission_id: Uuid::nil(),
+        }
+    }
+}
+
+impl RolePermission {
+    pub fn new(role_id: Uuid, permission_id: Uuid) -> RolePermission {
+        RolePermission {
+            role_id,
+            permission_id,
+            ..Default::default()
+        }
+    }
+}
+
+impl Iterable for RolePermission {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "role_id".to_string(),
+            "permission_id".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::Uuid(self.role_id),
+            IterableType::Uuid(self.permission_id),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: RolePermission
+
+// region: UserRole
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct UserRole {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub user_id: Uuid,
+    pub role_id: Uuid,
+}
+
+impl Default for UserRole {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            user_id: Uuid::nil(),
+            role_id: Uuid::nil(),
+        }
+    }
+}
+
+impl UserRole {
+    pub fn new(user_id: Uuid, role_id: Uuid) -> UserRole {
+        UserRole {
+            user_id,
+            role_id,
+            ..Default::default()
+        }
+    }
+}
+
+impl Iterable for UserRole {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "user_id".to_string(),
+            "role_id".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::Uuid(self.user_id),
+            IterableType::Uuid(self.role_id),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: UserRole
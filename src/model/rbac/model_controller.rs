@@ -0,0 +1,270 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::model::iterable::IterableType;
+use crate::model::{db, ModelManager};
+
+use super::{Permission, Role, RolePermission, UserRole};
+
+const ROLES_TABLE_NAME: &str = "roles";
+const PERMISSIONS_TABLE_NAME: &str = "permissions";
+const ROLE_PERMISSIONS_TABLE_NAME: &str = "role_permissions";
+const USER_ROLES_TABLE_NAME: &str = "user_roles";
+
+// region: RoleBmc
+
+pub struct RoleBmc;
+
+impl RoleBmc {
+    // region: Db CRUD operations
+
+    pub async fn create(model_manager: &ModelManager, role: Role) -> Result<Uuid> {
+        let res = db::crud::create(model_manager.db().clone(), ROLES_TABLE_NAME, role).await?;
+
+        let role_created = Role::from_row(&res)?;
+
+        Ok(role_created.id)
+    }
+
+    pub async fn get(model_manager: &ModelManager, id: Uuid) -> Result<Role> {
+        let res = db::crud::get_one_by_id(model_manager.db().clone(), ROLES_TABLE_NAME, id).await?;
+
+        let role = Role::from_row(&res)?;
+
+        Ok(role)
+    }
+
+    pub async fn get_from_name(model_manager: &ModelManager, name: String) -> Result<Role> {
+        let res = db::crud::get_one_by_field(
+            model_manager.db().clone(),
+            ROLES_TABLE_NAME,
+            "name",
+            IterableType::String(name),
+        )
+        .await?;
+
+        let role = Role::from_row(&res)?;
+
+        Ok(role)
+    }
+
+    pub async fn get_all(model_manager: &ModelManager) -> Result<Vec<Role>> {
+        let res = db::crud::get_all(model_manager.db().clone(), ROLES_TABLE_NAME).await?;
+
+        let mut roles = Vec::new();
+        for role in res {
+            let r = Role::from_row(&role)?;
+            roles.push(r);
+        }
+
+        Ok(roles)
+    }
+
+    /// Deletes the role, its permissions and its assignments to the users
+    pub async fn delete(model_manager: &ModelManager, id: Uuid) -> Result<()> {
+        db::crud::delete_by_id(model_manager.db().clone(), ROLES_TABLE_NAME, id).await?;
+
+        Ok(())
+    }
+
+    // endregion: Db CRUD operations
+
+    // region: Permissions Db operations
+
+    /// Grants the permission to the role, granting it again does nothing
+    pub async fn grant_permission(
+        model_manager: &ModelManager,
+        role_id: Uuid,
+        permission_id: Uuid,
+    ) -> Result<()> {
+        db::crud::create_if_absent(
+            model_manager.db().clone(),
+            ROLE_PERMISSIONS_TABLE_NAME,
+            RolePermission::new(role_id, permission_id),
+            &["role_id", "permission_id"],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes the permission from the role, returns false if the role did not have it
+    pub async fn revoke_permission(
+        model_manager: &ModelManager,
+        role_id: Uuid,
+        permission_id: Uuid,
+    ) -> Result<bool> {
+        let query = format!(
+            "delete from {} where role_id = $1 and permission_id = $2",
+            ROLE_PERMISSIONS_TABLE_NAME
+        );
+        let res = sqlx::query(&query)
+            .bind(role_id)
+            .bind(permission_id)
+            .execute(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    pub async fn get_permissions(
+        model_manager: &ModelManager,
+        role_id: Uuid,
+    ) -> Result<Vec<Permission>> {
+        let query = format!(
+            "select p.* from {} p join {} rp on rp.permission_id = p.id where rp.role_id = $1",
+            PERMISSIONS_TABLE_NAME, ROLE_PERMISSIONS_TABLE_NAME
+        );
+        let permissions = sqlx::query_as::<_, Permission>(&query)
+            .bind(role_id)
+            .fetch_all(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(permissions)
+    }
+
+    // endregion: Permissions Db operations
+}
+
+// endregion: RoleBmc
+
+// region: PermissionBmc
+
+pub struct PermissionBmc;
+
+impl PermissionBmc {
+    // region: Db CRUD operations
+
+    pub async fn create(model_manager: &ModelManager, permission: Permission) -> Result<Uuid> {
+        let res = db::crud::create(
+            model_manager.db().clone(),
+            PERMISSIONS_TABLE_NAME,
+            permission,
+        )
+        .await?;
+
+        let permission_created = Permission::from_row(&res)?;
+
+        Ok(permission_created.id)
+    }
+
+    pub async fn get_from_name(model_manager: &ModelManager, name: String) -> Result<Permission> {
+        let res = db::crud::get_one_by_field(
+            model_manager.db().clone(),
+            PERMISSIONS_TABLE_NAME,
+            "name",
+            IterableType::String(name),
+        )
+        .await?;
+
+        let permission = Permission::from_row(&res)?;
+
+        Ok(permission)
+    }
+
+    /// Returns the id of the permission, creating it the first time it is used
+    pub async fn get_or_create(model_manager: &ModelManager, name: String) -> Result<Uuid> {
+        let res = db::crud::create_if_absent(
+            model_manager.db().clone(),
+            PERMISSIONS_TABLE_NAME,
+            Permission::new(name.clone())?,
+            &["name"],
+        )
+        .await?;
+
+        // the permission already exists (or has just been created by a concurrent request)
+        match res {
+            Some(row) => Ok(Permission::from_row(&row)?.id),
+            None => Ok(Self::get_from_name(model_manager, name).await?.id),
+        }
+    }
+
+    // endregion: Db CRUD operations
+}
+
+// endregion: PermissionBmc
+
+// region: UserRoleBmc
+
+pub struct UserRoleBmc;
+
+impl UserRoleBmc {
+    // region: Db CRUD operations
+
+    /// Assigns the role to the user, assigning it again does nothing
+    pub async fn assign(model_manager: &ModelManager, user_id: Uuid, role_id: Uuid) -> Result<()> {
+        db::crud::create_if_absent(
+            model_manager.db().clone(),
+            USER_ROLES_TABLE_NAME,
+            UserRole::new(user_id, role_id),
+            &["user_id", "role_id"],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes the role from the user, returns false if the user did not have it
+    pub async fn unassign(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+        role_id: Uuid,
+    ) -> Result<bool> {
+        let query = format!(
+            "delete from {} where user_id = $1 and role_id = $2",
+            USER_ROLES_TABLE_NAME
+        );
+        let res = sqlx::query(&query)
+            .bind(user_id)
+            .bind(role_id)
+            .execute(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    pub async fn get_roles(model_manager: &ModelManager, user_id: Uuid) -> Result<Vec<Role>> {
+        let query = format!(
+            "select r.* from {} r join {} ur on ur.role_id = r.id where ur.user_id = $1",
+            ROLES_TABLE_NAME, USER_ROLES_TABLE_NAME
+        );
+        let roles = sqlx::query_as::<_, Role>(&query)
+            .bind(user_id)
+            .fetch_all(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(roles)
+    }
+
+    // endregion: Db CRUD operations
+
+    /// Returns true if any of the roles of the user grants the permission
+    pub async fn has_permission(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+        permission: String,
+    ) -> Result<bool> {
+        let query = format!(
+            "select exists(select 1 from {} ur \
+            join {} rp on rp.role_id = ur.role_id \
+            join {} p on p.id = rp.permission_id \
+            where ur.user_id = $1 and p.name = $2)",
+            USER_ROLES_TABLE_NAME, ROLE_PERMISSIONS_TABLE_NAME, PERMISSIONS_TABLE_NAME
+        );
+        let has_permission: bool = sqlx::query_scalar(&query)
+            .bind(user_id)
+            .bind(permission)
+            .fetch_one(model_manager.db())
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(has_permission)
+    }
+}
+
+// endregion: UserRoleBmc
@@ -0,0 +1,176 @@
+use redis::{cmd, pipe};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::model::session::SessionDb;
+use crate::model::token::{token_key, TokenKind};
+use crate::utils;
+
+use super::{family_key, session_families_key, RefreshOutcome, RefreshTokenFamily};
+
+// adds a family to the index of its session and extends the index to live at least as long as it
+// KEYS[1]: the index, KEYS[2]: the family key, ARGV[1]: the seconds the index has to live
+const INDEX_FAMILY_SCRIPT: &str = r"
+redis.call('SADD', KEYS[1], KEYS[2])
+local expiration = tonumber(ARGV[1])
+if redis.call('TTL', KEYS[1]) < expiration then
+    redis.call('EXPIRE', KEYS[1], expiration)
+end
+";
+
+// replaces a refresh token with a new one, if the family has not been revoked and the token has
+// not been used yet (otherwise the family is revoked)
+// KEYS[1]: the family key, KEYS[2]: the used marker of the token, KEYS[3]: the key of the new
+// token, KEYS[4]: the index of the families of the session
+// ARGV[1]: the family id, ARGV[2]: the seconds the family has to live
+// returns 1 if rotated, 0 if the token had already been used, -1 if the family does not exist
+const ROTATE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return -1
+end
+if not redis.call('SET', KEYS[2], '1', 'NX', 'EX', ARGV[2]) then
+    redis.call('DEL', KEYS[1])
+    redis.call('SREM', KEYS[4], KEYS[1])
+    return 0
+end
+redis.call('SET', KEYS[3], ARGV[1], 'EX', ARGV[2])
+return 1
+";
+
+// revokes all the refresh token families of the sessions
+// KEYS: the indexes of the families of the sessions
+pub const REVOKE_SESSION_FAMILIES_SCRIPT: &str = r"
+for _, index in ipairs(KEYS) do
+    local families = redis.call('SMEMBERS', index)
+    if #families > 0 then
+        redis.call('DEL', unpack(families))
+    end
+    redis.call('DEL', index)
+end
+";
+
+/// Create a new refresh token family in the session db and add it to the index of its session
+/// Returns the id of the family
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `family` - The record of the family
+/// * `expiration` - The expiration time of the family in seconds
+#[instrument(name = "refresh_token::crud::create", skip_all, fields(db.system = "redis"))]
+pub async fn create(
+    session_db: SessionDb,
+    family: RefreshTokenFamily,
+    expiration: u64,
+) -> Result<String> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // generate random id
+    let family_id = Uuid::new_v4().to_string();
+    let key = family_key(&family_id);
+
+    // save in the db, the ttl of the index is read and extended in the same transaction
+    pipe()
+        .atomic()
+        .cmd("SET")
+        .arg(&[
+            key.clone(),
+            family.to_value()?,
+            "EX".to_string(),
+            expiration.to_string(),
+        ])
+        .ignore()
+        .cmd("EVAL")
+        .arg(INDEX_FAMILY_SCRIPT)
+        .arg(2)
+        .arg(&[session_families_key(&family.session_id), key])
+        .arg(expiration)
+        .ignore()
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    Ok(family_id)
+}
+
+/// Get a refresh token family from the session db
+/// Returns None if the family does not exist, is expired or has been revoked
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `family_id` - The id of the family
+#[instrument(name = "refresh_token::crud::get", skip_all, fields(db.system = "redis"))]
+pub async fn get(session_db: SessionDb, family_id: String) -> Result<Option<RefreshTokenFamily>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let value: Option<String> = cmd("GET")
+        .arg(&[family_key(&family_id)])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    value.map(RefreshTokenFamily::from_value).transpose()
+}
+
+/// Delete a refresh token family from the session db, revoking all its tokens
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `family_id` - The id of the family
+#[instrument(name = "refresh_token::crud::delete", skip_all, fields(db.system = "redis"))]
+pub async fn delete(session_db: SessionDb, family_id: String) -> Result<()> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    cmd("DEL")
+        .arg(&[family_key(&family_id)])
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Replace a refresh token with a new token of its family, in a single transaction
+/// The token can be replaced only once, presenting it again revokes the family
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `family_id` - The id of the family of the token
+/// * `family` - The record of the family
+/// * `token` - The refresh token to replace
+/// * `expiration` - How long the new token and the used token are kept in seconds
+#[instrument(name = "refresh_token::crud::rotate", skip_all, fields(db.system = "redis"))]
+pub async fn rotate(
+    session_db: SessionDb,
+    family_id: String,
+    family: RefreshTokenFamily,
+    token: String,
+    expiration: u64,
+) -> Result<RefreshOutcome> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // generate the new random token
+    let new_token = utils::generate_token();
+
+    let res: i64 = cmd("EVAL")
+        .arg(ROTATE_SCRIPT)
+        .arg(4)
+        .arg(&[
+            family_key(&family_id),
+            token_key(TokenKind::RefreshTokenUsed, &token),
+            token_key(TokenKind::RefreshToken, &new_token),
+            session_families_key(&family.session_id),
+        ])
+        .arg(&[family_id.clone(), expiration.to_string()])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    let outcome = match res {
+        1 => RefreshOutcome::Rotated {
+            family_id,
+            family,
+            token: new_token,
+        },
+        0 => RefreshOutcome::Reused(family),
+        _ => RefreshOutcome::Invalid,
+    };
+
+    Ok(outcome)
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::config, error::Result};
+
+pub mod crud;
+pub mod model_controller;
+
+// region: RefreshTokenFamily
+
+/// The record stored for a family of refresh tokens: the token issued at login and all the
+/// tokens it has been rotated into
+/// Only the last token of the family can be used, deleting the record revokes all of them
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RefreshTokenFamily {
+    pub user_id: String,
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    // rotating the tokens does not extend the family
+    pub expires_at: DateTime<Utc>,
+}
+
+impl RefreshTokenFamily {
+    pub fn new(user_id: String, session_id: String) -> Self {
+        let now = Utc::now();
+
+        Self {
+            user_id,
+            session_id,
+            created_at: now,
+            expires_at: now + Duration::seconds(config().REFRESH_TOKEN_LIFETIME as i64),
+        }
+    }
+
+    /// Returns the seconds the family has to live from `now`
+    pub fn ttl(&self, now: DateTime<Utc>) -> u64 {
+        (self.expires_at - now).num_seconds().max(0) as u64
+    }
+
+    pub fn from_value(value: String) -> Result<Self> {
+        let family = serde_json::from_str(&value)?;
+
+        Ok(family)
+    }
+
+    pub fn to_value(&self) -> Result<String> {
+        let value = serde_json::to_string(self)?;
+
+        Ok(value)
+    }
+}
+
+// endregion: RefreshTokenFamily
+
+/// What happened to a refresh token presented to be rotated
+#[derive(Clone, Debug, PartialEq)]
+pub enum RefreshOutcome {
+    /// The token was the last of its family and has been replaced by `token`
+    Rotated {
+        family_id: String,
+        family: RefreshTokenFamily,
+        token: String,
+    },
+    /// The token had already been rotated, the whole family has been revoked
+    Reused(RefreshTokenFamily),
+    /// The token does not exist, is expired or its family has been revoked
+    Invalid,
+}
+
+/// Returns the key of the record of a refresh token family
+pub fn family_key(family_id: &str) -> String {
+    format!("refresh_token_family:{family_id}")
+}
+
+/// Returns the key of the set that indexes the refresh token families of a session, so that they
+/// are revoked with the session
+pub fn session_families_key(session_id: &str) -> String {
+    format!("session_refresh_families:{session_id}")
+}
@@ -0,0 +1,110 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    model::{
+        refresh_token,
+        token::{self, TokenKind},
+        ModelManager,
+    },
+};
+
+use super::{RefreshOutcome, RefreshTokenFamily};
+
+pub struct RefreshTokenBmc;
+
+impl RefreshTokenBmc {
+    // region: Session Db CRUD operations
+
+    /// Starts a new token family for the session
+    /// Returns the first refresh token of the family and the family record
+    pub async fn create_family(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+        session_id: String,
+    ) -> Result<(String, RefreshTokenFamily)> {
+        let family = RefreshTokenFamily::new(user_id.to_string(), session_id);
+        let expiration = family.ttl(Utc::now());
+
+        let family_id = refresh_token::crud::create(
+            model_manager.session_db().clone(),
+            family.clone(),
+            expiration,
+        )
+        .await?;
+
+        let token = token::crud::create(
+            model_manager.session_db().clone(),
+            TokenKind::RefreshToken,
+            family_id,
+            expiration,
+        )
+        .await?;
+
+        Ok((token, family))
+    }
+
+    /// Returns the id and the record of the family of a refresh token, without using the token
+    /// Returns None if the token does not exist or its family is expired or revoked
+    pub async fn get_family(
+        model_manager: &ModelManager,
+        token: String,
+    ) -> Result<Option<(String, RefreshTokenFamily)>> {
+        // the tokens are kept after being rotated, so that their reuse can be detected
+        let Some(family_id) = token::crud::get(
+            model_manager.session_db().clone(),
+            TokenKind::RefreshToken,
+            token,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let family =
+            refresh_token::crud::get(model_manager.session_db().clone(), family_id.clone()).await?;
+
+        Ok(family
+            .filter(|f| f.ttl(Utc::now()) > 0)
+            .map(|f| (family_id, f)))
+    }
+
+    /// Replaces the refresh token with a new one of the same family
+    /// A token can be rotated only once: presenting it again means that it has been stolen
+    /// (either by whoever presents it now or by whoever rotated it before), so the whole family
+    /// is revoked
+    pub async fn rotate(
+        model_manager: &ModelManager,
+        token: String,
+        family_id: String,
+        family: RefreshTokenFamily,
+    ) -> Result<RefreshOutcome> {
+        let expiration = family.ttl(Utc::now());
+        if expiration == 0 {
+            return Ok(RefreshOutcome::Invalid);
+        }
+
+        // marking the token as used, creating the new one and revoking the family on reuse are
+        // atomic, two concurrent rotations can't both succeed
+        let outcome = refresh_token::crud::rotate(
+            model_manager.session_db().clone(),
+            family_id,
+            family,
+            token,
+            expiration,
+        )
+        .await?;
+
+        Ok(outcome)
+    }
+
+    /// Revokes all the tokens of the family
+    pub async fn revoke_family(model_manager: &ModelManager, family_id: String) -> Result<()> {
+        refresh_token::crud::delete(model_manager.session_db().clone(), family_id).await?;
+
+        Ok(())
+    }
+
+    // endregion: Session Db CRUD operations
+}
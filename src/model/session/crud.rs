@@ -1,70 +1,425 @@
-use redis::cmd;
+use chrono::Utc;
+use redis::{cmd, pipe};
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::error::{Error, Result};
+use crate::model::refresh_token::{crud::REVOKE_SESSION_FAMILIES_SCRIPT, session_families_key};
 
-use super::SessionDb;
+use super::{user_sessions_key, Session, SessionDb, ACTIVE_SESSIONS_KEY};
 
-/// Create a new session in the session db
+// adds a session to the index of its user and extends the index to live at least as long as it
+// KEYS[1]: the index, KEYS[2]: the session key, ARGV[1]: the seconds the index has to live
+const INDEX_SESSION_SCRIPT: &str = r"
+redis.call('SADD', KEYS[1], KEYS[2])
+local expiration = tonumber(ARGV[1])
+if expiration > 0 and redis.call('TTL', KEYS[1]) < expiration then
+    redis.call('EXPIRE', KEYS[1], expiration)
+end
+";
+
+/// Create a new session in the session db and add it to the user's session index and to the
+/// active sessions
 /// Returns the session id
 /// # Arguments
 /// * `session_db` - The session db connection pool
-/// * `value` - The value to store in the session db
+/// * `session` - The session record to store in the session db
 /// * `expiration` - The expiration time of the session in seconds
-pub async fn create(session_db: SessionDb, value: String, expiration: u64) -> Result<String> {
+#[instrument(name = "session::crud::create", skip_all, fields(db.system = "redis"))]
+pub async fn create(session_db: SessionDb, session: Session, expiration: u64) -> Result<String> {
     // get connection to session db
     let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
 
     // generate random key
     let key = Uuid::new_v4().to_string();
+    let index_key = user_sessions_key(&session.user_id);
+    let value = session.to_value()?;
+
+    // the index has to live at least as long as the longest session it contains
+    // (sessions can be extended up to their absolute expiration)
+    let index_expiration = session
+        .expires_at
+        .map(|e| (e - Utc::now()).num_seconds().max(0) as u64)
+        .unwrap_or(expiration)
+        .max(expiration);
 
-    // save in the db
-    cmd("SET")
+    // save in the db, the ttl of the index is read and extended in the same transaction
+    pipe()
+        .atomic()
+        .cmd("SET")
         .arg(&[key.clone(), value, "EX".to_string(), expiration.to_string()])
-        .query_async(&mut session_db_conn)
+        .ignore()
+        .cmd("EVAL")
+        .arg(INDEX_SESSION_SCRIPT)
+        .arg(2)
+        .arg(&[&index_key, &key])
+        .arg(index_expiration)
+        .ignore()
+        .cmd("ZADD")
+        .arg(ACTIVE_SESSIONS_KEY)
+        .arg(Utc::now().timestamp() + expiration as i64)
+        .arg(&key)
+        .ignore()
+        .query_async::<_, ()>(&mut session_db_conn)
         .await?;
 
     Ok(key)
 }
 
 /// Get a session from the session db
-/// Returns the value of the session
+/// Returns the key and the record of the session, SessionNotFound if it does not exist
 /// # Arguments
 /// * `session_db` - The session db connection pool
 /// * `key` - The key of the session
-pub async fn get(session_db: SessionDb, key: String) -> Result<(String, String)> {
+#[instrument(name = "session::crud::get", skip_all, fields(db.system = "redis"))]
+pub async fn get(session_db: SessionDb, key: String) -> Result<(String, Session)> {
     // get connection to session db
     let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
 
     // get the value from the db
-    let value = cmd("GET")
+    let value: Option<String> = cmd("GET")
         .arg(&[&key])
         .query_async(&mut session_db_conn)
         .await?;
+    let value = value.ok_or(Error::SessionNotFound)?;
 
-    Ok((key, value))
+    Ok((key, Session::from_value(value)))
 }
 
-/// Delete a session from the session db
+/// Update the record of an existing session
 /// # Arguments
 /// * `session_db` - The session db connection pool
 /// * `key` - The key of the session
-pub async fn delete(session_db: SessionDb, key: String) -> Result<()> {
+/// * `session` - The updated session record
+/// * `expiration` - The new expiration time of the session in seconds, None to keep the current one
+#[instrument(name = "session::crud::update", skip_all, fields(db.system = "redis"))]
+pub async fn update(
+    session_db: SessionDb,
+    key: String,
+    session: Session,
+    expiration: Option<u64>,
+) -> Result<()> {
     // get connection to session db
     let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
 
-    // delete the value from the db
-    cmd("DEL")
+    let expiration_args = match expiration {
+        Some(expiration) => vec!["EX".to_string(), expiration.to_string()],
+        None => vec!["KEEPTTL".to_string()],
+    };
+
+    // overwrite the value only if the session still exists
+    let mut pipeline = pipe();
+    pipeline
+        .atomic()
+        .cmd("SET")
+        .arg(&[key.clone(), session.to_value()?, "XX".to_string()])
+        .arg(&expiration_args)
+        .ignore();
+    // move the expiration of the active session, the deleted sessions are not added back
+    if let Some(expiration) = expiration {
+        pipeline
+            .cmd("ZADD")
+            .arg(ACTIVE_SESSIONS_KEY)
+            .arg("XX")
+            .arg(Utc::now().timestamp() + expiration as i64)
+            .arg(&key)
+            .ignore();
+    }
+    pipeline.query_async::<_, ()>(&mut session_db_conn).await?;
+
+    Ok(())
+}
+
+/// Get the seconds left before a session expires
+/// Returns None if the session does not exist or does not expire
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `key` - The key of the session
+#[instrument(name = "session::crud::ttl", skip_all, fields(db.system = "redis"))]
+pub async fn ttl(session_db: SessionDb, key: String) -> Result<Option<u64>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let ttl: i64 = cmd("TTL")
         .arg(&[key])
         .query_async(&mut session_db_conn)
         .await?;
 
+    Ok((ttl >= 0).then_some(ttl as u64))
+}
+
+/// Get all the active sessions of a user
+/// Expired sessions still in the user's session index are removed from it
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `user_id` - The id of the user
+#[instrument(name = "session::crud::list", skip_all, fields(db.system = "redis"))]
+pub async fn list(session_db: SessionDb, user_id: String) -> Result<Vec<(String, Session)>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let index_key = user_sessions_key(&user_id);
+
+    // get the session ids from the index
+    let keys: Vec<String> = cmd("SMEMBERS")
+        .arg(&[&index_key])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // get all the sessions at once
+    let values: Vec<Option<String>> = cmd("MGET")
+        .arg(&keys)
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    let mut active_sessions = Vec::new();
+    let mut expired_keys = Vec::new();
+    for (key, value) in keys.into_iter().zip(values) {
+        match value {
+            Some(value) => active_sessions.push((key, Session::from_value(value))),
+            None => expired_keys.push(key),
+        }
+    }
+
+    // remove the expired sessions from the index
+    if !expired_keys.is_empty() {
+        cmd("SREM")
+            .arg(&index_key)
+            .arg(&expired_keys)
+            .query_async::<_, ()>(&mut session_db_conn)
+            .await?;
+    }
+
+    Ok(active_sessions)
+}
+
+/// Count the active sessions of all the users
+/// The expired sessions are removed from the active sessions first, so it reads only the sessions
+/// that have expired since the last count
+/// # Arguments
+/// * `session_db` - The session db connection pool
+#[instrument(name = "session::crud::count_active", skip_all, fields(db.system = "redis"))]
+pub async fn count_active(session_db: SessionDb) -> Result<u64> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // the sessions that expire in the current second are still active
+    let (count,): (u64,) = pipe()
+        .atomic()
+        .cmd("ZREMRANGEBYSCORE")
+        .arg(ACTIVE_SESSIONS_KEY)
+        .arg("-inf")
+        .arg(format!("({}", Utc::now().timestamp()))
+        .ignore()
+        .cmd("ZCARD")
+        .arg(ACTIVE_SESSIONS_KEY)
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok(count)
+}
+
+/// Delete a session from the session db, from the user's session index and from the active
+/// sessions, revoking its refresh tokens
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `key` - The key of the session
+#[instrument(name = "session::crud::delete", skip_all, fields(db.system = "redis"))]
+pub async fn delete(session_db: SessionDb, key: String) -> Result<()> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // get the user id to find the user's session index
+    let value: Option<String> = cmd("GET")
+        .arg(&[&key])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    // delete the value and the refresh token families from the db
+    let mut pipeline = pipe();
+    pipeline
+        .atomic()
+        .cmd("DEL")
+        .arg(&[&key])
+        .ignore()
+        .cmd("EVAL")
+        .arg(REVOKE_SESSION_FAMILIES_SCRIPT)
+        .arg(1)
+        .arg(session_families_key(&key))
+        .ignore()
+        .cmd("ZREM")
+        .arg(ACTIVE_SESSIONS_KEY)
+        .arg(&key)
+        .ignore();
+    if let Some(value) = value {
+        let session = Session::from_value(value);
+        pipeline
+            .cmd("SREM")
+            .arg(&[user_sessions_key(&session.user_id), key])
+            .ignore();
+    }
+    pipeline.query_async::<_, ()>(&mut session_db_conn).await?;
+
+    Ok(())
+}
+
+/// Delete all the sessions of a user, revoking their refresh tokens
+/// Returns the number of deleted sessions
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `user_id` - The id of the user
+/// * `keep` - The key of a session that must not be deleted
+#[instrument(name = "session::crud::delete_all", skip_all, fields(db.system = "redis"))]
+pub async fn delete_all(
+    session_db: SessionDb,
+    user_id: String,
+    keep: Option<String>,
+) -> Result<u64> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let index_key = user_sessions_key(&user_id);
+
+    // get the session ids from the index
+    let keys: Vec<String> = cmd("SMEMBERS")
+        .arg(&[&index_key])
+        .query_async(&mut session_db_conn)
+        .await?;
+    let keys: Vec<String> = keys
+        .into_iter()
+        .filter(|k| Some(k) != keep.as_ref())
+        .collect();
+
+    if keys.is_empty() {
+        return Ok(0);
+    }
+
+    let families_keys: Vec<String> = keys.iter().map(|k| session_families_key(k)).collect();
+
+    // delete the sessions with their refresh token families and remove them from the index
+    let (deleted,): (u64,) = pipe()
+        .atomic()
+        .cmd("DEL")
+        .arg(&keys)
+        .cmd("SREM")
+        .arg(&index_key)
+        .arg(&keys)
+        .ignore()
+        .cmd("EVAL")
+        .arg(REVOKE_SESSION_FAMILIES_SCRIPT)
+        .arg(families_keys.len())
+        .arg(&families_keys)
+        .ignore()
+        .cmd("ZREM")
+        .arg(ACTIVE_SESSIONS_KEY)
+        .arg(&keys)
+        .ignore()
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok(deleted)
+}
+
+/// Add the sessions created before the user's session indexes to the index of their user and to
+/// the active sessions, so that they can be listed, revoked and counted like the others
+/// It walks all the keys of the session db, so it is not run at startup but by the
+/// ```index-legacy-sessions``` command, running it again indexes nothing twice
+/// Returns the number of indexed sessions
+/// # Arguments
+/// * `session_db` - The session db connection pool
+#[instrument(name = "session::crud::index_legacy_sessions", skip_all, fields(db.system = "redis"))]
+pub async fn index_legacy_sessions(session_db: SessionDb) -> Result<u64> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let mut count = 0;
+    let mut cursor = 0u64;
+    loop {
+        let (next_cursor, keys): (u64, Vec<String>) = cmd("SCAN")
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(100)
+            .query_async(&mut session_db_conn)
+            .await?;
+
+        // the sessions are the only keys that are bare uuids
+        let keys: Vec<String> = keys
+            .into_iter()
+            .filter(|k| Uuid::parse_str(k).is_ok())
+            .collect();
+
+        for key in keys {
+            let value: Option<String> = cmd("GET")
+                .arg(&[&key])
+                .query_async(&mut session_db_conn)
+                .await?;
+            let ttl: i64 = cmd("TTL")
+                .arg(&[&key])
+                .query_async(&mut session_db_conn)
+                .await?;
+            // the session has expired in the meantime
+            let Some(value) = value else {
+                continue;
+            };
+
+            // the sessions without expiration are counted until they are deleted
+            let expires_at = if ttl >= 0 {
+                (Utc::now().timestamp() + ttl).to_string()
+            } else {
+                "+inf".to_string()
+            };
+
+            let session = Session::from_value(value);
+            pipe()
+                .atomic()
+                .cmd("EVAL")
+                .arg(INDEX_SESSION_SCRIPT)
+                .arg(2)
+                .arg(&[user_sessions_key(&session.user_id), key.clone()])
+                .arg(ttl.max(0))
+                .ignore()
+                .cmd("ZADD")
+                .arg(ACTIVE_SESSIONS_KEY)
+                .arg(expires_at)
+                .arg(&key)
+                .ignore()
+                .query_async::<_, ()>(&mut session_db_conn)
+                .await?;
+            count += 1;
+        }
+
+        if next_cursor == 0 {
+            break;
+        }
+        cursor = next_cursor;
+    }
+
+    Ok(count)
+}
+
+/// Checks that the session db answers a PING
+/// # Arguments
+/// * `session_db` - The session db connection pool
+#[instrument(name = "session::crud::ping", skip_all, fields(db.system = "redis"))]
+pub async fn ping(session_db: SessionDb) -> Result<()> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    cmd("PING")
+        .query_async::<_, String>(&mut session_db_conn)
+        .await?;
+
     Ok(())
 }
 
 /// Delete all records from the session db
 /// # Arguments
 /// * `session_db` - The session db connection pool
+#[instrument(name = "session::crud::flush_db", skip_all, fields(db.system = "redis"))]
 pub async fn flush_db(session_db: SessionDb) -> Result<()> {
     // get connection to session db
     let mut session_db_conn = session_db.get().await?;
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Duration, Utc};
 use deadpool_redis::{Config, Pool, Runtime};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     config::config,
@@ -9,7 +11,98 @@ pub type SessionDb = Pool;
 
 pub mod crud;
 
-// TODO: find a way to manage multiple user sessions
+// region: Session
+
+/// The record stored as value of a session in the session db
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Session {
+    pub user_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_label: Option<String>,
+    // absolute expiration, the session is never extended past it
+    pub expires_at: Option<DateTime<Utc>>,
+    // seconds of inactivity after which the session expires
+    pub idle_timeout: Option<u64>,
+    // organization the user is acting on behalf of, changed with SwitchOrganization
+    #[serde(default)]
+    pub active_organization_id: Option<String>,
+}
+
+impl Session {
+    pub fn new(user_id: String) -> Self {
+        let now = Utc::now();
+
+        Self {
+            user_id,
+            created_at: now,
+            last_seen_at: now,
+            ip: None,
+            user_agent: None,
+            device_label: None,
+            expires_at: None,
+            idle_timeout: None,
+            active_organization_id: None,
+        }
+    }
+
+    /// Sets the absolute lifetime and the idle timeout of the session (both in seconds)
+    pub fn with_lifetime(self, lifetime: u64, idle_timeout: u64) -> Self {
+        Self {
+            expires_at: Some(self.created_at + Duration::seconds(lifetime as i64)),
+            idle_timeout: Some(idle_timeout.min(lifetime)),
+            ..self
+        }
+    }
+
+    /// Returns the seconds the session has to live from `now`: the idle timeout, without going
+    /// past the absolute expiration
+    /// Returns None for sessions created without a lifetime
+    pub fn ttl(&self, now: DateTime<Utc>) -> Option<u64> {
+        let expires_at = self.expires_at?;
+        let idle_timeout = self.idle_timeout?;
+
+        let remaining = (expires_at - now).num_seconds().max(0) as u64;
+
+        Some(idle_timeout.min(remaining))
+    }
+
+    /// Parses the value stored in the session db
+    /// Sessions created before the metadata was stored only contain the user id, their
+    /// timestamps are set to the unix epoch since they are unknown
+    pub fn from_value(value: String) -> Self {
+        serde_json::from_str(&value).unwrap_or_else(|_| Self {
+            user_id: value,
+            created_at: DateTime::<Utc>::default(),
+            last_seen_at: DateTime::<Utc>::default(),
+            ip: None,
+            user_agent: None,
+            device_label: None,
+            expires_at: None,
+            idle_timeout: None,
+            active_organization_id: None,
+        })
+    }
+
+    pub fn to_value(&self) -> Result<String> {
+        let value = serde_json::to_string(self)?;
+
+        Ok(value)
+    }
+}
+
+// endregion: Session
+
+/// Key of the sorted set of all the sessions, scored by their expiration (unix seconds), to count
+/// the active sessions without walking the session db
+pub const ACTIVE_SESSIONS_KEY: &str = "active_sessions";
+
+/// Returns the key of the set that indexes all the sessions of a user
+pub fn user_sessions_key(user_id: &str) -> String {
+    format!("user_sessions:{user_id}")
+}
 
 pub async fn new_session_db_conn() -> Result<SessionDb> {
     let cfg = Config::from_url(config().SESSION_DB_URL.as_str());
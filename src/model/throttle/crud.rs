@@ -0,0 +1,148 @@
+use redis::cmd;
+use tracing::instrument;
+
+use crate::error::{Error, Result};
+use crate::model::session::SessionDb;
+
+use super::{attempts_key, block_key, LoginAttempt, ThrottlePolicy, ThrottleScope};
+
+// KEYS: attempts, block - ARGV: window, delay after each attempt (the last one also for the
+// following attempts)
+// checks the block, counts the attempt and blocks the next ones in a single step, so that
+// concurrent attempts can't all get past the check before being counted
+// returns {0, milliseconds left} if blocked, {attempts, 0} otherwise
+const COUNT_ATTEMPT_SCRIPT: &str = "
+local pttl = redis.call('PTTL', KEYS[2])
+if pttl > 0 then
+    return {0, pttl}
+end
+local attempts = redis.call('INCR', KEYS[1])
+redis.call('EXPIRE', KEYS[1], ARGV[1])
+local delay = tonumber(ARGV[math.min(attempts, #ARGV - 1) + 1])
+if delay > 0 then
+    redis.call('SET', KEYS[2], attempts, 'EX', delay)
+end
+return {attempts, 0}
+";
+
+// KEYS: attempts, block - ARGV: the attempt to undo
+// removes the block only if it was set by the attempt
+const UNDO_ATTEMPT_SCRIPT: &str = "
+if redis.call('GET', KEYS[2]) == ARGV[1] then
+    redis.call('DEL', KEYS[2])
+end
+if tonumber(redis.call('GET', KEYS[1]) or '0') > 0 then
+    redis.call('DECR', KEYS[1])
+end
+";
+
+/// Get the seconds left before a new login attempt is allowed
+/// Returns None if the login is not blocked
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `scope` - What the attempts are counted for
+/// * `id` - The account or the ip
+#[instrument(name = "throttle::crud::blocked_for", skip_all, fields(db.system = "redis"))]
+pub async fn blocked_for(
+    session_db: SessionDb,
+    scope: ThrottleScope,
+    id: String,
+) -> Result<Option<u64>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // milliseconds, so that the last second of the block is not lost to rounding
+    let pttl: i64 = cmd("PTTL")
+        .arg(&[block_key(scope, &id)])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok((pttl > 0).then(|| (pttl as u64).div_ceil(1000)))
+}
+
+/// Count a login attempt, before knowing if it fails, and block the next ones if needed
+/// The attempt is counted as failed until it is undone
+/// Returns the number of the attempt, or the seconds the login is blocked (the attempt is then
+/// not counted)
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `scope` - What the attempts are counted for
+/// * `id` - The account or the ip
+/// * `policy` - How the failed attempts are slowed down
+#[instrument(name = "throttle::crud::count_attempt", skip_all, fields(db.system = "redis"))]
+pub async fn count_attempt(
+    session_db: SessionDb,
+    scope: ThrottleScope,
+    id: String,
+    policy: &ThrottlePolicy,
+) -> Result<LoginAttempt> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // the delays are computed by the policy, up to the lockout that is the delay of all the
+    // attempts after it
+    let delays: Vec<u64> = (1..=policy.lockout_attempts.max(1))
+        .map(|attempts| policy.delay(attempts))
+        .collect();
+
+    let (attempts, pttl): (u64, u64) = cmd("EVAL")
+        .arg(COUNT_ATTEMPT_SCRIPT)
+        .arg(2)
+        .arg(&[attempts_key(scope, &id), block_key(scope, &id)])
+        .arg(policy.window)
+        .arg(delays)
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    // milliseconds, so that the last second of the block is not lost to rounding
+    if pttl > 0 {
+        return Ok(LoginAttempt::Blocked(pttl.div_ceil(1000)));
+    }
+
+    Ok(LoginAttempt::Counted(attempts))
+}
+
+/// Undo a login attempt that succeeded, and the block it has set
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `scope` - What the attempts are counted for
+/// * `id` - The account or the ip
+/// * `attempts` - The number of the attempt returned when it was counted
+#[instrument(name = "throttle::crud::undo_attempt", skip_all, fields(db.system = "redis"))]
+pub async fn undo_attempt(
+    session_db: SessionDb,
+    scope: ThrottleScope,
+    id: String,
+    attempts: u64,
+) -> Result<()> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    cmd("EVAL")
+        .arg(UNDO_ATTEMPT_SCRIPT)
+        .arg(2)
+        .arg(&[attempts_key(scope, &id), block_key(scope, &id)])
+        .arg(attempts)
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete the failed attempts and the block
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `scope` - What the attempts are counted for
+/// * `id` - The account or the ip
+#[instrument(name = "throttle::crud::clear", skip_all, fields(db.system = "redis"))]
+pub async fn clear(session_db: SessionDb, scope: ThrottleScope, id: String) -> Result<()> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    cmd("DEL")
+        .arg(&[attempts_key(scope, &id), block_key(scope, &id)])
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use strum_macros::AsRefStr;
+
+use crate::config::config;
+
+pub mod crud;
+
+/// What the failed login attempts are counted for, used as part of the keys in the session db
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ThrottleScope {
+    Account,
+    Ip,
+}
+
+/// Returns the key of the counter of the failed attempts
+pub fn attempts_key(scope: ThrottleScope, id: &str) -> String {
+    format!("login_attempts:{}:{id}", scope.as_ref())
+}
+
+/// Returns the key that blocks the login attempts until it expires
+pub fn block_key(scope: ThrottleScope, id: &str) -> String {
+    format!("login_block:{}:{id}", scope.as_ref())
+}
+
+/// Outcome of counting a login attempt
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoginAttempt {
+    // the attempt can go on, it is the n-th of the window
+    Counted(u64),
+    // the login is blocked for these seconds, the attempt has not been counted
+    Blocked(u64),
+}
+
+/// How failed attempts are slowed down
+/// After `free_attempts` failures every new failure blocks the login for a delay that doubles each
+/// time (up to `max_delay`), after `lockout_attempts` failures the login is locked out for
+/// `lockout_duration`
+#[derive(Clone, Debug)]
+pub struct ThrottlePolicy {
+    pub free_attempts: u64,
+    pub lockout_attempts: u64,
+    pub base_delay: u64,
+    pub max_delay: u64,
+    pub lockout_duration: u64,
+    // failures are forgotten after this many seconds without new ones
+    pub window: u64,
+}
+
+impl ThrottlePolicy {
+    /// Returns the policy from the config for the scope
+    /// The ip scope allows more attempts, since many users can share the same address
+    pub fn for_scope(scope: ThrottleScope) -> Self {
+        let (free_attempts, lockout_attempts) = match scope {
+            ThrottleScope::Account => (
+                config().LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS,
+                config().LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS,
+            ),
+            ThrottleScope::Ip => (
+                config().LOGIN_THROTTLE_IP_FREE_ATTEMPTS,
+                config().LOGIN_THROTTLE_IP_LOCKOUT_ATTEMPTS,
+            ),
+        };
+
+        Self {
+            free_attempts,
+            lockout_attempts,
+            base_delay: config().LOGIN_THROTTLE_BASE_DELAY,
+            max_delay: config().LOGIN_THROTTLE_MAX_DELAY,
+            lockout_duration: config().LOGIN_LOCKOUT_DURATION,
+            window: config().LOGIN_THROTTLE_WINDOW,
+        }
+    }
+
+    /// Returns the seconds the login is blocked after the given number of failed attempts
+    pub fn delay(&self, attempts: u64) -> u64 {
+        if attempts >= self.lockout_attempts {
+            return self.lockout_duration;
+        }
+        if attempts <= self.free_attempts {
+            return 0;
+        }
+
+        let exponent = (attempts - self.free_attempts - 1).min(63) as u32;
+        self.base_delay
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_delay)
+    }
+}
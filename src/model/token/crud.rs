@@ -0,0 +1,117 @@
+use redis::cmd;
+use tracing::instrument;
+
+use crate::error::{Error, Result};
+use crate::model::session::SessionDb;
+use crate::utils;
+
+use super::{token_key, TokenKind};
+
+/// Create a new single-use token in the session db
+/// Returns the token
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `kind` - The kind of the token
+/// * `value` - The value to store with the token
+/// * `expiration` - The expiration time of the token in seconds
+#[instrument(name = "token::crud::create", skip_all, fields(db.system = "redis"))]
+pub async fn create(
+    session_db: SessionDb,
+    kind: TokenKind,
+    value: String,
+    expiration: u64,
+) -> Result<String> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // generate random token
+    let token = utils::generate_token();
+
+    // save in the db
+    cmd("SET")
+        .arg(&[
+            token_key(kind, &token),
+            value,
+            "EX".to_string(),
+            expiration.to_string(),
+        ])
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    Ok(token)
+}
+
+/// Get a token from the session db without deleting it
+/// Returns the value stored with the token, None if the token does not exist or is expired
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `kind` - The kind of the token
+/// * `token` - The token
+#[instrument(name = "token::crud::get", skip_all, fields(db.system = "redis"))]
+pub async fn get(session_db: SessionDb, kind: TokenKind, token: String) -> Result<Option<String>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    let value = cmd("GET")
+        .arg(&[token_key(kind, &token)])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok(value)
+}
+
+/// Get and delete a token from the session db, so that it can be used only once
+/// Returns the value stored with the token, None if the token does not exist or is expired
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `kind` - The kind of the token
+/// * `token` - The token
+#[instrument(name = "token::crud::consume", skip_all, fields(db.system = "redis"))]
+pub async fn consume(
+    session_db: SessionDb,
+    kind: TokenKind,
+    token: String,
+) -> Result<Option<String>> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // get the value and delete the key in a single command
+    let value = cmd("GETDEL")
+        .arg(&[token_key(kind, &token)])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok(value)
+}
+
+/// Mark a token chosen by the caller as used, so that it can't be used again before it expires
+/// Returns false if the token had already been used
+/// # Arguments
+/// * `session_db` - The session db connection pool
+/// * `kind` - The kind of the token
+/// * `token` - The token
+/// * `expiration` - How long the token is remembered in seconds
+#[instrument(name = "token::crud::claim", skip_all, fields(db.system = "redis"))]
+pub async fn claim(
+    session_db: SessionDb,
+    kind: TokenKind,
+    token: String,
+    expiration: u64,
+) -> Result<bool> {
+    // get connection to session db
+    let mut session_db_conn = session_db.get().await.map_err(Error::RedisPool)?;
+
+    // set the key only if it does not exist yet
+    let res: Option<String> = cmd("SET")
+        .arg(&[
+            token_key(kind, &token),
+            "1".to_string(),
+            "NX".to_string(),
+            "EX".to_string(),
+            expiration.to_string(),
+        ])
+        .query_async(&mut session_db_conn)
+        .await?;
+
+    Ok(res.is_some())
+}
@@ -0,0 +1,24 @@
+use strum_macros::AsRefStr;
+
+use crate::utils;
+
+pub mod crud;
+
+/// The kind of a single-use token, used as prefix for the key in the session db
+#[derive(Clone, Copy, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum TokenKind {
+    EmailVerification,
+    PasswordReset,
+    MfaChallenge,
+    TotpCode,
+    OrganizationInvitation,
+    RefreshToken,
+    RefreshTokenUsed,
+}
+
+/// Returns the key of the token in the session db
+/// Only the hash of the token is stored, so a leak of the session db does not leak valid tokens
+pub fn token_key(kind: TokenKind, token: &str) -> String {
+    format!("{}:{}", kind.as_ref(), utils::hash_token(token))
+}
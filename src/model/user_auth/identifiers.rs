@@ -0,0 +1,83 @@
+//! Validation and normalization of the usernames and emails
+//! Usernames and emails are stored as NFKC normalized, while their lowercase form (the canonical
+//! form) is used for the unique indexes and the lookups, so that Bob@x.com and bob@x.com are the
+//! same account
+
+use email_address::EmailAddress;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+// length of the username and email columns
+const MAX_COLUMN_LEN: usize = 255;
+
+const USERNAME_SYMBOLS: [char; 3] = ['.', '_', '-'];
+
+/// Returns the NFKC normalized value without leading and trailing whitespaces
+pub fn normalize(value: &str) -> String {
+    value.trim().nfkc().collect()
+}
+
+/// Returns the form used to compare usernames and emails
+/// It is the unicode lowercase, not the full case folding (e.g. ß and SS stay different), the
+/// same mapping as lower() with an ICU collation that computed the existing ones in Postgres
+pub fn canonical(value: &str) -> String {
+    normalize(value).to_lowercase()
+}
+
+/// Checks a normalized username against the username policy from the config
+pub fn validate_username(username: &str) -> Result<()> {
+    let len = username.chars().count();
+    let min = config().USERNAME_MIN_LENGTH;
+    let max = config().USERNAME_MAX_LENGTH.min(MAX_COLUMN_LEN);
+
+    if len < min {
+        return Err(Error::UsernameInvalid(format!(
+            "must be at least {min} characters long"
+        )));
+    }
+    if len > max {
+        return Err(Error::UsernameInvalid(format!(
+            "must be at most {max} characters long"
+        )));
+    }
+
+    if !username
+        .chars()
+        .all(|c| c.is_alphanumeric() || USERNAME_SYMBOLS.contains(&c))
+    {
+        return Err(Error::UsernameInvalid(
+            "can only contain letters, digits, '.', '_' and '-'".to_string(),
+        ));
+    }
+
+    let starts_and_ends_alphanumeric = username.chars().next().is_some_and(char::is_alphanumeric)
+        && username.chars().last().is_some_and(char::is_alphanumeric);
+    if !starts_and_ends_alphanumeric {
+        return Err(Error::UsernameInvalid(
+            "must start and end with a letter or a digit".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that a normalized email is a valid address (RFC 5322)
+pub fn validate_email(email: &str) -> Result<()> {
+    if email.len() > MAX_COLUMN_LEN {
+        return Err(Error::EmailInvalid(format!(
+            "must be at most {MAX_COLUMN_LEN} bytes long"
+        )));
+    }
+
+    if !EmailAddress::is_valid(email) {
+        return Err(Error::EmailInvalid(
+            "is not a valid email address".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -12,7 +12,9 @@ use crate::{
 
 use super::iterable::{Iterable, IterableType};
 
+pub mod identifiers;
 pub mod model_controller;
+pub mod password_policy;
 
 // region: UserAuth
 
@@ -27,6 +29,8 @@ pub struct UserAuth {
     pub username: String,
     pub email: String,
     pub password: String,
+    pub username_normalized: String,
+    pub email_normalized: String,
 }
 
 impl Default for UserAuth {
@@ -41,18 +45,23 @@ impl Default for UserAuth {
             username: "".to_string(),
             email: "".to_string(),
             password: "".to_string(),
+            username_normalized: "".to_string(),
+            email_normalized: "".to_string(),
         }
     }
 }
 
 impl UserAuth {
     pub fn new(ua_fc: UserAuthForCreate) -> Result<UserAuth> {
+        let ua_fc = ua_fc.normalize();
         UserAuthForCreate::validate(&ua_fc)?;
 
         // Hash password
         let ua_fc = ua_fc.hash_password()?;
 
         Ok(UserAuth {
+            username_normalized: identifiers::canonical(&ua_fc.username),
+            email_normalized: identifiers::canonical(&ua_fc.email),
             username: ua_fc.username,
             email: ua_fc.email,
             password: ua_fc.password,
@@ -73,6 +82,8 @@ impl Iterable for UserAuth {
             "username".to_string(),
             "email".to_string(),
             "password".to_string(),
+            "username_normalized".to_string(),
+            "email_normalized".to_string(),
         ];
         let fields_values = vec![
             IterableType::Uuid(self.id),
@@ -84,6 +95,8 @@ impl Iterable for UserAuth {
             IterableType::String(self.username.to_string()),
             IterableType::String(self.email.to_string()),
             IterableType::String(self.password.to_string()),
+            IterableType::String(self.username_normalized.to_string()),
+            IterableType::String(self.email_normalized.to_string()),
         ];
 
         (fields_names, fields_values)
@@ -101,8 +114,16 @@ pub struct UserAuthForCreate {
     pub password: String,
 }
 
-// TODO: Implement email validation
 impl UserAuthForCreate {
+    /// Normalizes the username and the email (NFKC, without surrounding whitespaces)
+    pub fn normalize(self) -> Self {
+        Self {
+            username: identifiers::normalize(&self.username),
+            email: identifiers::normalize(&self.email),
+            ..self
+        }
+    }
+
     pub fn validate(ua_fc: &UserAuthForCreate) -> Result<()> {
         if ua_fc.username.is_empty() {
             return Err(Error::UsernameNotSet);
@@ -116,6 +137,11 @@ impl UserAuthForCreate {
             return Err(Error::PasswordNotSet);
         }
 
+        identifiers::validate_username(&ua_fc.username)?;
+        identifiers::validate_email(&ua_fc.email)?;
+
+        password_policy::check(&ua_fc.password, &ua_fc.username, &ua_fc.email)?;
+
         Ok(())
     }
 
@@ -193,34 +219,38 @@ impl Iterable for UserAuthForUpdate {
         fields_names.push("updated_at".to_string());
         fields_values.push(IterableType::DateTime(self.updated_at));
 
-        if self.last_login.is_some() {
+        if let Some(last_login) = self.last_login {
             fields_names.push("last_login".to_string());
-            fields_values.push(IterableType::DateTime(self.last_login.unwrap()));
+            fields_values.push(IterableType::DateTime(last_login));
         }
 
-        if self.needs_verify.is_some() {
+        if let Some(needs_verify) = self.needs_verify {
             fields_names.push("needs_verify".to_string());
-            fields_values.push(IterableType::Bool(self.needs_verify.unwrap()));
+            fields_values.push(IterableType::Bool(needs_verify));
         }
 
-        if self.is_blocked.is_some() {
+        if let Some(is_blocked) = self.is_blocked {
             fields_names.push("is_blocked".to_string());
-            fields_values.push(IterableType::Bool(self.is_blocked.unwrap()));
+            fields_values.push(IterableType::Bool(is_blocked));
         }
 
-        if self.username.is_some() {
+        if let Some(username) = &self.username {
             fields_names.push("username".to_string());
-            fields_values.push(IterableType::String(self.username.clone().unwrap()));
+            fields_values.push(IterableType::String(identifiers::normalize(username)));
+            fields_names.push("username_normalized".to_string());
+            fields_values.push(IterableType::String(identifiers::canonical(username)));
         }
 
-        if self.email.is_some() {
+        if let Some(email) = &self.email {
             fields_names.push("email".to_string());
-            fields_values.push(IterableType::String(self.email.clone().unwrap()));
+            fields_values.push(IterableType::String(identifiers::normalize(email)));
+            fields_names.push("email_normalized".to_string());
+            fields_values.push(IterableType::String(identifiers::canonical(email)));
         }
 
-        if self.password.is_some() {
+        if let Some(password) = &self.password {
             fields_names.push("password".to_string());
-            fields_values.push(IterableType::String(self.password.clone().unwrap()));
+            fields_values.push(IterableType::String(password.clone()));
         }
 
         (fields_names, fields_values)
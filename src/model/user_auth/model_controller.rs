@@ -1,11 +1,21 @@
+use chrono::{DateTime, Duration, Utc};
 use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::model::iterable::IterableType;
-use crate::model::{session, ModelManager};
-use crate::{error::Result, model::db};
-
-use super::{UserAuth, UserAuthForCreate, UserAuthForUpdate};
+use crate::model::throttle::{self, LoginAttempt, ThrottlePolicy, ThrottleScope};
+use crate::model::token::{self, TokenKind};
+use crate::model::{
+    session::{self, Session},
+    ModelManager,
+};
+use crate::{
+    error::{Error, Result},
+    model::db,
+    utils,
+};
+
+use super::{identifiers, UserAuth, UserAuthForCreate, UserAuthForUpdate};
 
 const TABLE_NAME: &str = "users_auth";
 
@@ -15,7 +25,11 @@ impl UserAuthBmc {
     // region: Db CRUD operations
 
     pub async fn create(model_manager: &ModelManager, ua_fc: UserAuthForCreate) -> Result<Uuid> {
-        let user_auth = UserAuth::new(ua_fc)?;
+        // newly registered users have to verify their email before they can login
+        let user_auth = UserAuth {
+            needs_verify: true,
+            ..utils::spawn_blocking(move || UserAuth::new(ua_fc)).await?
+        };
 
         let res = db::crud::create(model_manager.db().clone(), TABLE_NAME, user_auth).await?;
 
@@ -32,6 +46,7 @@ impl UserAuthBmc {
         Ok(user_auth)
     }
 
+    /// Returns the user with the username, regardless of its case and unicode form
     pub async fn get_from_username(
         model_manager: &ModelManager,
         username: String,
@@ -39,8 +54,8 @@ impl UserAuthBmc {
         let res = db::crud::get_one_by_field(
             model_manager.db().clone(),
             TABLE_NAME,
-            "username",
-            IterableType::String(username),
+            "username_normalized",
+            IterableType::String(identifiers::canonical(&username)),
         )
         .await?;
 
@@ -49,15 +64,13 @@ impl UserAuthBmc {
         Ok(user_auth)
     }
 
-    pub async fn get_from_email(
-        model_manager: &ModelManager,
-        username: String,
-    ) -> Result<UserAuth> {
+    /// Returns the user with the email, regardless of its case and unicode form
+    pub async fn get_from_email(model_manager: &ModelManager, email: String) -> Result<UserAuth> {
         let res = db::crud::get_one_by_field(
             model_manager.db().clone(),
             TABLE_NAME,
-            "email",
-            IterableType::String(username),
+            "email_normalized",
+            IterableType::String(identifiers::canonical(&email)),
         )
         .await?;
 
@@ -100,11 +113,11 @@ impl UserAuthBmc {
 
     pub async fn create_session(
         model_manager: &ModelManager,
-        value: String,
+        session: Session,
         expiration: u64,
     ) -> Result<String> {
         let res =
-            session::crud::create(model_manager.session_db().clone(), value, expiration).await?;
+            session::crud::create(model_manager.session_db().clone(), session, expiration).await?;
 
         Ok(res)
     }
@@ -112,17 +125,184 @@ impl UserAuthBmc {
     pub async fn get_session(
         model_manager: &ModelManager,
         session_id: String,
-    ) -> Result<(String, String)> {
+    ) -> Result<(String, Session)> {
         let res = session::crud::get(model_manager.session_db().clone(), session_id).await?;
 
         Ok(res)
     }
 
+    /// Updates the last_seen_at of the session and extends it by its idle timeout (without
+    /// going past its absolute expiration)
+    /// Returns the updated session and when it expires (None if unknown)
+    /// Returns SessionNotFound and deletes the session if it has no second left to live
+    pub async fn touch_session(
+        model_manager: &ModelManager,
+        session_id: String,
+        session: Session,
+    ) -> Result<(Session, Option<DateTime<Utc>>)> {
+        let now = Utc::now();
+        let session = Session {
+            last_seen_at: now,
+            ..session
+        };
+        let ttl = session.ttl(now);
+
+        // the session expires in less than a second, it can't be extended (redis rejects a 0
+        // expiration)
+        if ttl == Some(0) {
+            session::crud::delete(model_manager.session_db().clone(), session_id).await?;
+            return Err(Error::SessionNotFound);
+        }
+
+        session::crud::update(
+            model_manager.session_db().clone(),
+            session_id.clone(),
+            session.clone(),
+            ttl,
+        )
+        .await?;
+
+        // sessions without a lifetime keep their expiration, read it from the db
+        let ttl = match ttl {
+            Some(ttl) => Some(ttl),
+            None => session::crud::ttl(model_manager.session_db().clone(), session_id).await?,
+        };
+        let expires_at = ttl.map(|ttl| now + Duration::seconds(ttl as i64));
+
+        Ok((session, expires_at))
+    }
+
     pub async fn delete_session(model_manager: &ModelManager, session_id: String) -> Result<()> {
         session::crud::delete(model_manager.session_db().clone(), session_id).await?;
 
         Ok(())
     }
 
-    // endregion: Db CRUD operations
+    pub async fn list_sessions(
+        model_manager: &ModelManager,
+        id: Uuid,
+    ) -> Result<Vec<(String, Session)>> {
+        let res = session::crud::list(model_manager.session_db().clone(), id.to_string()).await?;
+
+        Ok(res)
+    }
+
+    /// Deletes all the sessions of the user except `keep_session_id` (if set)
+    /// Returns the number of deleted sessions
+    pub async fn delete_all_sessions(
+        model_manager: &ModelManager,
+        id: Uuid,
+        keep_session_id: Option<String>,
+    ) -> Result<u64> {
+        let res = session::crud::delete_all(
+            model_manager.session_db().clone(),
+            id.to_string(),
+            keep_session_id,
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    // endregion: Session Db CRUD operations
+
+    // region: Throttle Db operations
+
+    /// Returns the seconds left before a new login attempt is allowed, None if it is not blocked
+    pub async fn login_blocked_for(
+        model_manager: &ModelManager,
+        scope: ThrottleScope,
+        id: String,
+    ) -> Result<Option<u64>> {
+        let res =
+            throttle::crud::blocked_for(model_manager.session_db().clone(), scope, id).await?;
+
+        Ok(res)
+    }
+
+    /// Counts a login attempt as failed until it is undone, unless the login is blocked
+    pub async fn count_login_attempt(
+        model_manager: &ModelManager,
+        scope: ThrottleScope,
+        id: String,
+    ) -> Result<LoginAttempt> {
+        let res = throttle::crud::count_attempt(
+            model_manager.session_db().clone(),
+            scope,
+            id,
+            &ThrottlePolicy::for_scope(scope),
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Undoes a counted login attempt that has succeeded
+    pub async fn undo_login_attempt(
+        model_manager: &ModelManager,
+        scope: ThrottleScope,
+        id: String,
+        attempts: u64,
+    ) -> Result<()> {
+        throttle::crud::undo_attempt(model_manager.session_db().clone(), scope, id, attempts)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_login_failures(
+        model_manager: &ModelManager,
+        scope: ThrottleScope,
+        id: String,
+    ) -> Result<()> {
+        throttle::crud::clear(model_manager.session_db().clone(), scope, id).await?;
+
+        Ok(())
+    }
+
+    // endregion: Throttle Db operations
+
+    // region: Token Db CRUD operations
+
+    pub async fn create_token(
+        model_manager: &ModelManager,
+        kind: TokenKind,
+        id: Uuid,
+        expiration: u64,
+    ) -> Result<String> {
+        let res = token::crud::create(
+            model_manager.session_db().clone(),
+            kind,
+            id.to_string(),
+            expiration,
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Returns the id of the user the token was created for without consuming the token, None if
+    /// the token is not valid
+    pub async fn get_token(
+        model_manager: &ModelManager,
+        kind: TokenKind,
+        token: String,
+    ) -> Result<Option<String>> {
+        let res = token::crud::get(model_manager.session_db().clone(), kind, token).await?;
+
+        Ok(res)
+    }
+
+    /// Returns the id of the user the token was created for, None if the token is not valid
+    pub async fn consume_token(
+        model_manager: &ModelManager,
+        kind: TokenKind,
+        token: String,
+    ) -> Result<Option<String>> {
+        let res = token::crud::consume(model_manager.session_db().clone(), kind, token).await?;
+
+        Ok(res)
+    }
+
+    // endregion: Token Db CRUD operations
 }
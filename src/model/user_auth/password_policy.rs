@@ -0,0 +1,106 @@
+use serde::Serialize;
+use strum_macros::AsRefStr;
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+// identifiers shorter than this are too common to be banned from the passwords
+const MIN_BANNED_SUBSTRING_LEN: usize = 3;
+
+/// A rule of the password policy that a password does not respect
+#[derive(Clone, Debug, PartialEq, Serialize, AsRefStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum PasswordViolation {
+    TooShort { min: usize },
+    TooLong { max: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    ContainsUsername,
+    ContainsEmail,
+    Denylisted,
+}
+
+impl PasswordViolation {
+    /// Message that can be shown to the user
+    pub fn description(&self) -> String {
+        match self {
+            Self::TooShort { min } => format!("must be at least {min} characters long"),
+            Self::TooLong { max } => format!("must be at most {max} characters long"),
+            Self::MissingLowercase => "must contain a lowercase letter".to_string(),
+            Self::MissingUppercase => "must contain an uppercase letter".to_string(),
+            Self::MissingDigit => "must contain a digit".to_string(),
+            Self::MissingSymbol => "must contain a symbol".to_string(),
+            Self::ContainsUsername => "must not contain the username".to_string(),
+            Self::ContainsEmail => "must not contain the email".to_string(),
+            Self::Denylisted => "is too common".to_string(),
+        }
+    }
+}
+
+/// Checks a password against the policy from the config
+/// Returns all the rules the password does not respect as Error::PasswordPolicy
+pub fn check(password: &str, username: &str, email: &str) -> Result<()> {
+    let violations = violations(password, username, email);
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::PasswordPolicy(violations))
+    }
+}
+
+fn violations(password: &str, username: &str, email: &str) -> Vec<PasswordViolation> {
+    let mut violations = Vec::new();
+
+    // length (in characters, not bytes)
+    let len = password.chars().count();
+    if len < config().PASSWORD_MIN_LENGTH {
+        violations.push(PasswordViolation::TooShort {
+            min: config().PASSWORD_MIN_LENGTH,
+        });
+    }
+    if len > config().PASSWORD_MAX_LENGTH {
+        violations.push(PasswordViolation::TooLong {
+            max: config().PASSWORD_MAX_LENGTH,
+        });
+    }
+
+    // character classes
+    if config().PASSWORD_REQUIRE_LOWERCASE && !password.chars().any(char::is_lowercase) {
+        violations.push(PasswordViolation::MissingLowercase);
+    }
+    if config().PASSWORD_REQUIRE_UPPERCASE && !password.chars().any(char::is_uppercase) {
+        violations.push(PasswordViolation::MissingUppercase);
+    }
+    if config().PASSWORD_REQUIRE_DIGIT && !password.chars().any(|c| c.is_ascii_digit()) {
+        violations.push(PasswordViolation::MissingDigit);
+    }
+    if config().PASSWORD_REQUIRE_SYMBOL && password.chars().all(char::is_alphanumeric) {
+        violations.push(PasswordViolation::MissingSymbol);
+    }
+
+    // user identifiers (case insensitive)
+    let password_lowercase = password.to_lowercase();
+    let contains = |s: &str| {
+        s.chars().count() >= MIN_BANNED_SUBSTRING_LEN
+            && password_lowercase.contains(&s.to_lowercase())
+    };
+    if contains(username) {
+        violations.push(PasswordViolation::ContainsUsername);
+    }
+    let email_local_part = email.split('@').next().unwrap_or_default();
+    if contains(email_local_part) {
+        violations.push(PasswordViolation::ContainsEmail);
+    }
+
+    // common or breached passwords
+    if config().PASSWORD_DENYLIST.contains(&password_lowercase) {
+        violations.push(PasswordViolation::Denylisted);
+    }
+
+    violations
+}
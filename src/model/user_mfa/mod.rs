@@ -0,0 +1,293 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use totp_rs::{Algorithm, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+    utils,
+};
+
+use super::iterable::{Iterable, IterableType};
+
+pub mod model_controller;
+
+// TOTP parameters (RFC 6238 defaults, supported by every authenticator app)
+const TOTP_DIGITS: usize = 6;
+const TOTP_SKEW: u8 = 1;
+const TOTP_STEP: u64 = 30;
+
+/// Seconds during which a TOTP code is accepted (the current step plus the skew on both sides)
+pub const TOTP_CODE_LIFETIME: u64 = TOTP_STEP * (TOTP_SKEW as u64 * 2 + 1);
+
+// Recovery codes are 10 characters from the base32 alphabet (50 bits), shown as xxxxx-xxxxx
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+const RECOVERY_CODE_LEN: usize = 10;
+
+/// Generates a new random TOTP secret (160 bits, as recommended by RFC 4226)
+pub fn generate_totp_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 20];
+    OsRng.fill_bytes(&mut secret);
+
+    secret
+}
+
+/// Returns the TOTP generator for the secret, the account_name is shown in the authenticator app
+pub fn new_totp(secret: Vec<u8>, account_name: String) -> Result<TOTP> {
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW,
+        TOTP_STEP,
+        secret,
+        Some(config().MFA_ISSUER.clone()),
+        account_name,
+    )
+    .map_err(|e| Error::Totp(e.to_string()))
+}
+
+/// Generates a batch of random recovery codes
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let code: String = (0..RECOVERY_CODE_LEN)
+                .map(|_| {
+                    let i = OsRng.next_u32() as usize % RECOVERY_CODE_ALPHABET.len();
+                    RECOVERY_CODE_ALPHABET[i] as char
+                })
+                .collect();
+            let (left, right) = code.split_at(RECOVERY_CODE_LEN / 2);
+
+            format!("{left}-{right}")
+        })
+        .collect()
+}
+
+/// Removes the formatting of a recovery code typed by the user (case, spaces and dashes)
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Returns the keyed hash that finds a recovery code of the user without verifying the others
+pub fn recovery_code_lookup_hash(user_id: Uuid, code: &str) -> Result<String> {
+    utils::keyed_hash(
+        "recovery_code",
+        &format!("{user_id}:{}", normalize_recovery_code(code)),
+    )
+}
+
+// region: UserMfa
+
+/// The second factor of a user, the TOTP secret is encrypted at rest
+/// The secret is stored at enrollment and used only after the user confirms it (totp_enabled)
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct UserMfa {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub totp_secret: String,
+    pub totp_enabled: bool,
+}
+
+impl Default for UserMfa {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            totp_secret: "".to_string(),
+            totp_enabled: false,
+        }
+    }
+}
+
+impl UserMfa {
+    pub fn new(id: Uuid, totp_secret: &[u8]) -> Result<UserMfa> {
+        Ok(UserMfa {
+            id,
+            totp_secret: utils::encrypt_secret(totp_secret)?,
+            ..Default::default()
+        })
+    }
+
+    /// Checks a TOTP code against the secret of the user
+    pub fn check_totp(&self, code: &str) -> Result<bool> {
+        let secret = utils::decrypt_secret(&self.totp_secret)?;
+        let totp = new_totp(secret, "".to_string())?;
+
+        let valid = totp
+            .check_current(code)
+            .map_err(|e| Error::Totp(e.to_string()))?;
+
+        Ok(valid)
+    }
+}
+
+impl Iterable for UserMfa {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let fields_names = vec![
+            "id".to_string(),
+            "created_at".to_string(),
+            "updated_at".to_string(),
+            "totp_secret".to_string(),
+            "totp_enabled".to_string(),
+        ];
+        let fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::DateTime(self.created_at),
+            IterableType::DateTime(self.updated_at),
+            IterableType::String(self.totp_secret.to_string()),
+            IterableType::Bool(self.totp_enabled),
+        ];
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: UserMfa
+
+// region: UserMfaForUpdate
+
+pub struct UserMfaForUpdate {
+    pub updated_at: DateTime<Utc>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: Option<bool>,
+}
+
+impl Default for UserMfaForUpdate {
+    fn default() -> Self {
+        Self {
+            updated_at: chrono::Utc::now(),
+            totp_secret: None,
+            totp_enabled: None,
+        }
+    }
+}
+
+impl UserMfaForUpdate {
+    pub fn new() -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+}
+
+impl Iterable for UserMfaForUpdate {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let mut fields_names = Vec::new();
+        let mut fields_values = Vec::new();
+
+        fields_names.push("updated_at".to_string());
+        fields_values.push(IterableType::DateTime(self.updated_at));
+
+        if let Some(totp_secret) = &self.totp_secret {
+            fields_names.push("totp_secret".to_string());
+            fields_values.push(IterableType::String(totp_secret.clone()));
+        }
+
+        if let Some(totp_enabled) = self.totp_enabled {
+            fields_names.push("totp_enabled".to_string());
+            fields_values.push(IterableType::Bool(totp_enabled));
+        }
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: UserMfaForUpdate
+
+// region: RecoveryCode
+
+/// A one-time code that can be used in place of a TOTP code, only its argon2 hash is stored
+/// The keyed lookup hash finds the code to verify, so that a check runs a single argon2
+/// verification (it is None for the codes created before it)
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct RecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub code_hash: String,
+    pub lookup_hash: Option<String>,
+}
+
+impl Default for RecoveryCode {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            created_at: chrono::Utc::now(),
+            code_hash: "".to_string(),
+            lookup_hash: None,
+        }
+    }
+}
+
+impl RecoveryCode {
+    pub fn new(user_id: Uuid, code: &str) -> Result<RecoveryCode> {
+        Ok(RecoveryCode {
+            user_id,
+            code_hash: utils::hash_password(normalize_recovery_code(code))?,
+            lookup_hash: Some(recovery_code_lookup_hash(user_id, code)?),
+            ..Default::default()
+        })
+    }
+
+    /// Returns true if the code has to be verified against the hash: its lookup hash matches or
+    /// the code has been created without one
+    pub fn may_match(&self, lookup_hash: &str) -> bool {
+        self.lookup_hash
+            .as_ref()
+            .is_none_or(|h| h.as_str() == lookup_hash)
+    }
+
+    /// Checks a code typed by the user against the hash
+    pub fn matches(&self, code: &str) -> bool {
+        utils::verify_password(normalize_recovery_code(code), self.code_hash.clone()).is_ok()
+    }
+}
+
+impl Iterable for RecoveryCode {
+    fn get_fields(&self) -> (Vec<String>, Vec<IterableType>) {
+        let mut fields_names = vec![
+            "id".to_string(),
+            "user_id".to_string(),
+            "created_at".to_string(),
+            "code_hash".to_string(),
+        ];
+        let mut fields_values = vec![
+            IterableType::Uuid(self.id),
+            IterableType::Uuid(self.user_id),
+            IterableType::DateTime(self.created_at),
+            IterableType::String(self.code_hash.to_string()),
+        ];
+
+        if let Some(lookup_hash) = &self.lookup_hash {
+            fields_names.push("lookup_hash".to_string());
+            fields_values.push(IterableType::String(lookup_hash.clone()));
+        }
+
+        (fields_names, fields_values)
+    }
+}
+
+// endregion: RecoveryCode
+
+// region: MfaChallenge
+
+/// Stored with the challenge returned by Login when the user has MFA enabled, until the login
+/// is completed with the second factor
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MfaChallenge {
+    pub user_id: String,
+    pub remember_me: bool,
+    #[serde(default)]
+    pub issue_access_token: bool,
+}
+
+// endregion: MfaChallenge
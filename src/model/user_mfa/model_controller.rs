@@ -0,0 +1,245 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::error::{Error, Result};
+use crate::model::iterable::IterableType;
+use crate::model::token::{self, TokenKind};
+use crate::model::{db, ModelManager};
+use crate::utils;
+
+use super::{
+    generate_recovery_codes, recovery_code_lookup_hash, MfaChallenge, RecoveryCode, UserMfa,
+    UserMfaForUpdate, TOTP_CODE_LIFETIME,
+};
+
+const TABLE_NAME: &str = "users_mfa";
+const RECOVERY_CODES_TABLE_NAME: &str = "users_mfa_recovery_codes";
+
+pub struct UserMfaBmc;
+
+impl UserMfaBmc {
+    // region: Db CRUD operations
+
+    pub async fn create(model_manager: &ModelManager, user_mfa: UserMfa) -> Result<Uuid> {
+        let res = db::crud::create(model_manager.db().clone(), TABLE_NAME, user_mfa).await?;
+
+        let user_mfa_created = UserMfa::from_row(&res)?;
+
+        Ok(user_mfa_created.id)
+    }
+
+    pub async fn get(model_manager: &ModelManager, id: Uuid) -> Result<UserMfa> {
+        let res = db::crud::get_one_by_id(model_manager.db().clone(), TABLE_NAME, id).await?;
+
+        let user_mfa = UserMfa::from_row(&res)?;
+
+        Ok(user_mfa)
+    }
+
+    pub async fn update(
+        model_manager: &ModelManager,
+        um_fu: UserMfaForUpdate,
+        id: Uuid,
+    ) -> Result<()> {
+        db::crud::update_by_id(model_manager.db().clone(), TABLE_NAME, um_fu, id).await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(model_manager: &ModelManager, id: Uuid) -> Result<()> {
+        db::crud::delete_by_id(model_manager.db().clone(), TABLE_NAME, id).await?;
+
+        Ok(())
+    }
+
+    /// Returns true if the user has confirmed a second factor
+    pub async fn is_enabled(model_manager: &ModelManager, id: Uuid) -> Result<bool> {
+        match Self::get(model_manager, id).await {
+            Ok(user_mfa) => Ok(user_mfa.totp_enabled),
+            Err(Error::Sqlx(sqlx::Error::RowNotFound)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // endregion: Db CRUD operations
+
+    // region: Recovery codes Db CRUD operations
+
+    /// Deletes the recovery codes of the user and creates a new batch
+    /// Returns the new codes, they are stored hashed so this is the only time they can be read
+    pub async fn replace_recovery_codes(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+    ) -> Result<Vec<String>> {
+        let codes = generate_recovery_codes(config().MFA_RECOVERY_CODES_COUNT);
+        let hashed_codes = codes.clone();
+        let recovery_codes = utils::spawn_blocking(move || {
+            hashed_codes
+                .iter()
+                .map(|code| RecoveryCode::new(user_id, code))
+                .collect::<Result<Vec<_>>>()
+        })
+        .await?;
+
+        // the old codes stay valid if the new ones can't be stored
+        let mut tx = model_manager.db().begin().await?;
+        db::crud::delete_by_field_in_tx(
+            &mut tx,
+            RECOVERY_CODES_TABLE_NAME,
+            "user_id",
+            IterableType::Uuid(user_id),
+        )
+        .await?;
+        for recovery_code in recovery_codes {
+            db::crud::create_in_tx(&mut tx, RECOVERY_CODES_TABLE_NAME, recovery_code).await?;
+        }
+        tx.commit().await?;
+
+        Ok(codes)
+    }
+
+    pub async fn get_recovery_codes(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+    ) -> Result<Vec<RecoveryCode>> {
+        let res = db::crud::get_all_by_field(
+            model_manager.db().clone(),
+            RECOVERY_CODES_TABLE_NAME,
+            "user_id",
+            IterableType::Uuid(user_id),
+        )
+        .await?;
+
+        let mut recovery_codes = Vec::new();
+        for recovery_code in res {
+            let rc = RecoveryCode::from_row(&recovery_code)?;
+            recovery_codes.push(rc);
+        }
+
+        Ok(recovery_codes)
+    }
+
+    /// Checks a recovery code of the user and deletes it, so that it can be used only once
+    /// Returns false if the code is not valid (or has already been used)
+    pub async fn use_recovery_code(
+        model_manager: &ModelManager,
+        user_id: Uuid,
+        code: String,
+    ) -> Result<bool> {
+        // only the code with the same lookup hash is verified (and the codes without one)
+        let lookup_hash = recovery_code_lookup_hash(user_id, &code)?;
+        let recovery_codes: Vec<RecoveryCode> = Self::get_recovery_codes(model_manager, user_id)
+            .await?
+            .into_iter()
+            .filter(|rc| rc.may_match(&lookup_hash))
+            .collect();
+        let recovery_code = utils::spawn_blocking(move || {
+            Ok(recovery_codes.into_iter().find(|rc| rc.matches(&code)))
+        })
+        .await?;
+        let Some(recovery_code) = recovery_code else {
+            return Ok(false);
+        };
+
+        // if the code has been deleted in the meantime it has just been used by someone else
+        match db::crud::delete_by_id(
+            model_manager.db().clone(),
+            RECOVERY_CODES_TABLE_NAME,
+            recovery_code.id,
+        )
+        .await
+        {
+            Ok(()) => Ok(true),
+            Err(Error::SqlxEntityNotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the number of deleted codes
+    pub async fn delete_recovery_codes(model_manager: &ModelManager, user_id: Uuid) -> Result<u64> {
+        let res = db::crud::delete_by_field(
+            model_manager.db().clone(),
+            RECOVERY_CODES_TABLE_NAME,
+            "user_id",
+            IterableType::Uuid(user_id),
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    // endregion: Recovery codes Db CRUD operations
+
+    // region: Session Db CRUD operations
+
+    /// Checks a TOTP code of the user, a valid code is accepted only once
+    pub async fn verify_totp(
+        model_manager: &ModelManager,
+        user_mfa: &UserMfa,
+        code: String,
+    ) -> Result<bool> {
+        if !user_mfa.check_totp(&code)? {
+            return Ok(false);
+        }
+
+        // remember the code while it is still valid, so that it can't be replayed
+        let res = token::crud::claim(
+            model_manager.session_db().clone(),
+            TokenKind::TotpCode,
+            format!("{}:{}", user_mfa.id, code),
+            TOTP_CODE_LIFETIME,
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn create_challenge(
+        model_manager: &ModelManager,
+        challenge: MfaChallenge,
+    ) -> Result<String> {
+        let res = token::crud::create(
+            model_manager.session_db().clone(),
+            TokenKind::MfaChallenge,
+            serde_json::to_string(&challenge)?,
+            config().MFA_CHALLENGE_EXPIRATION,
+        )
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Returns the challenge without consuming it, None if the challenge is not valid
+    pub async fn get_challenge(
+        model_manager: &ModelManager,
+        challenge: String,
+    ) -> Result<Option<MfaChallenge>> {
+        let res = token::crud::get(
+            model_manager.session_db().clone(),
+            TokenKind::MfaChallenge,
+            challenge,
+        )
+        .await?;
+
+        Ok(res.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    /// Returns the challenge and makes sure it can't be used again, None if the challenge is not
+    /// valid
+    pub async fn consume_challenge(
+        model_manager: &ModelManager,
+        challenge: String,
+    ) -> Result<Option<MfaChallenge>> {
+        let res = token::crud::consume(
+            model_manager.session_db().clone(),
+            TokenKind::MfaChallenge,
+            challenge,
+        )
+        .await?;
+
+        Ok(res.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    // endregion: Session Db CRUD operations
+}
@@ -0,0 +1,38 @@
+use tonic::Request;
+
+/// Metadata key with the name of the API client that sends the request
+pub const CLIENT_ID_METADATA_KEY: &str = "x-client-id";
+/// Metadata key with the secret of the API client that sends the request
+pub const CLIENT_SECRET_METADATA_KEY: &str = "x-client-secret";
+
+/// The API client that sent the request, added to the request extensions once authenticated
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthenticatedClient {
+    pub name: String,
+}
+
+impl AuthenticatedClient {
+    /// Returns the API client that sent the request, None if it has not been authenticated
+    pub fn from_request<T>(request: &Request<T>) -> Option<Self> {
+        request.extensions().get::<Self>().cloned()
+    }
+}
+
+/// The name of the RPC called by the request (e.g. Login), taken from the path of the http
+/// request since the interceptors only see the metadata and the extensions
+#[derive(Clone, Debug, PartialEq)]
+pub struct RpcMethod(pub String);
+
+/// Adds the RPC method to the extensions of the http request, to be used as tower layer
+pub fn insert_rpc_method<B>(mut request: http::Request<B>) -> http::Request<B> {
+    let rpc_method = request
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    request.extensions_mut().insert(RpcMethod(rpc_method));
+
+    request
+}
@@ -0,0 +1,40 @@
+use tonic::Request;
+
+use crate::config::config;
+
+/// Information about the client that sent a request, stored in the sessions
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_label: Option<String>,
+}
+
+impl ClientInfo {
+    /// Takes the client info from the request metadata and the remote address
+    /// The x-forwarded-for header is used only if the proxy headers are trusted
+    pub fn from_request<T>(request: &Request<T>) -> Self {
+        let metadata = request.metadata();
+        let get_metadata = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let forwarded_ip = if config().TRUST_PROXY_HEADERS {
+            get_metadata("x-forwarded-for")
+                .and_then(|v| v.split(',').next().map(|ip| ip.trim().to_string()))
+        } else {
+            None
+        };
+        let ip = forwarded_ip.or_else(|| request.remote_addr().map(|a| a.ip().to_string()));
+
+        Self {
+            ip,
+            user_agent: get_metadata("user-agent"),
+            device_label: get_metadata("x-device-label"),
+        }
+    }
+}
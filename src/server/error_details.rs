@@ -0,0 +1,506 @@
+//! The google.rpc error details sent in the grpc-status-details-bin metadata
+//! Only the messages used by the server are defined, see
+//! https://github.com/googleapis/googleapis/blob/master/google/rpc/error_details.proto
+//! Every error returned from an Error carries an ErrorInfo with a stable reason (e.g.
+//! USERNAME_TAKEN) that clients can match on, the messages are meant for humans only
+
+use std::collections::HashMap;
+
+use argon2::password_hash;
+use prost::{bytes::Bytes, Message};
+use prost_types::Any;
+use tonic::{metadata::MetadataValue, Code, Status};
+use tracing::error;
+
+use crate::error::Error;
+
+const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+/// Domain of the ErrorInfo details
+pub const ERROR_DOMAIN: &str = "mandos";
+
+/// Description of the field violation of an invalid role or permission name
+const NAME_DESCRIPTION: &str =
+    "can only contain letters, digits, '.', '_', ':' and '-' (at most 255 characters)";
+
+/// google.rpc.Status
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<Any>,
+}
+
+/// google.rpc.ErrorInfo
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorInfo {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+    #[prost(string, tag = "2")]
+    pub domain: String,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: HashMap<String, String>,
+}
+
+/// google.rpc.BadRequest
+#[derive(Clone, PartialEq, Message)]
+pub struct BadRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub field_violations: Vec<FieldViolation>,
+}
+
+/// google.rpc.BadRequest.FieldViolation
+#[derive(Clone, PartialEq, Message)]
+pub struct FieldViolation {
+    #[prost(string, tag = "1")]
+    pub field: String,
+    #[prost(string, tag = "2")]
+    pub description: String,
+    #[prost(string, tag = "3")]
+    pub reason: String,
+}
+
+/// Returns a status with an ErrorInfo detail, followed by the BadRequest details when there are
+/// field violations
+fn error_status(
+    code: Code,
+    message: &str,
+    reason: &str,
+    metadata: HashMap<String, String>,
+    field_violations: Vec<FieldViolation>,
+) -> Status {
+    let mut details = vec![Any {
+        type_url: ERROR_INFO_TYPE_URL.to_string(),
+        value: ErrorInfo {
+            reason: reason.to_string(),
+            domain: ERROR_DOMAIN.to_string(),
+            metadata,
+        }
+        .encode_to_vec(),
+    }];
+    if !field_violations.is_empty() {
+        details.push(Any {
+            type_url: BAD_REQUEST_TYPE_URL.to_string(),
+            value: BadRequest { field_violations }.encode_to_vec(),
+        });
+    }
+
+    let rpc_status = RpcStatus {
+        code: code as i32,
+        message: message.to_string(),
+        details,
+    };
+
+    Status::with_details(code, message, Bytes::from(rpc_status.encode_to_vec()))
+}
+
+/// Returns an invalid_argument status for a single field, the reason is used both for the
+/// ErrorInfo and for the field violation
+fn invalid_field(message: &str, field: &str, description: String, reason: &str) -> Status {
+    bad_request(
+        message,
+        reason,
+        vec![FieldViolation {
+            field: field.to_string(),
+            description,
+            reason: reason.to_string(),
+        }],
+    )
+}
+
+/// Returns an invalid_argument status with the violations as BadRequest details
+pub fn bad_request(message: &str, reason: &str, field_violations: Vec<FieldViolation>) -> Status {
+    error_status(
+        Code::InvalidArgument,
+        message,
+        reason,
+        HashMap::new(),
+        field_violations,
+    )
+}
+
+/// Returns the reason and the message of the violation of a unique index
+fn unique_violation(constraint: Option<&str>) -> (&'static str, &'static str) {
+    match constraint {
+        Some("users_auth_username_idx" | "users_auth_username_normalized_idx") => {
+            ("USERNAME_TAKEN", "username already exists")
+        }
+        Some("users_auth_email_idx" | "users_auth_email_normalized_idx") => {
+            ("EMAIL_TAKEN", "email already exists")
+        }
+        Some("roles_name_idx") => ("ROLE_ALREADY_EXISTS", "role already exists"),
+        Some("permissions_name_idx") => ("PERMISSION_ALREADY_EXISTS", "permission already exists"),
+        Some("role_permissions_role_id_permission_id_idx") => (
+            "PERMISSION_ALREADY_GRANTED",
+            "permission already granted to the role",
+        ),
+        Some("user_roles_user_id_role_id_idx") => {
+            ("ROLE_ALREADY_ASSIGNED", "role already assigned to the user")
+        }
+        Some("organization_members_organization_id_user_id_idx") => (
+            "ALREADY_MEMBER",
+            "user is already a member of the organization",
+        ),
+        _ => ("ALREADY_EXISTS", "already exists"),
+    }
+}
+
+/// Maps the errors to the status codes, the errors that are not caused by the request are
+/// logged and returned as unavailable (databases unreachable) or internal without their details
+/// (queries, rows, connection strings)
+impl From<Error> for Status {
+    fn from(e: Error) -> Self {
+        match e {
+            // not found
+            Error::SqlxEntityNotFound { entity, .. } | Error::EntityNotFound(entity) => {
+                error_status(
+                    Code::NotFound,
+                    &format!("{entity} not found"),
+                    "NOT_FOUND",
+                    HashMap::from([("entity".to_string(), entity.to_string())]),
+                    vec![],
+                )
+            }
+            Error::PermissionNotGranted => error_status(
+                Code::NotFound,
+                "permission not granted to the role",
+                "PERMISSION_NOT_GRANTED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::RoleNotAssigned => error_status(
+                Code::NotFound,
+                "role not assigned to the user",
+                "ROLE_NOT_ASSIGNED",
+                HashMap::new(),
+                vec![],
+            ),
+
+            // conflicts
+            Error::Sqlx(sqlx::Error::Database(db_error)) if db_error.is_unique_violation() => {
+                let (reason, message) = unique_violation(db_error.constraint());
+                error_status(Code::AlreadyExists, message, reason, HashMap::new(), vec![])
+            }
+
+            // validation
+            Error::FieldsEmpty => error_status(
+                Code::InvalidArgument,
+                "one or more fields are empty",
+                "FIELDS_EMPTY",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::InvalidId(field) => invalid_field(
+                &format!("invalid {field}"),
+                field,
+                "is not a valid id".to_string(),
+                "INVALID_ID",
+            ),
+            Error::UserIdMismatch => invalid_field(
+                "user_id does not match",
+                "user_id",
+                "is not the user of the session".to_string(),
+                "USER_ID_MISMATCH",
+            ),
+            Error::UsernameNotSet => invalid_field(
+                "missing username",
+                "username",
+                "is required".to_string(),
+                "REQUIRED",
+            ),
+            Error::EmailNotSet => invalid_field(
+                "missing email",
+                "email",
+                "is required".to_string(),
+                "REQUIRED",
+            ),
+            Error::PasswordNotSet => invalid_field(
+                "missing password",
+                "password",
+                "is required".to_string(),
+                "REQUIRED",
+            ),
+            Error::UsernameInvalid(description) => invalid_field(
+                "invalid username",
+                "username",
+                description,
+                "INVALID_USERNAME",
+            ),
+            Error::EmailInvalid(description) => {
+                invalid_field("invalid email", "email", description, "INVALID_EMAIL")
+            }
+            Error::PasswordPolicy(_) => password_error_status("password", e),
+            // the descriptions are fixed, the values sent by the caller are never echoed back
+            Error::RoleNameInvalid => invalid_field(
+                "invalid role name",
+                "name",
+                NAME_DESCRIPTION.to_string(),
+                "INVALID_ROLE_NAME",
+            ),
+            Error::PermissionNameInvalid => invalid_field(
+                "invalid permission name",
+                "permission",
+                NAME_DESCRIPTION.to_string(),
+                "INVALID_PERMISSION_NAME",
+            ),
+            Error::OrganizationNameInvalid => invalid_field(
+                "invalid organization name",
+                "name",
+                "must be between 1 and 255 characters long".to_string(),
+                "INVALID_ORGANIZATION_NAME",
+            ),
+            Error::OrganizationRoleInvalid => invalid_field(
+                "invalid organization role",
+                "role",
+                "must be owner, admin or member".to_string(),
+                "INVALID_ORGANIZATION_ROLE",
+            ),
+
+            // authentication, every failed login gets the same status so that it can't tell
+            // which accounts exist
+            Error::InvalidCredentials
+            | Error::Argon2ErrorPasswordHash(password_hash::Error::Password) => error_status(
+                Code::Unauthenticated,
+                "invalid credentials",
+                "INVALID_CREDENTIALS",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::SessionNotFound => error_status(
+                Code::Unauthenticated,
+                "session not found or expired",
+                "SESSION_NOT_FOUND",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::AccountInactive => error_status(
+                Code::Unauthenticated,
+                "user is blocked or needs verification",
+                "ACCOUNT_INACTIVE",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::InvalidToken => error_status(
+                Code::InvalidArgument,
+                "invalid or expired token",
+                "INVALID_TOKEN",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::RefreshTokenReused => error_status(
+                Code::Unauthenticated,
+                "refresh token already used, the session has been revoked",
+                "REFRESH_TOKEN_REUSED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::RefreshTokenInvalid => error_status(
+                Code::Unauthenticated,
+                "invalid or expired refresh token",
+                "INVALID_REFRESH_TOKEN",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaChallengeInvalid => error_status(
+                Code::Unauthenticated,
+                "invalid or expired challenge",
+                "INVALID_MFA_CHALLENGE",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaCodeInvalid => error_status(
+                Code::Unauthenticated,
+                "invalid code",
+                "INVALID_MFA_CODE",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::ApiClientUnauthenticated => error_status(
+                Code::Unauthenticated,
+                "no valid auth token",
+                "INVALID_API_CLIENT",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::EmailNotVerified => error_status(
+                Code::FailedPrecondition,
+                "email not verified",
+                "EMAIL_NOT_VERIFIED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaNotEnabled => error_status(
+                Code::FailedPrecondition,
+                "MFA is not enabled",
+                "MFA_NOT_ENABLED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaAlreadyEnabled => error_status(
+                Code::FailedPrecondition,
+                "MFA is already enabled",
+                "MFA_ALREADY_ENABLED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaEnrollmentNotStarted => error_status(
+                Code::FailedPrecondition,
+                "MFA enrollment not started",
+                "MFA_ENROLLMENT_NOT_STARTED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::LastOrganizationOwner => error_status(
+                Code::FailedPrecondition,
+                "an organization needs at least one owner",
+                "LAST_OWNER",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::JwtNotConfigured => error_status(
+                Code::FailedPrecondition,
+                "access tokens are not enabled on this server",
+                "ACCESS_TOKENS_DISABLED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::MfaNotConfigured => error_status(
+                Code::FailedPrecondition,
+                "MFA is not enabled on this server",
+                "MFA_DISABLED",
+                HashMap::new(),
+                vec![],
+            ),
+
+            // permissions
+            Error::ApiClientNotAllowed { client, rpc_method } => error_status(
+                Code::PermissionDenied,
+                &format!("client {client} is not allowed to call {rpc_method}"),
+                "RPC_NOT_ALLOWED",
+                HashMap::from([("rpc_method".to_string(), rpc_method)]),
+                vec![],
+            ),
+            Error::NotOrganizationMember => error_status(
+                Code::PermissionDenied,
+                "not a member of the organization",
+                "NOT_A_MEMBER",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::OrganizationRoleNotAllowed => error_status(
+                Code::PermissionDenied,
+                "not allowed to manage members with this role",
+                "ROLE_NOT_ALLOWED",
+                HashMap::new(),
+                vec![],
+            ),
+            Error::InvitationEmailMismatch => error_status(
+                Code::PermissionDenied,
+                "the invitation was sent to another email",
+                "INVITATION_EMAIL_MISMATCH",
+                HashMap::new(),
+                vec![],
+            ),
+
+            // throttling, the clients can wait for the retry-after seconds
+            Error::LoginThrottled { retry_after } => {
+                let mut status = error_status(
+                    Code::ResourceExhausted,
+                    "too many failed login attempts",
+                    "LOGIN_THROTTLED",
+                    HashMap::from([("retry_after".to_string(), retry_after.to_string())]),
+                    vec![],
+                );
+                status
+                    .metadata_mut()
+                    .insert("retry-after", MetadataValue::from(retry_after));
+
+                status
+            }
+
+            // the databases can't be reached, the request can be retried later
+            Error::RedisPool(_) | Error::Sqlx(sqlx::Error::PoolTimedOut | sqlx::Error::Io(_)) => {
+                error!("Unavailable: {e:?}");
+                error_status(
+                    Code::Unavailable,
+                    "service unavailable",
+                    "UNAVAILABLE",
+                    HashMap::new(),
+                    vec![],
+                )
+            }
+            Error::Redis(ref redis_error)
+                if redis_error.is_connection_refusal()
+                    || redis_error.is_connection_dropped()
+                    || redis_error.is_timeout() =>
+            {
+                error!("Unavailable: {e:?}");
+                error_status(
+                    Code::Unavailable,
+                    "service unavailable",
+                    "UNAVAILABLE",
+                    HashMap::new(),
+                    vec![],
+                )
+            }
+
+            e => {
+                error!("Internal error: {e:?}");
+                error_status(
+                    Code::Internal,
+                    "internal error",
+                    "INTERNAL",
+                    HashMap::new(),
+                    vec![],
+                )
+            }
+        }
+    }
+}
+
+/// Maps the error of a password check to a status
+/// The rules of the password policy that failed are returned as BadRequest details, with the rule
+/// as reason (e.g. TOO_SHORT) and a message for the user as description
+pub fn password_error_status(field: &str, e: Error) -> Status {
+    match e {
+        Error::PasswordPolicy(violations) => bad_request(
+            "password does not respect the password policy",
+            "PASSWORD_POLICY",
+            violations
+                .iter()
+                .map(|v| FieldViolation {
+                    field: field.to_string(),
+                    description: v.description(),
+                    reason: v.as_ref().to_string(),
+                })
+                .collect(),
+        ),
+        e => Status::from(e),
+    }
+}
+
+/// Returns the ErrorInfo details of a status, None if it has none
+pub fn error_info_details(status: &Status) -> Option<ErrorInfo> {
+    let rpc_status = RpcStatus::decode(status.details()).ok()?;
+
+    rpc_status
+        .details
+        .into_iter()
+        .find(|d| d.type_url == ERROR_INFO_TYPE_URL)
+        .and_then(|d| ErrorInfo::decode(d.value.as_slice()).ok())
+}
+
+/// Returns the BadRequest details of a status, None if it has none
+pub fn bad_request_details(status: &Status) -> Option<BadRequest> {
+    let rpc_status = RpcStatus::decode(status.details()).ok()?;
+
+    rpc_status
+        .details
+        .into_iter()
+        .find(|d| d.type_url == BAD_REQUEST_TYPE_URL)
+        .and_then(|d| BadRequest::decode(d.value.as_slice()).ok())
+}
@@ -0,0 +1,107 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use tonic::server::NamedService;
+use tonic_health::{server::HealthReporter, ServingStatus};
+use tracing::{info, warn};
+
+use crate::{
+    config::config,
+    error::Result,
+    mandos_auth::mandos_auth_server::MandosAuthServer,
+    model::{db, session, ModelManager},
+    server::ServiceMandosAuth,
+};
+
+/// Name of the mandos_auth service in the grpc.health.v1 statuses
+pub const SERVICE_NAME: &str = <MandosAuthServer<ServiceMandosAuth> as NamedService>::NAME;
+
+/// The result of a ping to one of the dependencies of the server
+#[derive(Clone, Debug)]
+pub struct DependencyStatus {
+    pub name: &'static str,
+    pub serving: bool,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+/// Pings all the dependencies of the server at the same time
+pub async fn check_dependencies(model_manager: &ModelManager) -> Vec<DependencyStatus> {
+    let (db_status, session_db_status) = tokio::join!(
+        check_dependency("postgres", db::ping(model_manager.db())),
+        check_dependency(
+            "redis",
+            session::crud::ping(model_manager.session_db().clone())
+        ),
+    );
+
+    vec![db_status, session_db_status]
+}
+
+/// Runs the ping of a dependency, a ping slower than the health check timeout counts as failed
+async fn check_dependency(
+    name: &'static str,
+    ping: impl Future<Output = Result<()>>,
+) -> DependencyStatus {
+    let start = Instant::now();
+    let timeout = Duration::from_secs(config().HEALTH_CHECK_TIMEOUT);
+    let res = tokio::time::timeout(timeout, ping).await;
+    let latency = start.elapsed();
+
+    let error = match res {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no answer after {}s", timeout.as_secs())),
+    };
+
+    DependencyStatus {
+        name,
+        serving: error.is_none(),
+        latency,
+        error,
+    }
+}
+
+/// Keeps the grpc.health.v1 statuses in line with the dependencies, pinging them at every health
+/// check interval
+/// It runs until the task is aborted
+pub async fn report_health(model_manager: ModelManager, mut health_reporter: HealthReporter) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config().HEALTH_CHECK_INTERVAL));
+    let mut last_status = None;
+
+    loop {
+        interval.tick().await;
+
+        let dependencies = check_dependencies(&model_manager).await;
+        let status = if dependencies.iter().all(|d| d.serving) {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+
+        // log only the changes, not every check
+        if last_status != Some(status) {
+            for dependency in dependencies.iter().filter(|d| !d.serving) {
+                warn!(
+                    "{} is not available: {}",
+                    dependency.name,
+                    dependency.error.as_deref().unwrap_or_default()
+                );
+            }
+            info!("Health status: {status}");
+            last_status = Some(status);
+        }
+
+        set_status(&mut health_reporter, status).await;
+    }
+}
+
+/// Sets the status of the whole server and of the mandos_auth service
+pub async fn set_status(health_reporter: &mut HealthReporter, status: ServingStatus) {
+    health_reporter.set_service_status("", status).await;
+    health_reporter
+        .set_service_status(SERVICE_NAME, status)
+        .await;
+}
@@ -1,28 +1,83 @@
 use tonic::{Request, Status};
 use tracing::debug;
 
-use crate::config::config;
+use crate::{
+    api_client::LEGACY_API_CLIENT_NAME,
+    config::config,
+    error::Error,
+    server::{
+        api_client::{
+            AuthenticatedClient, RpcMethod, CLIENT_ID_METADATA_KEY, CLIENT_SECRET_METADATA_KEY,
+        },
+        tls,
+    },
+};
 
-pub fn check_auth(request: Request<()>) -> std::result::Result<Request<()>, Status> {
+// the interceptors have to return a tonic::Status
+#[allow(clippy::result_large_err)]
+pub fn check_auth(mut request: Request<()>) -> std::result::Result<Request<()>, Status> {
     debug!("FN: check_auth - Verifying auth token");
 
-    let request_grpc_auth_value = match request.metadata().get(&config().GRPC_AUTH_KEY) {
-        Some(v) => {
-            // if the value canno be converted to a string, set it to an empty string
-            v.to_str().unwrap_or("").to_string()
-        }
-        None => {
-            return Err(Status::unauthenticated("No valid auth token"));
-        }
+    // if a value cannot be converted to a string, set it to an empty string
+    let get_metadata = |key: &str| {
+        request
+            .metadata()
+            .get(key)
+            .map(|v| v.to_str().unwrap_or("").to_string())
     };
+    let client_id = get_metadata(CLIENT_ID_METADATA_KEY);
+    let client_secret = get_metadata(CLIENT_SECRET_METADATA_KEY);
+    let legacy_secret = config()
+        .GRPC_AUTH_KEY
+        .as_deref()
+        .and_then(|key| get_metadata(key));
 
-    // get the auth value to validate request
-    let grpc_auth_value = config().GRPC_AUTH_VALUE.clone();
+    // the callers that send the legacy GRPC_AUTH_KEY metadata authenticate as the legacy client
+    let (client_id, client_secret) = match (client_id, client_secret, legacy_secret) {
+        (None, None, Some(legacy_secret)) => (
+            Some(LEGACY_API_CLIENT_NAME.to_string()),
+            Some(legacy_secret),
+        ),
+        (client_id, client_secret, _) => (client_id, client_secret),
+    };
 
-    // check that that the auth value is correct
-    if request_grpc_auth_value != grpc_auth_value {
-        return Err(Status::unauthenticated("No valid auth token"));
+    // the registry can be reloaded meanwhile, the request is checked against the current one
+    let api_clients = config().API_CLIENTS.get();
+    let api_client = match (client_id, client_secret) {
+        // the names of the clients are not secret, only the secrets are compared in constant time
+        (Some(client_id), Some(client_secret)) => api_clients
+            .iter()
+            .find(|c| c.name == client_id)
+            .filter(|c| c.verify_secret(&client_secret)),
+        // with mTLS on, the verified client certificate stands in for the secret
+        (client_id, None) => tls::peer_common_name(&request).and_then(|common_name| {
+            api_clients
+                .iter()
+                .find(|c| c.certificate_common_names.contains(&common_name))
+                .filter(|c| client_id.as_ref().is_none_or(|id| *id == c.name))
+        }),
+        (None, Some(_)) => None,
     }
+    .ok_or(Error::ApiClientUnauthenticated)?;
+
+    // check that the client can call the rpc
+    let rpc_method = request
+        .extensions()
+        .get::<RpcMethod>()
+        .map(|m| m.0.clone())
+        .unwrap_or_default();
+    if !api_client.allows(&rpc_method) {
+        return Err(Error::ApiClientNotAllowed {
+            client: api_client.name.clone(),
+            rpc_method,
+        }
+        .into());
+    }
+
+    debug!("API client {} calling {rpc_method}", api_client.name);
+    request.extensions_mut().insert(AuthenticatedClient {
+        name: api_client.name.clone(),
+    });
 
     Ok(request)
 }
@@ -1,32 +1,77 @@
-use std::time::Duration;
+use std::{
+    future::{self, Future},
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
 
-use tonic::{transport::Server, Request, Response, Status};
-use tracing::{debug, info};
+use tokio::{net::TcpListener, signal, sync::oneshot, task::JoinHandle};
+use tonic::{
+    transport::{server::TcpIncoming, Server, ServerTlsConfig},
+    Request, Response, Status,
+};
+use tonic_health::ServingStatus;
+use tower::util::MapRequestLayer;
+use tracing::{debug, info, warn};
 
 use crate::{
+    config::{self, config},
     error,
+    mailer::{self, Mailer},
     mandos_auth::{
         mandos_auth_server::{MandosAuth, MandosAuthServer},
-        DeleteAccountRequest, DeleteAccountResponse, HealthCheckRequest, HealthCheckResponse,
-        LoginRequest, LoginResponse, LogoutRequest, LogoutResponse, RegisterRequest,
-        RegisterResponse, UpdatePasswordRequest, UpdatePasswordResponse, ValidateRequest,
-        ValidateResponse,
+        AcceptInvitationRequest, AcceptInvitationResponse, AssignRoleRequest, AssignRoleResponse,
+        CheckPermissionRequest, CheckPermissionResponse, ClearLoginLockoutRequest,
+        ClearLoginLockoutResponse, CompleteMfaLoginRequest, CompleteMfaLoginResponse,
+        ConfirmTotpRequest, ConfirmTotpResponse, CountRecoveryCodesRequest,
+        CountRecoveryCodesResponse, CreateOrganizationRequest, CreateOrganizationResponse,
+        CreateRoleRequest, CreateRoleResponse, DeleteAccountRequest, DeleteAccountResponse,
+        DeleteRoleRequest, DeleteRoleResponse, DependencyStatus, DisableTotpRequest,
+        DisableTotpResponse, EnrollTotpRequest, EnrollTotpResponse, GetJwksRequest,
+        GetJwksResponse, GrantPermissionRequest, GrantPermissionResponse, HealthCheckRequest,
+        HealthCheckResponse, InviteMemberRequest, InviteMemberResponse, ListOrganizationsRequest,
+        ListOrganizationsResponse, ListSessionsRequest, ListSessionsResponse, LoginRequest,
+        LoginResponse, LogoutRequest, LogoutResponse, RefreshSessionRequest,
+        RefreshSessionResponse, RegenerateRecoveryCodesRequest, RegenerateRecoveryCodesResponse,
+        RegisterRequest, RegisterResponse, RemoveMemberRequest, RemoveMemberResponse,
+        RequestPasswordResetRequest, RequestPasswordResetResponse, ResendVerificationRequest,
+        ResendVerificationResponse, ResetPasswordRequest, ResetPasswordResponse,
+        RevokeAllSessionsRequest, RevokeAllSessionsResponse, RevokePermissionRequest,
+        RevokePermissionResponse, RevokeSessionRequest, RevokeSessionResponse,
+        SwitchOrganizationRequest, SwitchOrganizationResponse, UnassignRoleRequest,
+        UnassignRoleResponse, UpdatePasswordRequest, UpdatePasswordResponse, ValidateRequest,
+        ValidateResponse, VerifyEmailRequest, VerifyEmailResponse,
     },
-    mandos_auth_proto,
+    mandos_auth_proto, metrics,
     model::{self, ModelManager},
-    server::middleware::check_auth,
+    server::{
+        api_client::insert_rpc_method, client_info::ClientInfo, middleware::check_auth,
+        request_trace::RequestTraceLayer, rpc_metrics::RpcMetricsLayer,
+    },
+    utils,
 };
 
+pub mod api_client;
+pub mod client_info;
+pub mod error_details;
+pub mod health;
 pub mod middleware;
+pub mod request_trace;
 mod routes;
+pub mod rpc_metrics;
+pub mod tls;
 
 pub struct ServiceMandosAuth {
     model_manager: model::ModelManager,
+    mailer: Arc<dyn Mailer>,
 }
 
 impl ServiceMandosAuth {
-    pub fn new(model_manager: model::ModelManager) -> Self {
-        Self { model_manager }
+    pub fn new(model_manager: model::ModelManager, mailer: Arc<dyn Mailer>) -> Self {
+        Self {
+            model_manager,
+            mailer,
+        }
     }
 }
 
@@ -36,9 +81,23 @@ impl MandosAuth for ServiceMandosAuth {
         &self,
         _: Request<HealthCheckRequest>,
     ) -> Result<Response<HealthCheckResponse>, Status> {
-        debug!("FN: health_check - Service to check if server is up");
+        debug!("FN: health_check - Service to check if server and its dependencies are up");
+
+        let dependencies: Vec<DependencyStatus> = health::check_dependencies(&self.model_manager)
+            .await
+            .into_iter()
+            .map(|d| DependencyStatus {
+                name: d.name.to_string(),
+                serving: d.serving,
+                latency_micros: d.latency.as_micros() as u64,
+                error: d.error.unwrap_or_default(),
+            })
+            .collect();
 
-        let res = HealthCheckResponse { success: true };
+        let res = HealthCheckResponse {
+            success: dependencies.iter().all(|d| d.serving),
+            dependencies,
+        };
         Ok(Response::new(res))
     }
 
@@ -46,7 +105,13 @@ impl MandosAuth for ServiceMandosAuth {
         &self,
         request: Request<LoginRequest>,
     ) -> Result<Response<LoginResponse>, Status> {
-        routes::auth::login(request.into_inner(), self.model_manager.clone()).await
+        let client_info = ClientInfo::from_request(&request);
+        routes::auth::login(
+            request.into_inner(),
+            self.model_manager.clone(),
+            client_info,
+        )
+        .await
     }
 
     async fn logout(
@@ -60,7 +125,12 @@ impl MandosAuth for ServiceMandosAuth {
         &self,
         request: Request<RegisterRequest>,
     ) -> Result<Response<RegisterResponse>, Status> {
-        routes::auth::register(request.into_inner(), self.model_manager.clone()).await
+        routes::auth::register(
+            request.into_inner(),
+            self.model_manager.clone(),
+            self.mailer.clone(),
+        )
+        .await
     }
 
     async fn validate_session(
@@ -83,44 +153,470 @@ impl MandosAuth for ServiceMandosAuth {
     ) -> Result<Response<DeleteAccountResponse>, Status> {
         routes::auth::delete_account(request.into_inner(), self.model_manager.clone()).await
     }
+
+    async fn verify_email(
+        &self,
+        request: Request<VerifyEmailRequest>,
+    ) -> Result<Response<VerifyEmailResponse>, Status> {
+        routes::auth::verify_email(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn resend_verification(
+        &self,
+        request: Request<ResendVerificationRequest>,
+    ) -> Result<Response<ResendVerificationResponse>, Status> {
+        routes::auth::resend_verification(
+            request.into_inner(),
+            self.model_manager.clone(),
+            self.mailer.clone(),
+        )
+        .await
+    }
+
+    async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<RequestPasswordResetResponse>, Status> {
+        routes::auth::request_password_reset(
+            request.into_inner(),
+            self.model_manager.clone(),
+            self.mailer.clone(),
+        )
+        .await
+    }
+
+    async fn reset_password(
+        &self,
+        request: Request<ResetPasswordRequest>,
+    ) -> Result<Response<ResetPasswordResponse>, Status> {
+        routes::auth::reset_password(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        routes::session::list_sessions(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionRequest>,
+    ) -> Result<Response<RevokeSessionResponse>, Status> {
+        routes::session::revoke_session(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn revoke_all_sessions(
+        &self,
+        request: Request<RevokeAllSessionsRequest>,
+    ) -> Result<Response<RevokeAllSessionsResponse>, Status> {
+        routes::session::revoke_all_sessions(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn complete_mfa_login(
+        &self,
+        request: Request<CompleteMfaLoginRequest>,
+    ) -> Result<Response<CompleteMfaLoginResponse>, Status> {
+        let client_info = ClientInfo::from_request(&request);
+        routes::mfa::complete_mfa_login(
+            request.into_inner(),
+            self.model_manager.clone(),
+            client_info,
+        )
+        .await
+    }
+
+    async fn enroll_totp(
+        &self,
+        request: Request<EnrollTotpRequest>,
+    ) -> Result<Response<EnrollTotpResponse>, Status> {
+        routes::mfa::enroll_totp(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn confirm_totp(
+        &self,
+        request: Request<ConfirmTotpRequest>,
+    ) -> Result<Response<ConfirmTotpResponse>, Status> {
+        routes::mfa::confirm_totp(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn disable_totp(
+        &self,
+        request: Request<DisableTotpRequest>,
+    ) -> Result<Response<DisableTotpResponse>, Status> {
+        routes::mfa::disable_totp(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn regenerate_recovery_codes(
+        &self,
+        request: Request<RegenerateRecoveryCodesRequest>,
+    ) -> Result<Response<RegenerateRecoveryCodesResponse>, Status> {
+        routes::mfa::regenerate_recovery_codes(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn count_recovery_codes(
+        &self,
+        request: Request<CountRecoveryCodesRequest>,
+    ) -> Result<Response<CountRecoveryCodesResponse>, Status> {
+        routes::mfa::count_recovery_codes(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn clear_login_lockout(
+        &self,
+        request: Request<ClearLoginLockoutRequest>,
+    ) -> Result<Response<ClearLoginLockoutResponse>, Status> {
+        routes::throttle::clear_login_lockout(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn create_role(
+        &self,
+        request: Request<CreateRoleRequest>,
+    ) -> Result<Response<CreateRoleResponse>, Status> {
+        routes::rbac::create_role(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn delete_role(
+        &self,
+        request: Request<DeleteRoleRequest>,
+    ) -> Result<Response<DeleteRoleResponse>, Status> {
+        routes::rbac::delete_role(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn grant_permission(
+        &self,
+        request: Request<GrantPermissionRequest>,
+    ) -> Result<Response<GrantPermissionResponse>, Status> {
+        routes::rbac::grant_permission(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn revoke_permission(
+        &self,
+        request: Request<RevokePermissionRequest>,
+    ) -> Result<Response<RevokePermissionResponse>, Status> {
+        routes::rbac::revoke_permission(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn assign_role(
+        &self,
+        request: Request<AssignRoleRequest>,
+    ) -> Result<Response<AssignRoleResponse>, Status> {
+        routes::rbac::assign_role(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn unassign_role(
+        &self,
+        request: Request<UnassignRoleRequest>,
+    ) -> Result<Response<UnassignRoleResponse>, Status> {
+        routes::rbac::unassign_role(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn check_permission(
+        &self,
+        request: Request<CheckPermissionRequest>,
+    ) -> Result<Response<CheckPermissionResponse>, Status> {
+        routes::rbac::check_permission(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn get_jwks(
+        &self,
+        request: Request<GetJwksRequest>,
+    ) -> Result<Response<GetJwksResponse>, Status> {
+        routes::jwt::get_jwks(request.into_inner()).await
+    }
+
+    async fn create_organization(
+        &self,
+        request: Request<CreateOrganizationRequest>,
+    ) -> Result<Response<CreateOrganizationResponse>, Status> {
+        routes::organization::create_organization(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn invite_member(
+        &self,
+        request: Request<InviteMemberRequest>,
+    ) -> Result<Response<InviteMemberResponse>, Status> {
+        routes::organization::invite_member(
+            request.into_inner(),
+            self.model_manager.clone(),
+            self.mailer.clone(),
+        )
+        .await
+    }
+
+    async fn accept_invitation(
+        &self,
+        request: Request<AcceptInvitationRequest>,
+    ) -> Result<Response<AcceptInvitationResponse>, Status> {
+        routes::organization::accept_invitation(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberResponse>, Status> {
+        routes::organization::remove_member(request.into_inner(), self.model_manager.clone()).await
+    }
+
+    async fn list_organizations(
+        &self,
+        request: Request<ListOrganizationsRequest>,
+    ) -> Result<Response<ListOrganizationsResponse>, Status> {
+        routes::organization::list_organizations(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn switch_organization(
+        &self,
+        request: Request<SwitchOrganizationRequest>,
+    ) -> Result<Response<SwitchOrganizationResponse>, Status> {
+        routes::organization::switch_organization(request.into_inner(), self.model_manager.clone())
+            .await
+    }
+
+    async fn refresh_session(
+        &self,
+        request: Request<RefreshSessionRequest>,
+    ) -> Result<Response<RefreshSessionResponse>, Status> {
+        let client_info = ClientInfo::from_request(&request);
+        routes::session::refresh_session(
+            request.into_inner(),
+            self.model_manager.clone(),
+            client_info,
+        )
+        .await
+    }
 }
 
 pub async fn start(model_manager: ModelManager) -> error::Result<()> {
-    let addr = "0.0.0.0:50051".parse()?;
-    let mandos_auth = ServiceMandosAuth::new(model_manager.clone());
+    let listener = TcpListener::bind(config().GRPC_ADDR).await?;
+    let mandos_auth = ServiceMandosAuth::new(model_manager.clone(), mailer::new_mailer());
+
+    info!(
+        "Starting gRPC server on {} ({})",
+        listener.local_addr()?,
+        if config().TLS.is_some() {
+            "TLS"
+        } else {
+            "plaintext"
+        }
+    );
+
+    // the metrics keep being served while the gRPC server drains
+    let metrics_listener = TcpListener::bind(config().METRICS_ADDR).await?;
+    info!(
+        "Serving the metrics on http://{}/metrics",
+        metrics_listener.local_addr()?
+    );
+    let (metrics_shutdown_tx, metrics_shutdown_rx) = oneshot::channel::<()>();
+    let metrics_task = tokio::spawn(metrics::serve(
+        metrics_listener,
+        model_manager.clone(),
+        async move {
+            let _ = metrics_shutdown_rx.await;
+        },
+    ));
+
+    let reload_task = tokio::spawn(reload_on_hangup());
+
+    let res = serve(
+        mandos_auth,
+        listener,
+        config().TLS.clone(),
+        shutdown_signal(),
+    )
+    .await;
+
+    reload_task.abort();
+    let _ = metrics_shutdown_tx.send(());
+    match metrics_task.await {
+        Ok(Err(e)) => warn!("Metrics server stopped with an error: {e:?}"),
+        Err(e) => warn!("Metrics server task failed: {e}"),
+        Ok(Ok(())) => {}
+    }
+    res?;
 
-    info!("Starting gRPC server on {}", addr);
+    // the in-flight requests are done, the connections can be closed
+    model_manager.close().await;
+    info!("gRPC server stopped");
 
+    Ok(())
+}
+
+/// A server running in the background, started with `start_background`
+/// Dropping the handle leaves the server running
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    shutdown_tx: oneshot::Sender<()>,
+    join_handle: JoinHandle<error::Result<()>>,
+}
+
+impl ServerHandle {
+    /// Returns the address the server is bound to, with the actual port when started on port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops the server, waiting for the in-flight requests (up to the drain timeout)
+    pub async fn shutdown(self) -> error::Result<()> {
+        // the server may have already stopped with an error, returned by the task
+        let _ = self.shutdown_tx.send(());
+
+        self.join_handle
+            .await
+            .map_err(|e| error::Error::Service(e.to_string()))?
+    }
+}
+
+/// Starts the server in the background, it accepts connections as soon as this returns
+/// # Arguments
+/// * `model_manager` - The model manager used by the server
+/// * `mailer` - The mailer used by the server
+/// * `addr` - The address to listen on, port 0 picks a free port
+/// * `tls_config` - The TLS config, None to serve plaintext
+pub async fn start_background(
+    model_manager: ModelManager,
+    mailer: Arc<dyn Mailer>,
+    addr: SocketAddr,
+    tls_config: Option<ServerTlsConfig>,
+) -> error::Result<ServerHandle> {
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    let mandos_auth = ServiceMandosAuth::new(model_manager, mailer);
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let shutdown = async move {
+        // a dropped handle can't stop the server anymore
+        if shutdown_rx.await.is_err() {
+            future::pending::<()>().await;
+        }
+    };
+    let join_handle = tokio::spawn(serve(mandos_auth, listener, tls_config, shutdown));
+
+    Ok(ServerHandle {
+        local_addr,
+        shutdown_tx,
+        join_handle,
+    })
+}
+
+/// Serves the requests until `shutdown` completes, then stops accepting new connections and waits
+/// for the in-flight requests, for at most the drain timeout
+async fn serve(
+    mandos_auth: ServiceMandosAuth,
+    listener: TcpListener,
+    tls_config: Option<ServerTlsConfig>,
+    shutdown: impl Future<Output = ()>,
+) -> error::Result<()> {
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(mandos_auth_proto::FILE_DESCRIPTOR_SET)
         .build()
         .unwrap();
 
-    Server::builder()
+    // computed before serving, so that the first login of an unknown user is not slower than the
+    // next ones
+    tokio::task::spawn_blocking(utils::dummy_password_hash)
+        .await
+        .map_err(|e| error::Error::Service(e.to_string()))?;
+
+    let mut server = Server::builder();
+    if let Some(tls_config) = tls_config {
+        server = server.tls_config(tls_config)?;
+    }
+
+    let incoming = TcpIncoming::from_listener(listener, true, None)
+        .map_err(|e| error::Error::Service(e.to_string()))?;
+
+    // the grpc.health.v1 statuses follow the dependencies, the service is not behind check_auth
+    // so that the probes don't need credentials
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    let health_task = tokio::spawn(health::report_health(
+        mandos_auth.model_manager.clone(),
+        health_reporter.clone(),
+    ));
+
+    // tells when the server has started draining
+    let (draining_tx, draining_rx) = oneshot::channel();
+    let serving = server
+        .layer(RequestTraceLayer)
+        .layer(RpcMetricsLayer)
+        .layer(MapRequestLayer::new(insert_rpc_method))
         .add_service(MandosAuthServer::with_interceptor(mandos_auth, check_auth))
+        .add_service(health_service)
         .add_service(reflection_service)
-        .serve(addr)
-        .await?;
+        .serve_with_incoming_shutdown(incoming, async move {
+            shutdown.await;
+            let _ = draining_tx.send(());
+        });
+    tokio::pin!(serving);
 
-    Ok(())
+    let res = tokio::select! {
+        res = &mut serving => res,
+        _ = draining_rx => {
+            info!("Shutting down gRPC server, waiting for the in-flight requests");
+            health_task.abort();
+            health::set_status(&mut health_reporter, ServingStatus::NotServing).await;
+
+            let drain_timeout = Duration::from_secs(config().SHUTDOWN_DRAIN_TIMEOUT);
+            match tokio::time::timeout(drain_timeout, serving).await {
+                Ok(res) => res,
+                Err(_) => {
+                    warn!("Drain timeout expired, the requests still in flight have been dropped");
+                    Ok(())
+                }
+            }
+        }
+    };
+    health_task.abort();
+
+    Ok(res?)
+}
+
+/// Reloads the reloadable config values every time the process receives SIGHUP
+async fn reload_on_hangup() {
+    #[cfg(unix)]
+    match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(mut hangup) => {
+            while hangup.recv().await.is_some() {
+                match config::reload() {
+                    Ok(()) => info!("SIGHUP received, config reloaded"),
+                    Err(e) => warn!("SIGHUP received, failed to reload the config: {e:?}"),
+                }
+            }
+        }
+        Err(e) => warn!("Failed to listen for SIGHUP: {e}"),
+    }
 }
 
-pub async fn start_background(model_manager: ModelManager) -> error::Result<()> {
-    let addr = "0.0.0.0:50051".parse()?;
-    let mandos_auth = ServiceMandosAuth::new(model_manager.clone());
-
-    tokio::spawn(async move {
-        let server = Server::builder()
-            .add_service(MandosAuthServer::with_interceptor(mandos_auth, check_auth))
-            .serve(addr)
-            .await;
-        if let Err(e) = server {
-            e.to_string();
+/// Completes when the process receives SIGTERM or SIGINT
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = signal::ctrl_c().await {
+            warn!("Failed to listen for SIGINT: {e}");
+            future::pending::<()>().await;
         }
-    });
+    };
 
-    // Wait for the server to be ready (optional)
-    tokio::time::sleep(Duration::from_secs(2)).await;
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {e}");
+                future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
 
-    Ok(())
+    tokio::select! {
+        _ = interrupt => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
 }
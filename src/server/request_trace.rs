@@ -0,0 +1,144 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::{HeaderMap, HeaderValue};
+use opentelemetry::{global, propagation::Extractor};
+use tonic::Request;
+use tower::{Layer, Service};
+use tracing::{info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use super::rpc_metrics::response_code;
+
+/// Metadata key with the id of the request, taken from the caller or generated, echoed back in
+/// the response metadata
+pub const REQUEST_ID_METADATA_KEY: &str = "x-request-id";
+
+/// Longest request id accepted from the callers
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// The id of the request, added to the request extensions and to the span of the request
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Returns the id of the request, None if the request did not go through the trace layer
+    pub fn from_request<T>(request: &Request<T>) -> Option<Self> {
+        request.extensions().get::<Self>().cloned()
+    }
+}
+
+/// Returns whether a request id sent by a caller can be logged as it is
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= REQUEST_ID_MAX_LEN
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Reads the W3C trace context from the headers of the http request
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Tower layer that runs every RPC in a span, child of the trace context of the caller (W3C
+/// traceparent), and tags it with the request id
+#[derive(Clone, Debug, Default)]
+pub struct RequestTraceLayer;
+
+impl<S> Layer<S> for RequestTraceLayer {
+    type Service = RequestTrace<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTrace { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RequestTrace<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RequestTrace<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // the service that is ready is the one that has to be called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        // the ids that can't be logged safely are replaced
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_METADATA_KEY)
+            .and_then(|v| v.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        request
+            .extensions_mut()
+            .insert(RequestId(request_id.clone()));
+
+        // the path of a gRPC request is /package.Service/Method
+        let path = request.uri().path().trim_start_matches('/').to_string();
+        let (service, method) = path.split_once('/').unwrap_or_default();
+        let span = info_span!(
+            "grpc_request",
+            otel.name = %path,
+            otel.kind = "server",
+            rpc.system = "grpc",
+            rpc.service = %service,
+            rpc.method = %method,
+            rpc.grpc.status_code = tracing::field::Empty,
+            request_id = %request_id,
+        );
+        let parent_context = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent_context);
+
+        Box::pin(
+            async move {
+                let mut res = inner.call(request).await;
+
+                if let Ok(response) = &mut res {
+                    tracing::Span::current()
+                        .record("rpc.grpc.status_code", response_code(response) as i32);
+
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        response
+                            .headers_mut()
+                            .insert(REQUEST_ID_METADATA_KEY, value);
+                    }
+                }
+
+                res
+            }
+            .instrument(span),
+        )
+    }
+}
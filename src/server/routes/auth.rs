@@ -1,23 +1,47 @@
+use std::{future::Future, sync::Arc};
+
 use tonic::{Response, Status};
-use tracing::debug;
+use tracing::{debug, error, Instrument};
 use uuid::Uuid;
 
+use super::{
+    jwt::{check_access_token_available, issue_access_token},
+    organization::check_active_organization,
+    session::{issue_refresh_token, session_info, start_session},
+    throttle::{
+        check_login_throttle, clear_account_login_failures, count_login_attempt,
+        login_throttle_keys, undo_login_attempt,
+    },
+};
 use crate::{
+    config::config,
+    error::Error,
+    mailer::{Mail, Mailer},
     mandos_auth::{
         DeleteAccountRequest, DeleteAccountResponse, LoginRequest, LoginResponse, LogoutRequest,
-        LogoutResponse, RegisterRequest, RegisterResponse, UpdatePasswordRequest,
-        UpdatePasswordResponse, ValidateRequest, ValidateResponse,
+        LogoutResponse, RegisterRequest, RegisterResponse, RequestPasswordResetRequest,
+        RequestPasswordResetResponse, ResendVerificationRequest, ResendVerificationResponse,
+        ResetPasswordRequest, ResetPasswordResponse, UpdatePasswordRequest, UpdatePasswordResponse,
+        ValidateRequest, ValidateResponse, VerifyEmailRequest, VerifyEmailResponse,
     },
+    metrics::{metrics, LoginFailure, LoginMethod},
     model::{
-        user_auth::{self, model_controller::UserAuthBmc, UserAuthForUpdate},
+        session::Session,
+        token::TokenKind,
+        user_auth::{
+            self, identifiers, model_controller::UserAuthBmc, password_policy, UserAuthForUpdate,
+        },
+        user_mfa::{model_controller::UserMfaBmc, MfaChallenge},
         ModelManager,
     },
+    server::{client_info::ClientInfo, error_details::password_error_status},
     utils,
 };
 
 pub async fn login(
     login_request: LoginRequest,
     model_maanger: ModelManager,
+    client_info: ClientInfo,
 ) -> Result<Response<LoginResponse>, Status> {
     debug!("FN: login - Service to login user");
 
@@ -25,57 +49,150 @@ pub async fn login(
     if (login_request.username.is_empty() && login_request.email.is_empty())
         || login_request.password.is_empty()
     {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // fail before creating a session that could not be returned with its access token
+    if login_request.issue_access_token {
+        check_access_token_available()?;
     }
 
+    // check that the client ip is not blocked by too many failed attempts
+    let ip_throttle_keys = login_throttle_keys(&client_info, None);
+    check_login_throttle(&model_maanger, &ip_throttle_keys)
+        .await
+        .inspect_err(|_| metrics().login_failed(LoginFailure::Throttled))?;
+
     // get user from db
     // if email is not empty, search by email otherwise search by username
-    let db_res = if !login_request.email.is_empty() {
-        user_auth::model_controller::UserAuthBmc::get_from_email(
+    let (db_res, identifier) = if !login_request.email.is_empty() {
+        let db_res = user_auth::model_controller::UserAuthBmc::get_from_email(
             &model_maanger,
-            login_request.email,
+            login_request.email.clone(),
         )
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?
+        .await;
+        (db_res, login_request.email)
     } else {
-        user_auth::model_controller::UserAuthBmc::get_from_username(
+        let db_res = user_auth::model_controller::UserAuthBmc::get_from_username(
             &model_maanger,
-            login_request.username,
+            login_request.username.clone(),
         )
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?
+        .await;
+        (db_res, login_request.username)
+    };
+    let db_res = match db_res {
+        Ok(user_auth) => Some(user_auth),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => None,
+        Err(e) => return Err(e.into()),
     };
 
-    // check if the user is blocked or if it stills needs verification
-    if db_res.is_blocked || db_res.needs_verify {
-        return Err(Status::unauthenticated(
-            "user is blocked or needs verification".to_string(),
-        ));
-    }
-
-    // check that the password is correct
-    utils::verify_password(login_request.password, db_res.password)
-        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    // check that the account and the ip are not blocked by too many failed attempts and count
+    // the attempt, it is undone if the password is right
+    // the unknown accounts are throttled by their identifier, like the existing ones
+    let account = match &db_res {
+        Some(user_auth) => user_auth.id.to_string(),
+        None => format!("unknown:{}", identifiers::canonical(&identifier)),
+    };
+    let throttle_keys = login_throttle_keys(&client_info, Some(account));
+    let attempts = count_login_attempt(&model_maanger, &throttle_keys)
+        .await
+        .inspect_err(|_| metrics().login_failed(LoginFailure::Throttled))?;
 
-    // generate the struct to update the user (last_login)
-    let mut user_auth_for_update = UserAuthForUpdate::new();
-    user_auth_for_update.last_login = Some(chrono::Utc::now());
+    // the password is always verified, against a dummy hash for the unknown users, so that all
+    // the failures take the same time
+    let password_hash = match &db_res {
+        Some(user_auth) => user_auth.password.clone(),
+        None => utils::dummy_password_hash().to_string(),
+    };
+    let password = login_request.password.clone();
+    let password_ok =
+        utils::spawn_blocking(move || utils::verify_password(password, password_hash))
+            .await
+            .is_ok();
+
+    // every failure gets the same response, so that it can't tell which accounts exist
+    let db_res = match db_res {
+        None => Err(LoginFailure::UnknownUser),
+        Some(_) if !password_ok => Err(LoginFailure::InvalidPassword),
+        Some(user_auth) if user_auth.is_blocked => Err(LoginFailure::Blocked),
+        // the password is correct, the owner of the account can be told to verify the email
+        Some(user_auth) if user_auth.needs_verify && config().LOGIN_REVEAL_UNVERIFIED => {
+            metrics().login_failed(LoginFailure::Blocked);
+            undo_login_attempt(&model_maanger, &throttle_keys, &attempts).await?;
+            return Err(Error::EmailNotVerified.into());
+        }
+        Some(user_auth) if user_auth.needs_verify => Err(LoginFailure::Blocked),
+        Some(user_auth) => Ok(user_auth),
+    };
+    let db_res = match db_res {
+        Ok(user_auth) => user_auth,
+        Err(failure) => {
+            metrics().login_failed(failure);
+            return Err(Error::InvalidCredentials.into());
+        }
+    };
+    undo_login_attempt(&model_maanger, &throttle_keys, &attempts).await?;
+
+    // the password is right, hash it again if the config asks for a stronger hash
+    if utils::password_needs_rehash(&db_res.password)? {
+        let mut user_auth_for_update = UserAuthForUpdate::new();
+        user_auth_for_update.password = Some(login_request.password);
+        let ua_fu = utils::spawn_blocking(move || user_auth_for_update.hash_password()).await?;
+        UserAuthBmc::update(&model_maanger, ua_fu, db_res.id).await?;
+    }
 
-    // update user's last_login in db
-    UserAuthBmc::update(&model_maanger, user_auth_for_update, db_res.id)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+    // with MFA enabled the session is created only after the second factor has been checked
+    let mfa_enabled = UserMfaBmc::is_enabled(&model_maanger, db_res.id).await?;
+    if mfa_enabled {
+        let mfa_challenge = UserMfaBmc::create_challenge(
+            &model_maanger,
+            MfaChallenge {
+                user_id: db_res.id.to_string(),
+                remember_me: login_request.remember_me,
+                issue_access_token: login_request.issue_access_token,
+            },
+        )
+        .await?;
+
+        let res = LoginResponse {
+            mfa_required: true,
+            mfa_challenge,
+            ..Default::default()
+        };
+        return Ok(Response::new(res));
+    }
 
     // create session in the db
-    let session_id = UserAuthBmc::create_session(
+    let (session_id, expires_at, idle_expires_at) = start_session(
         &model_maanger,
-        db_res.id.to_string(),
-        (60 * 60 * 24 * 30) as u64,
+        db_res.id,
+        client_info,
+        login_request.remember_me,
     )
-    .await
-    .map_err(|e| Status::internal(e.to_string()))?;
+    .await?;
+    clear_account_login_failures(&model_maanger, db_res.id).await?;
+
+    let (refresh_token, refresh_token_expires_at) =
+        issue_refresh_token(&model_maanger, db_res.id, session_id.clone()).await?;
+
+    let (access_token, access_token_expires_at) = if login_request.issue_access_token {
+        issue_access_token(&model_maanger, db_res.id, &session_id).await?
+    } else {
+        Default::default()
+    };
 
-    let res = LoginResponse { session_id };
+    metrics().login_succeeded(LoginMethod::Password);
+
+    let res = LoginResponse {
+        session_id,
+        expires_at,
+        access_token,
+        access_token_expires_at,
+        refresh_token,
+        refresh_token_expires_at,
+        idle_expires_at,
+        ..Default::default()
+    };
     Ok(Response::new(res))
 }
 
@@ -87,25 +204,20 @@ pub async fn logout(
 
     // check that the fields are not empty
     if logout_request.session_id.is_empty() || logout_request.user_id.is_empty() {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
     }
 
     // get session from db
-    let (session_id, user_id) = UserAuthBmc::get_session(&model_maanger, logout_request.session_id)
-        .await
-        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let (session_id, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, logout_request.session_id).await?;
 
     // check that the user_id matches
     if user_id != logout_request.user_id {
-        return Err(Status::invalid_argument(
-            "user_id does not match".to_string(),
-        ));
+        return Err(Error::UserIdMismatch.into());
     }
 
     // delete session from db
-    UserAuthBmc::delete_session(&model_maanger, session_id)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+    UserAuthBmc::delete_session(&model_maanger, session_id).await?;
 
     let res = LogoutResponse { success: true };
     Ok(Response::new(res))
@@ -114,6 +226,7 @@ pub async fn logout(
 pub async fn register(
     register_request: RegisterRequest,
     model_maanger: ModelManager,
+    mailer: Arc<dyn Mailer>,
 ) -> Result<Response<RegisterResponse>, Status> {
     debug!("FN: register - Service to register user");
 
@@ -122,9 +235,10 @@ pub async fn register(
         || register_request.email.is_empty()
         || register_request.password.is_empty()
     {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
     }
 
+    let email = register_request.email.clone();
     let user_auth_for_create = user_auth::UserAuthForCreate {
         username: register_request.username,
         email: register_request.email,
@@ -135,16 +249,18 @@ pub async fn register(
     let db_res =
         user_auth::model_controller::UserAuthBmc::create(&model_maanger, user_auth_for_create)
             .await;
-    match db_res {
+    let user_id = match db_res {
         Ok(id) => {
             debug!("User created with id: {}", id);
+            id
         }
         Err(e) => {
-            return Err(Status::internal(e.to_string()));
+            return Err(Status::from(e));
         }
-    }
+    };
 
-    // TODO: send email to user to confirm email
+    // send email to user to confirm email
+    send_verification_email(&model_maanger, mailer, user_id, email).await?;
 
     let res = RegisterResponse { success: true };
     Ok(Response::new(res))
@@ -158,22 +274,30 @@ pub async fn validate_session(
 
     // check that the fields are not empty
     if validate_request.session_id.is_empty() || validate_request.user_id.is_empty() {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
     }
 
     // get session from db
-    let (_, user_id) = UserAuthBmc::get_session(&model_maanger, validate_request.session_id)
-        .await
-        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let (session_id, session) =
+        UserAuthBmc::get_session(&model_maanger, validate_request.session_id).await?;
 
     // check that the user_id matches
-    if user_id != validate_request.user_id {
-        return Err(Status::invalid_argument(
-            "user_id does not match".to_string(),
-        ));
+    if session.user_id != validate_request.user_id {
+        return Err(Error::UserIdMismatch.into());
     }
 
-    let res = ValidateResponse { success: true };
+    // the active organization is unset once the user is no longer a member of it
+    let session = check_active_organization(&model_maanger, session).await?;
+
+    // update the last time the session has been seen and extend it
+    let (session, expires_at) =
+        UserAuthBmc::touch_session(&model_maanger, session_id.clone(), session).await?;
+
+    let res = ValidateResponse {
+        success: true,
+        session: Some(session_info(session_id, session, true)),
+        expires_at: expires_at.map(|e| e.timestamp()).unwrap_or_default(),
+    };
     Ok(Response::new(res))
 }
 
@@ -189,52 +313,58 @@ pub async fn update_password(
         || update_password_request.old_password.is_empty()
         || update_password_request.new_password.is_empty()
     {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
     }
 
     // get session from db
-    let (_, user_id) = UserAuthBmc::get_session(&model_maanger, update_password_request.session_id)
-        .await
-        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let (session_id, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, update_password_request.session_id).await?;
 
     // check that the user_id matches
     if user_id != update_password_request.user_id {
-        return Err(Status::invalid_argument(
-            "user_id does not match".to_string(),
-        ));
+        return Err(Error::UserIdMismatch.into());
     }
 
     // get user from db
     let user_uuid = Uuid::parse_str(update_password_request.user_id.as_str())
-        .map_err(|e| Status::invalid_argument(e.to_string()))?;
-    let db_res = user_auth::model_controller::UserAuthBmc::get(&model_maanger, user_uuid)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+        .map_err(|_| Error::InvalidId("user_id"))?;
+    let db_res =
+        match user_auth::model_controller::UserAuthBmc::get(&model_maanger, user_uuid).await {
+            Ok(user_auth) => user_auth,
+            Err(Error::Sqlx(sqlx::Error::RowNotFound)) => return Err(Error::SessionNotFound.into()),
+            Err(e) => return Err(e.into()),
+        };
 
     // check if the user is blocked or if it stills needs verification
     if db_res.is_blocked || db_res.needs_verify {
-        return Err(Status::unauthenticated(
-            "user is blocked or needs verification".to_string(),
-        ));
+        return Err(Error::AccountInactive.into());
     }
 
     // check that the old password is correct
-    utils::verify_password(update_password_request.old_password, db_res.password)
-        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let old_password = update_password_request.old_password;
+    let password_hash = db_res.password.clone();
+    utils::spawn_blocking(move || utils::verify_password(old_password, password_hash)).await?;
+
+    // check that the new password respects the password policy
+    password_policy::check(
+        &update_password_request.new_password,
+        &db_res.username,
+        &db_res.email,
+    )
+    .map_err(|e| password_error_status("new_password", e))?;
 
     // generate the struct to update the user
     let mut user_auth_for_update = UserAuthForUpdate::new();
     user_auth_for_update.password = Some(update_password_request.new_password);
 
     // hash the new password
-    let ua_fu = user_auth_for_update
-        .hash_password()
-        .map_err(|e| Status::internal(e.to_string()))?;
+    let ua_fu = utils::spawn_blocking(move || user_auth_for_update.hash_password()).await?;
 
     // update password in db
-    UserAuthBmc::update(&model_maanger, ua_fu, user_uuid)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+    UserAuthBmc::update(&model_maanger, ua_fu, user_uuid).await?;
+
+    // revoke all the other sessions of the user
+    UserAuthBmc::delete_all_sessions(&model_maanger, user_uuid, Some(session_id)).await?;
 
     let res = UpdatePasswordResponse { success: true };
     Ok(Response::new(res))
@@ -248,35 +378,254 @@ pub async fn delete_account(
 
     // check that the fields are not empty
     if delete_account_request.session_id.is_empty() || delete_account_request.user_id.is_empty() {
-        return Err(Status::invalid_argument("one ore more fields are empty"));
+        return Err(Error::FieldsEmpty.into());
     }
 
     // get session from db
-    let (session_id, user_id) =
-        UserAuthBmc::get_session(&model_maanger, delete_account_request.session_id)
-            .await
-            .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, delete_account_request.session_id).await?;
 
     // check that the user_id matches
     if user_id != delete_account_request.user_id {
-        return Err(Status::invalid_argument(
-            "user_id does not match".to_string(),
-        ));
+        return Err(Error::UserIdMismatch.into());
     }
 
     // delete user from db
-    let user_uuid =
-        Uuid::parse_str(user_id.as_str()).map_err(|e| Status::invalid_argument(e.to_string()))?;
-    UserAuthBmc::delete(&model_maanger, user_uuid)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    UserAuthBmc::delete(&model_maanger, user_uuid).await?;
 
-    // TODO: delete all user's sessions
-    // delete current session from db
-    UserAuthBmc::delete_session(&model_maanger, session_id)
-        .await
-        .map_err(|e| Status::internal(e.to_string()))?;
+    // delete all user's sessions from db
+    UserAuthBmc::delete_all_sessions(&model_maanger, user_uuid, None).await?;
 
     let res = DeleteAccountResponse { success: true };
     Ok(Response::new(res))
 }
+
+pub async fn verify_email(
+    verify_email_request: VerifyEmailRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<VerifyEmailResponse>, Status> {
+    debug!("FN: verify_email - Service to confirm the email of a registered user");
+
+    // check that the fields are not empty
+    if verify_email_request.token.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get the user_id from the token (the token can be used only once)
+    let user_id = UserAuthBmc::consume_token(
+        &model_maanger,
+        TokenKind::EmailVerification,
+        verify_email_request.token,
+    )
+    .await?
+    .ok_or(Error::InvalidToken)?;
+
+    // generate the struct to update the user
+    let mut user_auth_for_update = UserAuthForUpdate::new();
+    user_auth_for_update.needs_verify = Some(false);
+
+    // update user in db
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(Error::from)?;
+    UserAuthBmc::update(&model_maanger, user_auth_for_update, user_uuid).await?;
+
+    let res = VerifyEmailResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn resend_verification(
+    resend_verification_request: ResendVerificationRequest,
+    model_maanger: ModelManager,
+    mailer: Arc<dyn Mailer>,
+) -> Result<Response<ResendVerificationResponse>, Status> {
+    debug!("FN: resend_verification - Service to send again the email verification token");
+
+    // check that the fields are not empty
+    if resend_verification_request.email.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get user from db
+    // the response is the same when the user does not exist, so that it can't be used to find
+    // out who has an account
+    let db_res = match UserAuthBmc::get_from_email(
+        &model_maanger,
+        resend_verification_request.email,
+    )
+    .await
+    {
+        Ok(user_auth) => Some(user_auth),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => None,
+        Err(e) => return Err(Status::from(e)),
+    };
+
+    // send the email only if the user still needs verification
+    if let Some(user_auth) = db_res.filter(|ua| ua.needs_verify) {
+        send_in_background("verification", async move {
+            send_verification_email(&model_maanger, mailer, user_auth.id, user_auth.email).await
+        });
+    }
+
+    let res = ResendVerificationResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn request_password_reset(
+    request_password_reset_request: RequestPasswordResetRequest,
+    model_maanger: ModelManager,
+    mailer: Arc<dyn Mailer>,
+) -> Result<Response<RequestPasswordResetResponse>, Status> {
+    debug!("FN: request_password_reset - Service to send a password reset token by email");
+
+    // check that the fields are not empty
+    if request_password_reset_request.username.is_empty()
+        && request_password_reset_request.email.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get user from db
+    // if email is not empty, search by email otherwise search by username
+    let db_res = if !request_password_reset_request.email.is_empty() {
+        UserAuthBmc::get_from_email(&model_maanger, request_password_reset_request.email).await
+    } else {
+        UserAuthBmc::get_from_username(&model_maanger, request_password_reset_request.username)
+            .await
+    };
+
+    // the response is the same when the user does not exist, so that it can't be used to find
+    // out who has an account
+    let db_res = match db_res {
+        Ok(user_auth) => Some(user_auth),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => None,
+        Err(e) => return Err(Status::from(e)),
+    };
+
+    // create the reset token and send it by email
+    if let Some(user_auth) = db_res {
+        send_in_background("password reset", async move {
+            send_password_reset_email(&model_maanger, mailer, user_auth.id, user_auth.email).await
+        });
+    }
+
+    let res = RequestPasswordResetResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn reset_password(
+    reset_password_request: ResetPasswordRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<ResetPasswordResponse>, Status> {
+    debug!("FN: reset_password - Service to set a new password with a reset token");
+
+    // check that the fields are not empty
+    if reset_password_request.token.is_empty() || reset_password_request.new_password.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get the user_id from the token (the token is consumed only once the password is valid)
+    let user_id = UserAuthBmc::get_token(
+        &model_maanger,
+        TokenKind::PasswordReset,
+        reset_password_request.token.clone(),
+    )
+    .await?
+    .ok_or(Error::InvalidToken)?;
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(Error::from)?;
+
+    // check that the new password respects the password policy, the token of a deleted user
+    // is no longer valid
+    let db_res = match UserAuthBmc::get(&model_maanger, user_uuid).await {
+        Ok(user_auth) => user_auth,
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => return Err(Error::InvalidToken.into()),
+        Err(e) => return Err(e.into()),
+    };
+    password_policy::check(
+        &reset_password_request.new_password,
+        &db_res.username,
+        &db_res.email,
+    )
+    .map_err(|e| password_error_status("new_password", e))?;
+
+    // the token can be used only once
+    UserAuthBmc::consume_token(
+        &model_maanger,
+        TokenKind::PasswordReset,
+        reset_password_request.token,
+    )
+    .await?
+    .filter(|id| *id == user_id)
+    .ok_or(Error::InvalidToken)?;
+
+    // generate the struct to update the user
+    let mut user_auth_for_update = UserAuthForUpdate::new();
+    user_auth_for_update.password = Some(reset_password_request.new_password);
+
+    // hash the new password
+    let ua_fu = utils::spawn_blocking(move || user_auth_for_update.hash_password()).await?;
+
+    // update password in db
+    UserAuthBmc::update(&model_maanger, ua_fu, user_uuid).await?;
+
+    // revoke all the sessions of the user
+    UserAuthBmc::delete_all_sessions(&model_maanger, user_uuid, None).await?;
+
+    let res = ResetPasswordResponse { success: true };
+    Ok(Response::new(res))
+}
+
+/// Creates an email verification token for the user and sends it by email
+async fn send_verification_email(
+    model_maanger: &ModelManager,
+    mailer: Arc<dyn Mailer>,
+    user_id: Uuid,
+    email: String,
+) -> Result<(), Error> {
+    let token = UserAuthBmc::create_token(
+        model_maanger,
+        TokenKind::EmailVerification,
+        user_id,
+        config().EMAIL_VERIFICATION_EXPIRATION,
+    )
+    .await?;
+
+    mailer.send(Mail::email_verification(email, token)).await?;
+
+    Ok(())
+}
+
+/// Creates a password reset token for the user and sends it by email
+async fn send_password_reset_email(
+    model_maanger: &ModelManager,
+    mailer: Arc<dyn Mailer>,
+    user_id: Uuid,
+    email: String,
+) -> Result<(), Error> {
+    let token = UserAuthBmc::create_token(
+        model_maanger,
+        TokenKind::PasswordReset,
+        user_id,
+        config().PASSWORD_RESET_EXPIRATION,
+    )
+    .await?;
+
+    mailer.send(Mail::password_reset(email, token)).await?;
+
+    Ok(())
+}
+
+/// Sends an email in the background, so that the response (its time and its errors) is the same
+/// whether the user exists or not, the failures are only logged
+fn send_in_background<F>(mail: &'static str, send: F)
+where
+    F: Future<Output = Result<(), Error>> + Send + 'static,
+{
+    tokio::spawn(
+        async move {
+            if let Err(e) = send.await {
+                error!("Failed to send the {mail} email: {e:?}");
+            }
+        }
+        .in_current_span(),
+    );
+}
@@ -0,0 +1,60 @@
+use tonic::{Response, Status};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    config::config,
+    error::Error,
+    jwt,
+    mandos_auth::{GetJwksRequest, GetJwksResponse, Jwk},
+    model::{rbac::model_controller::UserRoleBmc, ModelManager},
+};
+
+pub async fn get_jwks(_: GetJwksRequest) -> Result<Response<GetJwksResponse>, Status> {
+    debug!("FN: get_jwks - Service to get the public keys that verify the access tokens");
+
+    let keys = jwt::jwks()
+        .into_iter()
+        .map(|k| Jwk {
+            kty: k.kty,
+            kid: k.kid,
+            alg: k.alg,
+            r#use: "sig".to_string(),
+            crv: k.crv,
+            x: k.x,
+            n: k.n,
+            e: k.e,
+        })
+        .collect();
+
+    let res = GetJwksResponse { keys };
+    Ok(Response::new(res))
+}
+
+/// Returns JwtNotConfigured if access tokens can't be issued because no key is configured
+pub fn check_access_token_available() -> Result<(), Error> {
+    if config().JWT_KEYS.get().is_empty() {
+        return Err(Error::JwtNotConfigured.into());
+    }
+
+    Ok(())
+}
+
+/// Returns an access token for the session, with the roles of the user, and its expiration
+pub async fn issue_access_token(
+    model_maanger: &ModelManager,
+    user_id: Uuid,
+    session_id: &str,
+) -> Result<(String, i64), Status> {
+    let roles = UserRoleBmc::get_roles(model_maanger, user_id)
+        .await?
+        .into_iter()
+        .map(|r| r.name)
+        .collect();
+
+    Ok(jwt::issue_access_token(
+        user_id.to_string(),
+        session_id,
+        roles,
+    )?)
+}
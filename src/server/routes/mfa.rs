@@ -0,0 +1,397 @@
+use tonic::{Response, Status};
+use tracing::debug;
+use uuid::Uuid;
+
+use super::{
+    jwt::issue_access_token,
+    session::{issue_refresh_token, start_session},
+    throttle::{
+        clear_account_login_failures, count_login_attempt, login_throttle_keys, undo_login_attempt,
+    },
+};
+use crate::{
+    error::Error,
+    mandos_auth::{
+        CompleteMfaLoginRequest, CompleteMfaLoginResponse, ConfirmTotpRequest, ConfirmTotpResponse,
+        CountRecoveryCodesRequest, CountRecoveryCodesResponse, DisableTotpRequest,
+        DisableTotpResponse, EnrollTotpRequest, EnrollTotpResponse, RegenerateRecoveryCodesRequest,
+        RegenerateRecoveryCodesResponse,
+    },
+    metrics::{metrics, LoginFailure, LoginMethod},
+    model::{
+        audit_log::{model_controller::AuditLogBmc, AuditEvent, AuditLog},
+        session::Session,
+        user_auth::model_controller::UserAuthBmc,
+        user_mfa::{self, model_controller::UserMfaBmc, UserMfa, UserMfaForUpdate},
+        ModelManager,
+    },
+    server::client_info::ClientInfo,
+    utils,
+};
+
+pub async fn complete_mfa_login(
+    complete_mfa_login_request: CompleteMfaLoginRequest,
+    model_maanger: ModelManager,
+    client_info: ClientInfo,
+) -> Result<Response<CompleteMfaLoginResponse>, Status> {
+    debug!("FN: complete_mfa_login - Service to complete the login with the second factor");
+
+    // check that the fields are not empty
+    if complete_mfa_login_request.mfa_challenge.is_empty()
+        || (complete_mfa_login_request.code.is_empty()
+            && complete_mfa_login_request.recovery_code.is_empty())
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get the challenge created by login (it stays valid if the code is wrong)
+    let challenge = UserMfaBmc::get_challenge(
+        &model_maanger,
+        complete_mfa_login_request.mfa_challenge.clone(),
+    )
+    .await?
+    .ok_or_else(|| {
+        metrics().login_failed(LoginFailure::InvalidChallenge);
+        Error::MfaChallengeInvalid
+    })?;
+    let user_uuid = Uuid::parse_str(challenge.user_id.as_str()).map_err(Error::from)?;
+
+    // check that the account and the client ip are not blocked by too many failed attempts and
+    // count the attempt, it is undone if the code is right
+    let throttle_keys = login_throttle_keys(&client_info, Some(user_uuid.to_string()));
+    let attempts = count_login_attempt(&model_maanger, &throttle_keys)
+        .await
+        .inspect_err(|_| metrics().login_failed(LoginFailure::Throttled))?;
+
+    // get user from db, a challenge of a deleted user is no longer valid
+    let db_res = match UserAuthBmc::get(&model_maanger, user_uuid).await {
+        Ok(user_auth) => user_auth,
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => return Err(Error::MfaChallengeInvalid.into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    // check if the user is blocked or if it stills needs verification
+    if db_res.is_blocked || db_res.needs_verify {
+        metrics().login_failed(LoginFailure::Blocked);
+        return Err(Error::AccountInactive.into());
+    }
+
+    // check the TOTP code or the recovery code
+    let user_mfa = get_user_mfa(&model_maanger, user_uuid)
+        .await?
+        .filter(|um| um.totp_enabled)
+        .ok_or(Error::MfaNotEnabled)?;
+    let use_recovery_code = !complete_mfa_login_request.recovery_code.is_empty();
+    let valid = if use_recovery_code {
+        UserMfaBmc::use_recovery_code(
+            &model_maanger,
+            user_uuid,
+            complete_mfa_login_request.recovery_code,
+        )
+        .await
+    } else {
+        UserMfaBmc::verify_totp(&model_maanger, &user_mfa, complete_mfa_login_request.code).await
+    }?;
+    if !valid {
+        metrics().login_failed(LoginFailure::InvalidCode);
+        return Err(Error::MfaCodeInvalid.into());
+    }
+    undo_login_attempt(&model_maanger, &throttle_keys, &attempts).await?;
+
+    // keep track of the recovery codes used
+    if use_recovery_code {
+        let audit_log = AuditLog::new(
+            user_uuid,
+            AuditEvent::RecoveryCodeUsed,
+            client_info.ip.clone(),
+            client_info.user_agent.clone(),
+        );
+        AuditLogBmc::create(&model_maanger, audit_log).await?;
+    }
+
+    // the challenge can be used only once
+    UserMfaBmc::consume_challenge(&model_maanger, complete_mfa_login_request.mfa_challenge)
+        .await?
+        .ok_or(Error::MfaChallengeInvalid)?;
+
+    // create session in the db
+    let (session_id, expires_at, idle_expires_at) = start_session(
+        &model_maanger,
+        user_uuid,
+        client_info,
+        challenge.remember_me,
+    )
+    .await?;
+    clear_account_login_failures(&model_maanger, user_uuid).await?;
+
+    let (refresh_token, refresh_token_expires_at) =
+        issue_refresh_token(&model_maanger, user_uuid, session_id.clone()).await?;
+
+    let (access_token, access_token_expires_at) = if challenge.issue_access_token {
+        issue_access_token(&model_maanger, user_uuid, &session_id).await?
+    } else {
+        Default::default()
+    };
+
+    metrics().login_succeeded(LoginMethod::Mfa);
+
+    let res = CompleteMfaLoginResponse {
+        session_id,
+        expires_at,
+        access_token,
+        access_token_expires_at,
+        refresh_token,
+        refresh_token_expires_at,
+        idle_expires_at,
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn enroll_totp(
+    enroll_totp_request: EnrollTotpRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<EnrollTotpResponse>, Status> {
+    debug!("FN: enroll_totp - Service to generate a new TOTP secret for a user");
+
+    // check that the fields are not empty
+    if enroll_totp_request.session_id.is_empty() || enroll_totp_request.user_id.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, enroll_totp_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != enroll_totp_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get user from db
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let db_res = match UserAuthBmc::get(&model_maanger, user_uuid).await {
+        Ok(user_auth) => user_auth,
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => return Err(Error::SessionNotFound.into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    // a confirmed second factor has to be disabled before enrolling a new one
+    let user_mfa = get_user_mfa(&model_maanger, user_uuid).await?;
+    if user_mfa.as_ref().is_some_and(|um| um.totp_enabled) {
+        return Err(Error::MfaAlreadyEnabled.into());
+    }
+
+    // generate a new secret, replacing the one of an unconfirmed enrollment
+    let secret = user_mfa::generate_totp_secret();
+    match user_mfa {
+        Some(_) => {
+            let mut user_mfa_for_update = UserMfaForUpdate::new();
+            user_mfa_for_update.totp_secret = Some(utils::encrypt_secret(&secret)?);
+            UserMfaBmc::update(&model_maanger, user_mfa_for_update, user_uuid).await?;
+        }
+        None => {
+            let user_mfa = UserMfa::new(user_uuid, &secret)?;
+            UserMfaBmc::create(&model_maanger, user_mfa).await?;
+        }
+    }
+
+    let totp = user_mfa::new_totp(secret, db_res.email)?;
+
+    let res = EnrollTotpResponse {
+        secret: totp.get_secret_base32(),
+        otpauth_uri: totp.get_url(),
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn confirm_totp(
+    confirm_totp_request: ConfirmTotpRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<ConfirmTotpResponse>, Status> {
+    debug!("FN: confirm_totp - Service to enable MFA after the user entered a valid code");
+
+    // check that the fields are not empty
+    if confirm_totp_request.session_id.is_empty()
+        || confirm_totp_request.user_id.is_empty()
+        || confirm_totp_request.code.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, confirm_totp_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != confirm_totp_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get the enrollment of the user
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let user_mfa = get_user_mfa(&model_maanger, user_uuid)
+        .await?
+        .ok_or(Error::MfaEnrollmentNotStarted)?;
+    if user_mfa.totp_enabled {
+        return Err(Error::MfaAlreadyEnabled.into());
+    }
+
+    // check the TOTP code
+    let valid =
+        UserMfaBmc::verify_totp(&model_maanger, &user_mfa, confirm_totp_request.code).await?;
+    if !valid {
+        return Err(Error::MfaCodeInvalid.into());
+    }
+
+    // enable MFA
+    let mut user_mfa_for_update = UserMfaForUpdate::new();
+    user_mfa_for_update.totp_enabled = Some(true);
+    UserMfaBmc::update(&model_maanger, user_mfa_for_update, user_uuid).await?;
+
+    // generate the recovery codes to use if the device is lost
+    let recovery_codes = UserMfaBmc::replace_recovery_codes(&model_maanger, user_uuid).await?;
+
+    let res = ConfirmTotpResponse {
+        success: true,
+        recovery_codes,
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn disable_totp(
+    disable_totp_request: DisableTotpRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<DisableTotpResponse>, Status> {
+    debug!("FN: disable_totp - Service to disable MFA");
+
+    // check that the fields are not empty
+    if disable_totp_request.session_id.is_empty()
+        || disable_totp_request.user_id.is_empty()
+        || disable_totp_request.code.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, disable_totp_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != disable_totp_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get the second factor of the user
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let user_mfa = get_user_mfa(&model_maanger, user_uuid)
+        .await?
+        .filter(|um| um.totp_enabled)
+        .ok_or(Error::MfaNotEnabled)?;
+
+    // check the TOTP code, so that a stolen session is not enough to disable MFA
+    let valid =
+        UserMfaBmc::verify_totp(&model_maanger, &user_mfa, disable_totp_request.code).await?;
+    if !valid {
+        return Err(Error::MfaCodeInvalid.into());
+    }
+
+    // delete the secret and the recovery codes
+    UserMfaBmc::delete(&model_maanger, user_uuid).await?;
+    UserMfaBmc::delete_recovery_codes(&model_maanger, user_uuid).await?;
+
+    let res = DisableTotpResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn regenerate_recovery_codes(
+    regenerate_recovery_codes_request: RegenerateRecoveryCodesRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<RegenerateRecoveryCodesResponse>, Status> {
+    debug!("FN: regenerate_recovery_codes - Service to replace the recovery codes of a user");
+
+    // check that the fields are not empty
+    if regenerate_recovery_codes_request.session_id.is_empty()
+        || regenerate_recovery_codes_request.user_id.is_empty()
+        || regenerate_recovery_codes_request.code.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, regenerate_recovery_codes_request.session_id)
+            .await?;
+
+    // check that the user_id matches
+    if user_id != regenerate_recovery_codes_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get the second factor of the user
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let user_mfa = get_user_mfa(&model_maanger, user_uuid)
+        .await?
+        .filter(|um| um.totp_enabled)
+        .ok_or(Error::MfaNotEnabled)?;
+
+    // check the TOTP code, so that a stolen session is not enough to get new codes
+    let valid = UserMfaBmc::verify_totp(
+        &model_maanger,
+        &user_mfa,
+        regenerate_recovery_codes_request.code,
+    )
+    .await?;
+    if !valid {
+        return Err(Error::MfaCodeInvalid.into());
+    }
+
+    // replace the recovery codes
+    let recovery_codes = UserMfaBmc::replace_recovery_codes(&model_maanger, user_uuid).await?;
+
+    let res = RegenerateRecoveryCodesResponse { recovery_codes };
+    Ok(Response::new(res))
+}
+
+pub async fn count_recovery_codes(
+    count_recovery_codes_request: CountRecoveryCodesRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<CountRecoveryCodesResponse>, Status> {
+    debug!("FN: count_recovery_codes - Service to get the number of unused recovery codes");
+
+    // check that the fields are not empty
+    if count_recovery_codes_request.session_id.is_empty()
+        || count_recovery_codes_request.user_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, count_recovery_codes_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != count_recovery_codes_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get the recovery codes left
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let recovery_codes = UserMfaBmc::get_recovery_codes(&model_maanger, user_uuid).await?;
+
+    let res = CountRecoveryCodesResponse {
+        remaining: recovery_codes.len() as u64,
+    };
+    Ok(Response::new(res))
+}
+
+/// Returns the MFA settings of the user, None if the user never enrolled
+async fn get_user_mfa(
+    model_maanger: &ModelManager,
+    user_id: Uuid,
+) -> Result<Option<UserMfa>, Status> {
+    match UserMfaBmc::get(model_maanger, user_id).await {
+        Ok(user_mfa) => Ok(Some(user_mfa)),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => Ok(None),
+        Err(e) => Err(Status::from(e)),
+    }
+}
@@ -1 +1,7 @@
 pub mod auth;
+pub mod jwt;
+pub mod mfa;
+pub mod organization;
+pub mod rbac;
+pub mod session;
+pub mod throttle;
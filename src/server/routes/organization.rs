@@ -0,0 +1,357 @@
+use std::{str::FromStr, sync::Arc};
+
+use tonic::{Response, Status};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    mailer::{Mail, Mailer},
+    mandos_auth::{
+        AcceptInvitationRequest, AcceptInvitationResponse, CreateOrganizationRequest,
+        CreateOrganizationResponse, InviteMemberRequest, InviteMemberResponse,
+        ListOrganizationsRequest, ListOrganizationsResponse, OrganizationInfo, RemoveMemberRequest,
+        RemoveMemberResponse, SwitchOrganizationRequest, SwitchOrganizationResponse,
+    },
+    model::{
+        organization::{
+            model_controller::OrganizationBmc, Organization, OrganizationInvitation,
+            OrganizationMember, OrganizationRole,
+        },
+        session::Session,
+        user_auth::{identifiers, model_controller::UserAuthBmc},
+        ModelManager,
+    },
+};
+
+pub async fn create_organization(
+    create_organization_request: CreateOrganizationRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<CreateOrganizationResponse>, Status> {
+    debug!("FN: create_organization - Service to create an organization");
+
+    // check that the fields are not empty
+    if create_organization_request.session_id.is_empty()
+        || create_organization_request.user_id.is_empty()
+        || create_organization_request.name.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, create_organization_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != create_organization_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let organization = Organization::new(create_organization_request.name)?;
+
+    // the user creating the organization is its first owner
+    let organization_id = OrganizationBmc::create(&model_maanger, organization, user_uuid).await?;
+
+    let res = CreateOrganizationResponse {
+        organization_id: organization_id.to_string(),
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn invite_member(
+    invite_member_request: InviteMemberRequest,
+    model_maanger: ModelManager,
+    mailer: Arc<dyn Mailer>,
+) -> Result<Response<InviteMemberResponse>, Status> {
+    debug!("FN: invite_member - Service to invite someone to join an organization");
+
+    // check that the fields are not empty
+    if invite_member_request.session_id.is_empty()
+        || invite_member_request.user_id.is_empty()
+        || invite_member_request.organization_id.is_empty()
+        || invite_member_request.email.is_empty()
+        || invite_member_request.role.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, invite_member_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != invite_member_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let organization_uuid = Uuid::parse_str(invite_member_request.organization_id.as_str())
+        .map_err(|_| Error::InvalidId("organization_id"))?;
+    let role = OrganizationRole::from_str(&invite_member_request.role)?;
+    let email = identifiers::normalize(&invite_member_request.email);
+    identifiers::validate_email(&email)?;
+
+    // check that the user can give the role to the new member
+    let member = get_member(&model_maanger, organization_uuid, user_uuid).await?;
+    if !member.role()?.can_manage(role) {
+        return Err(Error::OrganizationRoleNotAllowed.into());
+    }
+
+    // the caller is a member, telling that the organization is gone reveals nothing
+    let organization = match OrganizationBmc::get(&model_maanger, organization_uuid).await {
+        Ok(organization) => organization,
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => {
+            return Err(Error::EntityNotFound("organization").into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // create the invitation and send it by email
+    let invitation = OrganizationInvitation {
+        organization_id: organization_uuid,
+        email: identifiers::canonical(&email),
+        role,
+    };
+    let token = OrganizationBmc::create_invitation(&model_maanger, invitation).await?;
+
+    mailer
+        .send(Mail::organization_invitation(
+            email,
+            organization.name,
+            token,
+        ))
+        .await?;
+
+    let res = InviteMemberResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn accept_invitation(
+    accept_invitation_request: AcceptInvitationRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<AcceptInvitationResponse>, Status> {
+    debug!("FN: accept_invitation - Service to join an organization with an invitation");
+
+    // check that the fields are not empty
+    if accept_invitation_request.session_id.is_empty()
+        || accept_invitation_request.user_id.is_empty()
+        || accept_invitation_request.token.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, accept_invitation_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != accept_invitation_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get user from db
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let db_res = match UserAuthBmc::get(&model_maanger, user_uuid).await {
+        Ok(user_auth) => user_auth,
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => return Err(Error::SessionNotFound.into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    // only the invited email can accept the invitation (the token is consumed only then)
+    let invitation =
+        OrganizationBmc::get_invitation(&model_maanger, accept_invitation_request.token.clone())
+            .await?
+            .ok_or(Error::InvalidToken)?;
+    if invitation.email != db_res.email_normalized {
+        return Err(Error::InvitationEmailMismatch.into());
+    }
+
+    // the invitation can be used only once
+    OrganizationBmc::consume_invitation(&model_maanger, accept_invitation_request.token)
+        .await?
+        .ok_or(Error::InvalidToken)?;
+
+    // members keep their current role
+    let member =
+        OrganizationBmc::get_member(&model_maanger, invitation.organization_id, user_uuid).await?;
+    if member.is_none() {
+        OrganizationBmc::add_member(
+            &model_maanger,
+            OrganizationMember::new(invitation.organization_id, user_uuid, invitation.role),
+        )
+        .await?;
+    }
+
+    let res = AcceptInvitationResponse {
+        organization_id: invitation.organization_id.to_string(),
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn remove_member(
+    remove_member_request: RemoveMemberRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<RemoveMemberResponse>, Status> {
+    debug!("FN: remove_member - Service to remove a member from an organization");
+
+    // check that the fields are not empty
+    if remove_member_request.session_id.is_empty()
+        || remove_member_request.user_id.is_empty()
+        || remove_member_request.organization_id.is_empty()
+        || remove_member_request.member_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, remove_member_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != remove_member_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let organization_uuid = Uuid::parse_str(remove_member_request.organization_id.as_str())
+        .map_err(|_| Error::InvalidId("organization_id"))?;
+    let member_uuid = Uuid::parse_str(remove_member_request.member_id.as_str())
+        .map_err(|_| Error::InvalidId("member_id"))?;
+
+    let member = get_member(&model_maanger, organization_uuid, user_uuid).await?;
+    let removed_member =
+        OrganizationBmc::get_member(&model_maanger, organization_uuid, member_uuid)
+            .await?
+            .ok_or(Error::EntityNotFound("member"))?;
+    let removed_role = removed_member.role()?;
+
+    // members can leave, otherwise the user has to be allowed to manage the removed member
+    if member_uuid != user_uuid && !member.role()?.can_manage(removed_role) {
+        return Err(Error::OrganizationRoleNotAllowed.into());
+    }
+
+    // the last owner can't leave the organization
+    OrganizationBmc::remove_member(&model_maanger, organization_uuid, member_uuid).await?;
+
+    let res = RemoveMemberResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn list_organizations(
+    list_organizations_request: ListOrganizationsRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<ListOrganizationsResponse>, Status> {
+    debug!("FN: list_organizations - Service to list the organizations of a user");
+
+    // check that the fields are not empty
+    if list_organizations_request.session_id.is_empty()
+        || list_organizations_request.user_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, list_organizations_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != list_organizations_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let organizations = OrganizationBmc::get_all_for_user(&model_maanger, user_uuid).await?;
+
+    let res = ListOrganizationsResponse {
+        organizations: organizations
+            .into_iter()
+            .map(|(organization, member)| OrganizationInfo {
+                organization_id: organization.id.to_string(),
+                name: organization.name,
+                role: member.role,
+            })
+            .collect(),
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn switch_organization(
+    switch_organization_request: SwitchOrganizationRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<SwitchOrganizationResponse>, Status> {
+    debug!("FN: switch_organization - Service to change the active organization of a session");
+
+    // check that the fields are not empty (an empty organization_id unsets the active one)
+    if switch_organization_request.session_id.is_empty()
+        || switch_organization_request.user_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (session_id, session) =
+        UserAuthBmc::get_session(&model_maanger, switch_organization_request.session_id).await?;
+
+    // check that the user_id matches
+    if session.user_id != switch_organization_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // the user has to be a member of the new active organization
+    let active_organization_id = if switch_organization_request.organization_id.is_empty() {
+        None
+    } else {
+        let user_uuid =
+            Uuid::parse_str(session.user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+        let organization_uuid =
+            Uuid::parse_str(switch_organization_request.organization_id.as_str())
+                .map_err(|_| Error::InvalidId("organization_id"))?;
+        get_member(&model_maanger, organization_uuid, user_uuid).await?;
+
+        Some(organization_uuid.to_string())
+    };
+
+    let session = Session {
+        active_organization_id,
+        ..session
+    };
+    UserAuthBmc::touch_session(&model_maanger, session_id, session).await?;
+
+    let res = SwitchOrganizationResponse { success: true };
+    Ok(Response::new(res))
+}
+
+/// Unsets the active organization of the session if the user is no longer a member of it
+pub async fn check_active_organization(
+    model_maanger: &ModelManager,
+    session: Session,
+) -> Result<Session, Status> {
+    let Some(active_organization_id) = &session.active_organization_id else {
+        return Ok(session);
+    };
+
+    let user_uuid = Uuid::parse_str(session.user_id.as_str()).map_err(Error::from)?;
+    let organization_uuid =
+        Uuid::parse_str(active_organization_id.as_str()).map_err(Error::from)?;
+    let member = OrganizationBmc::get_member(model_maanger, organization_uuid, user_uuid).await?;
+
+    match member {
+        Some(_) => Ok(session),
+        None => Ok(Session {
+            active_organization_id: None,
+            ..session
+        }),
+    }
+}
+
+/// Returns the membership of the user, permission_denied if the user is not a member
+async fn get_member(
+    model_maanger: &ModelManager,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<OrganizationMember, Status> {
+    OrganizationBmc::get_member(model_maanger, organization_id, user_id)
+        .await?
+        .ok_or_else(|| Error::NotOrganizationMember.into())
+}
@@ -0,0 +1,213 @@
+use tonic::{Response, Status};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    mandos_auth::{
+        AssignRoleRequest, AssignRoleResponse, CheckPermissionRequest, CheckPermissionResponse,
+        CreateRoleRequest, CreateRoleResponse, DeleteRoleRequest, DeleteRoleResponse,
+        GrantPermissionRequest, GrantPermissionResponse, RevokePermissionRequest,
+        RevokePermissionResponse, UnassignRoleRequest, UnassignRoleResponse,
+    },
+    model::{
+        rbac::{
+            model_controller::{PermissionBmc, RoleBmc, UserRoleBmc},
+            Role,
+        },
+        user_auth::model_controller::UserAuthBmc,
+        ModelManager,
+    },
+};
+
+pub async fn create_role(
+    create_role_request: CreateRoleRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<CreateRoleResponse>, Status> {
+    debug!("FN: create_role - Service to create a role");
+
+    // check that the fields are not empty
+    if create_role_request.name.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let role = Role::new(create_role_request.name, create_role_request.description)?;
+    let role_id = RoleBmc::create(&model_maanger, role).await?;
+
+    let res = CreateRoleResponse {
+        role_id: role_id.to_string(),
+    };
+    Ok(Response::new(res))
+}
+
+pub async fn delete_role(
+    delete_role_request: DeleteRoleRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<DeleteRoleResponse>, Status> {
+    debug!("FN: delete_role - Service to delete a role");
+
+    // check that the fields are not empty
+    if delete_role_request.name.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let role = get_role(&model_maanger, delete_role_request.name).await?;
+
+    // the permissions of the role and its assignments are deleted with it
+    RoleBmc::delete(&model_maanger, role.id).await?;
+
+    let res = DeleteRoleResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn grant_permission(
+    grant_permission_request: GrantPermissionRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<GrantPermissionResponse>, Status> {
+    debug!("FN: grant_permission - Service to grant a permission to a role");
+
+    // check that the fields are not empty
+    if grant_permission_request.role.is_empty() || grant_permission_request.permission.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let role = get_role(&model_maanger, grant_permission_request.role).await?;
+
+    // permissions are created the first time they are granted
+    let permission_id =
+        PermissionBmc::get_or_create(&model_maanger, grant_permission_request.permission).await?;
+
+    RoleBmc::grant_permission(&model_maanger, role.id, permission_id).await?;
+
+    let res = GrantPermissionResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn revoke_permission(
+    revoke_permission_request: RevokePermissionRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<RevokePermissionResponse>, Status> {
+    debug!("FN: revoke_permission - Service to revoke a permission from a role");
+
+    // check that the fields are not empty
+    if revoke_permission_request.role.is_empty() || revoke_permission_request.permission.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let role = get_role(&model_maanger, revoke_permission_request.role).await?;
+
+    let permission =
+        match PermissionBmc::get_from_name(&model_maanger, revoke_permission_request.permission)
+            .await
+        {
+            Ok(permission) => permission,
+            Err(Error::Sqlx(sqlx::Error::RowNotFound)) => {
+                return Err(Error::EntityNotFound("permission").into());
+            }
+            Err(e) => return Err(Status::from(e)),
+        };
+
+    let revoked = RoleBmc::revoke_permission(&model_maanger, role.id, permission.id).await?;
+    if !revoked {
+        return Err(Error::PermissionNotGranted.into());
+    }
+
+    let res = RevokePermissionResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn assign_role(
+    assign_role_request: AssignRoleRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<AssignRoleResponse>, Status> {
+    debug!("FN: assign_role - Service to assign a role to a user");
+
+    // check that the fields are not empty
+    if assign_role_request.user_id.is_empty() || assign_role_request.role.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let user_id = get_user_id(&model_maanger, assign_role_request.user_id).await?;
+    let role = get_role(&model_maanger, assign_role_request.role).await?;
+
+    UserRoleBmc::assign(&model_maanger, user_id, role.id).await?;
+
+    let res = AssignRoleResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn unassign_role(
+    unassign_role_request: UnassignRoleRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<UnassignRoleResponse>, Status> {
+    debug!("FN: unassign_role - Service to remove a role from a user");
+
+    // check that the fields are not empty
+    if unassign_role_request.user_id.is_empty() || unassign_role_request.role.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    let user_id = get_user_id(&model_maanger, unassign_role_request.user_id).await?;
+    let role = get_role(&model_maanger, unassign_role_request.role).await?;
+
+    let unassigned = UserRoleBmc::unassign(&model_maanger, user_id, role.id).await?;
+    if !unassigned {
+        return Err(Error::RoleNotAssigned.into());
+    }
+
+    let res = UnassignRoleResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn check_permission(
+    check_permission_request: CheckPermissionRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<CheckPermissionResponse>, Status> {
+    debug!("FN: check_permission - Service to check if the user of a session has a permission");
+
+    // check that the fields are not empty
+    if check_permission_request.session_id.is_empty()
+        || check_permission_request.permission.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, session) =
+        UserAuthBmc::get_session(&model_maanger, check_permission_request.session_id).await?;
+
+    let user_uuid = Uuid::parse_str(session.user_id.as_str()).map_err(Error::from)?;
+    let allowed = UserRoleBmc::has_permission(
+        &model_maanger,
+        user_uuid,
+        check_permission_request.permission,
+    )
+    .await?;
+
+    let res = CheckPermissionResponse {
+        allowed,
+        user_id: session.user_id,
+    };
+    Ok(Response::new(res))
+}
+
+/// Returns the role with the name, not_found if it does not exist
+async fn get_role(model_maanger: &ModelManager, name: String) -> Result<Role, Status> {
+    match RoleBmc::get_from_name(model_maanger, name).await {
+        Ok(role) => Ok(role),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => Err(Error::EntityNotFound("role").into()),
+        Err(e) => Err(Status::from(e)),
+    }
+}
+
+/// Returns the id of an existing user, not_found if it does not exist
+async fn get_user_id(model_maanger: &ModelManager, user_id: String) -> Result<Uuid, Status> {
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+
+    match UserAuthBmc::get(model_maanger, user_uuid).await {
+        Ok(user_auth) => Ok(user_auth.id),
+        Err(Error::Sqlx(sqlx::Error::RowNotFound)) => Err(Error::EntityNotFound("user").into()),
+        Err(e) => Err(Status::from(e)),
+    }
+}
@@ -0,0 +1,305 @@
+use tonic::{Response, Status};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::jwt::{check_access_token_available, issue_access_token};
+use crate::{
+    config::config,
+    error::Error,
+    jwt,
+    mandos_auth::{
+        ListSessionsRequest, ListSessionsResponse, RefreshSessionRequest, RefreshSessionResponse,
+        RevokeAllSessionsRequest, RevokeAllSessionsResponse, RevokeSessionRequest,
+        RevokeSessionResponse, SessionInfo,
+    },
+    model::{
+        audit_log::{model_controller::AuditLogBmc, AuditEvent, AuditLog},
+        refresh_token::{model_controller::RefreshTokenBmc, RefreshOutcome},
+        session::Session,
+        user_auth::{model_controller::UserAuthBmc, UserAuthForUpdate},
+        ModelManager,
+    },
+    server::client_info::ClientInfo,
+};
+
+/// Returns a new session for the user with the client info and the lifetime from the config
+pub fn new_session(user_id: Uuid, client_info: ClientInfo, remember_me: bool) -> Session {
+    let lifetime = if remember_me {
+        config().SESSION_REMEMBER_ME_LIFETIME
+    } else {
+        config().SESSION_LIFETIME
+    };
+
+    Session {
+        ip: client_info.ip,
+        user_agent: client_info.user_agent,
+        device_label: client_info.device_label,
+        ..Session::new(user_id.to_string())
+    }
+    .with_lifetime(lifetime, config().SESSION_IDLE_TIMEOUT)
+}
+
+/// Updates the last_login of the user and creates a new session
+/// Returns the session id, when it expires and when it expires if not used (unix seconds)
+pub async fn start_session(
+    model_maanger: &ModelManager,
+    user_id: Uuid,
+    client_info: ClientInfo,
+    remember_me: bool,
+) -> Result<(String, i64, i64), Status> {
+    // generate the struct to update the user (last_login)
+    let mut user_auth_for_update = UserAuthForUpdate::new();
+    user_auth_for_update.last_login = Some(chrono::Utc::now());
+
+    // update user's last_login in db
+    UserAuthBmc::update(model_maanger, user_auth_for_update, user_id).await?;
+
+    // create session in the db
+    let session = new_session(user_id, client_info, remember_me);
+    let now = chrono::Utc::now();
+    let expiration = session.ttl(now).unwrap_or_default();
+    let expires_at = session
+        .expires_at
+        .map(|e| e.timestamp())
+        .unwrap_or_default();
+    let session_id = UserAuthBmc::create_session(model_maanger, session, expiration).await?;
+
+    Ok((session_id, expires_at, now.timestamp() + expiration as i64))
+}
+
+/// Starts a new refresh token family for the session
+/// Returns the refresh token and when it expires (unix seconds)
+pub async fn issue_refresh_token(
+    model_maanger: &ModelManager,
+    user_id: Uuid,
+    session_id: String,
+) -> Result<(String, i64), Status> {
+    let (refresh_token, family) =
+        RefreshTokenBmc::create_family(model_maanger, user_id, session_id).await?;
+
+    Ok((refresh_token, family.expires_at.timestamp()))
+}
+
+/// Converts a session record to the gRPC message
+pub fn session_info(session_id: String, session: Session, current: bool) -> SessionInfo {
+    SessionInfo {
+        sid: jwt::session_sid(&session_id),
+        session_id,
+        current,
+        created_at: session.created_at.timestamp(),
+        last_seen_at: session.last_seen_at.timestamp(),
+        ip: session.ip.unwrap_or_default(),
+        user_agent: session.user_agent.unwrap_or_default(),
+        device_label: session.device_label.unwrap_or_default(),
+        active_organization_id: session.active_organization_id.unwrap_or_default(),
+    }
+}
+
+pub async fn list_sessions(
+    list_sessions_request: ListSessionsRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<ListSessionsResponse>, Status> {
+    debug!("FN: list_sessions - Service to list all the active sessions of a user");
+
+    // check that the fields are not empty
+    if list_sessions_request.session_id.is_empty() || list_sessions_request.user_id.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (session_id, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, list_sessions_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != list_sessions_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // get all the sessions of the user
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let user_sessions = UserAuthBmc::list_sessions(&model_maanger, user_uuid).await?;
+
+    let sessions = user_sessions
+        .into_iter()
+        .map(|(id, session)| {
+            let current = id == session_id;
+            session_info(id, session, current)
+        })
+        .collect();
+
+    let res = ListSessionsResponse { sessions };
+    Ok(Response::new(res))
+}
+
+pub async fn revoke_session(
+    revoke_session_request: RevokeSessionRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<RevokeSessionResponse>, Status> {
+    debug!("FN: revoke_session - Service to revoke one of the sessions of a user");
+
+    // check that the fields are not empty
+    if revoke_session_request.session_id.is_empty()
+        || revoke_session_request.user_id.is_empty()
+        || revoke_session_request.revoke_session_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (_, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, revoke_session_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != revoke_session_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // check that the session to revoke belongs to the user
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let user_sessions = UserAuthBmc::list_sessions(&model_maanger, user_uuid).await?;
+    if !user_sessions
+        .iter()
+        .any(|(id, _)| *id == revoke_session_request.revoke_session_id)
+    {
+        return Err(Error::EntityNotFound("session").into());
+    }
+
+    // delete session from db
+    UserAuthBmc::delete_session(&model_maanger, revoke_session_request.revoke_session_id).await?;
+
+    let res = RevokeSessionResponse { success: true };
+    Ok(Response::new(res))
+}
+
+pub async fn revoke_all_sessions(
+    revoke_all_sessions_request: RevokeAllSessionsRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<RevokeAllSessionsResponse>, Status> {
+    debug!("FN: revoke_all_sessions - Service to revoke all the sessions of a user");
+
+    // check that the fields are not empty
+    if revoke_all_sessions_request.session_id.is_empty()
+        || revoke_all_sessions_request.user_id.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // get session from db
+    let (session_id, Session { user_id, .. }) =
+        UserAuthBmc::get_session(&model_maanger, revoke_all_sessions_request.session_id).await?;
+
+    // check that the user_id matches
+    if user_id != revoke_all_sessions_request.user_id {
+        return Err(Error::UserIdMismatch.into());
+    }
+
+    // delete the sessions from db, keeping the current one if requested
+    let user_uuid = Uuid::parse_str(user_id.as_str()).map_err(|_| Error::InvalidId("user_id"))?;
+    let keep_session_id = revoke_all_sessions_request
+        .keep_current
+        .then_some(session_id);
+    let revoked =
+        UserAuthBmc::delete_all_sessions(&model_maanger, user_uuid, keep_session_id).await?;
+
+    let res = RevokeAllSessionsResponse { revoked };
+    Ok(Response::new(res))
+}
+
+pub async fn refresh_session(
+    refresh_session_request: RefreshSessionRequest,
+    model_maanger: ModelManager,
+    client_info: ClientInfo,
+) -> Result<Response<RefreshSessionResponse>, Status> {
+    debug!("FN: refresh_session - Service to swap a refresh token for new credentials");
+
+    // check that the fields are not empty
+    if refresh_session_request.refresh_token.is_empty() {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // fail before rotating a refresh token that could not be returned with its access token
+    if refresh_session_request.issue_access_token {
+        check_access_token_available()?;
+    }
+
+    // look up the family of the token without using it, the token is rotated once everything
+    // else has succeeded
+    let (family_id, family) = RefreshTokenBmc::get_family(
+        &model_maanger,
+        refresh_session_request.refresh_token.clone(),
+    )
+    .await?
+    .ok_or(Error::RefreshTokenInvalid)?;
+
+    // the refresh tokens are valid only as long as their session, revoke the family once the
+    // session is gone (expiration...)
+    let (session_id, session) =
+        match UserAuthBmc::get_session(&model_maanger, family.session_id.clone()).await {
+            Ok(res) => res,
+            Err(Error::SessionNotFound) => {
+                RefreshTokenBmc::revoke_family(&model_maanger, family_id).await?;
+                return Err(Error::SessionNotFound.into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+    // refreshing is an activity of the session, extend it
+    let (session, idle_expires_at) =
+        UserAuthBmc::touch_session(&model_maanger, session_id.clone(), session).await?;
+    let idle_expires_at = idle_expires_at.map(|e| e.timestamp()).unwrap_or_default();
+
+    let user_uuid = Uuid::parse_str(family.user_id.as_str()).map_err(Error::from)?;
+    let (access_token, access_token_expires_at) = if refresh_session_request.issue_access_token {
+        issue_access_token(&model_maanger, user_uuid, &session_id).await?
+    } else {
+        Default::default()
+    };
+
+    // replace the refresh token with a new one, nothing can fail after it
+    let outcome = RefreshTokenBmc::rotate(
+        &model_maanger,
+        refresh_session_request.refresh_token,
+        family_id,
+        family,
+    )
+    .await?;
+    let (family, refresh_token) = match outcome {
+        RefreshOutcome::Rotated { family, token, .. } => (family, token),
+        RefreshOutcome::Reused(family) => {
+            // the token has been stolen, the family is already revoked: revoke the session too
+            warn!(
+                "refresh token reused, revoking the session of user {}",
+                family.user_id
+            );
+            UserAuthBmc::delete_session(&model_maanger, session_id).await?;
+
+            let audit_log = AuditLog::new(
+                user_uuid,
+                AuditEvent::RefreshTokenReused,
+                client_info.ip,
+                client_info.user_agent,
+            );
+            AuditLogBmc::create(&model_maanger, audit_log).await?;
+
+            return Err(Error::RefreshTokenReused.into());
+        }
+        RefreshOutcome::Invalid => {
+            return Err(Error::RefreshTokenInvalid.into());
+        }
+    };
+
+    let res = RefreshSessionResponse {
+        session_id,
+        // the sessions created without a lifetime are not extended, they expire when idle
+        expires_at: session
+            .expires_at
+            .map(|e| e.timestamp())
+            .unwrap_or(idle_expires_at),
+        refresh_token,
+        refresh_token_expires_at: family.expires_at.timestamp(),
+        access_token,
+        access_token_expires_at,
+        idle_expires_at,
+    };
+    Ok(Response::new(res))
+}
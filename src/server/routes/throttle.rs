@@ -0,0 +1,147 @@
+use tonic::{Response, Status};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    mandos_auth::{ClearLoginLockoutRequest, ClearLoginLockoutResponse},
+    model::{
+        throttle::{LoginAttempt, ThrottleScope},
+        user_auth::model_controller::UserAuthBmc,
+        ModelManager,
+    },
+    server::client_info::ClientInfo,
+};
+
+/// Returns the counters a login attempt is checked against: the client ip (if known) and the
+/// account (if known, the user id or the identifier of an unknown account)
+pub fn login_throttle_keys(
+    client_info: &ClientInfo,
+    account: Option<String>,
+) -> Vec<(ThrottleScope, String)> {
+    let mut keys = Vec::new();
+    if let Some(ip) = &client_info.ip {
+        keys.push((ThrottleScope::Ip, ip.clone()));
+    }
+    if let Some(account) = account {
+        keys.push((ThrottleScope::Account, account));
+    }
+
+    keys
+}
+
+/// Returns a resource_exhausted status if the login is blocked for any of the keys, with the
+/// seconds to wait in the retry-after metadata
+/// The attempt is not counted, this only rejects the blocked clients early
+pub async fn check_login_throttle(
+    model_maanger: &ModelManager,
+    keys: &[(ThrottleScope, String)],
+) -> Result<(), Status> {
+    for (scope, id) in keys {
+        let blocked_for = UserAuthBmc::login_blocked_for(model_maanger, *scope, id.clone()).await?;
+
+        if let Some(retry_after) = blocked_for {
+            return Err(Error::LoginThrottled { retry_after }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts a login attempt for all the keys before checking the credentials, it is a failure
+/// until undone by `undo_login_attempt`
+/// Returns the number of the attempt for each key, or a resource_exhausted status (like
+/// `check_login_throttle`) if the login is blocked for any of them
+pub async fn count_login_attempt(
+    model_maanger: &ModelManager,
+    keys: &[(ThrottleScope, String)],
+) -> Result<Vec<u64>, Status> {
+    let mut attempts = Vec::new();
+    for (scope, id) in keys {
+        match UserAuthBmc::count_login_attempt(model_maanger, *scope, id.clone()).await? {
+            LoginAttempt::Counted(n) => attempts.push(n),
+            LoginAttempt::Blocked(retry_after) => {
+                // the blocked attempt does not count for the other keys either
+                undo_login_attempt(model_maanger, keys, &attempts).await?;
+                return Err(Error::LoginThrottled { retry_after }.into());
+            }
+        }
+    }
+
+    Ok(attempts)
+}
+
+/// Undoes the attempt counted by `count_login_attempt` once the credentials are right
+pub async fn undo_login_attempt(
+    model_maanger: &ModelManager,
+    keys: &[(ThrottleScope, String)],
+    attempts: &[u64],
+) -> Result<(), Status> {
+    for ((scope, id), n) in keys.iter().zip(attempts) {
+        UserAuthBmc::undo_login_attempt(model_maanger, *scope, id.clone(), *n).await?;
+    }
+
+    Ok(())
+}
+
+/// Forgets the failed login attempts of the account after a successful login
+/// The ip counter is kept, so that an attacker can't reset it with an account of its own
+pub async fn clear_account_login_failures(
+    model_maanger: &ModelManager,
+    user_id: Uuid,
+) -> Result<(), Status> {
+    UserAuthBmc::clear_login_failures(model_maanger, ThrottleScope::Account, user_id.to_string())
+        .await
+        .map_err(Status::from)
+}
+
+pub async fn clear_login_lockout(
+    clear_login_lockout_request: ClearLoginLockoutRequest,
+    model_maanger: ModelManager,
+) -> Result<Response<ClearLoginLockoutResponse>, Status> {
+    debug!("FN: clear_login_lockout - Service to clear the failed login attempts of a user or ip");
+
+    // check that the fields are not empty
+    if clear_login_lockout_request.username.is_empty()
+        && clear_login_lockout_request.email.is_empty()
+        && clear_login_lockout_request.ip.is_empty()
+    {
+        return Err(Error::FieldsEmpty.into());
+    }
+
+    // clear the failed attempts of the account
+    // if email is not empty, search by email otherwise search by username
+    if !clear_login_lockout_request.email.is_empty()
+        || !clear_login_lockout_request.username.is_empty()
+    {
+        let db_res = if !clear_login_lockout_request.email.is_empty() {
+            UserAuthBmc::get_from_email(&model_maanger, clear_login_lockout_request.email).await
+        } else {
+            UserAuthBmc::get_from_username(&model_maanger, clear_login_lockout_request.username)
+                .await
+        };
+        // only admins can call the method, so it can tell that the user does not exist
+        let db_res = match db_res {
+            Ok(user_auth) => user_auth,
+            Err(Error::Sqlx(sqlx::Error::RowNotFound)) => {
+                return Err(Error::EntityNotFound("user").into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        clear_account_login_failures(&model_maanger, db_res.id).await?;
+    }
+
+    // clear the failed attempts of the ip
+    if !clear_login_lockout_request.ip.is_empty() {
+        UserAuthBmc::clear_login_failures(
+            &model_maanger,
+            ThrottleScope::Ip,
+            clear_login_lockout_request.ip,
+        )
+        .await?;
+    }
+
+    let res = ClearLoginLockoutResponse { success: true };
+    Ok(Response::new(res))
+}
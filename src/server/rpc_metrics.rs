@@ -0,0 +1,91 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use tonic::Code;
+use tower::{Layer, Service};
+
+use crate::metrics::metrics;
+
+/// Tower layer that records the count and the latency of every RPC, by status code
+#[derive(Clone, Debug, Default)]
+pub struct RpcMetricsLayer;
+
+impl<S> Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcMetrics { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RpcMetrics<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RpcMetrics<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        // the service that is ready is the one that has to be called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        // the path of a gRPC request is /package.Service/Method
+        let (service, method) = request
+            .uri()
+            .path()
+            .trim_start_matches('/')
+            .split_once('/')
+            .map(|(s, m)| (s.to_string(), m.to_string()))
+            .unwrap_or_default();
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let res = inner.call(request).await;
+
+            if let Ok(response) = &res {
+                let code = response_code(response);
+
+                // the paths of unknown methods are chosen by the clients, don't keep them
+                let (service, method) = if code == Code::Unimplemented {
+                    ("unknown", "unknown")
+                } else {
+                    (service.as_str(), method.as_str())
+                };
+
+                metrics().observe_rpc(service, method, &format!("{code:?}"), start.elapsed());
+            }
+
+            res
+        })
+    }
+}
+
+/// Returns the status code of a gRPC response
+/// The errors are sent in the headers (trailers-only responses), the successful responses send
+/// their status in the trailers of the body
+pub(crate) fn response_code<B>(response: &http::Response<B>) -> Code {
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|s| s.to_str().ok())
+        .and_then(|s| s.parse::<i32>().ok())
+        .map(Code::from_i32)
+        .unwrap_or(Code::Ok)
+}
@@ -0,0 +1,54 @@
+use std::fs;
+
+use tonic::{
+    transport::{Certificate, Identity, ServerTlsConfig},
+    Request,
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::error::{Error, Result};
+
+/// Loads the TLS config of the server from PEM files
+/// With a client CA bundle the clients must present a certificate signed by one of its CAs
+/// # Arguments
+/// * `cert_path` - The certificate chain of the server
+/// * `key_path` - The private key of the server
+/// * `client_ca_path` - The CA bundle that verifies the client certificates (mTLS)
+pub fn load_server_tls_config(
+    cert_path: &str,
+    key_path: &str,
+    client_ca_path: Option<&str>,
+) -> Result<ServerTlsConfig> {
+    let read = |path: &str| {
+        fs::read_to_string(path).map_err(|e| Error::ConfigInvalidTls(format!("{path}: {e}")))
+    };
+
+    let tls_config =
+        ServerTlsConfig::new().identity(Identity::from_pem(read(cert_path)?, read(key_path)?));
+
+    match client_ca_path {
+        Some(client_ca_path) => {
+            Ok(tls_config.client_ca_root(Certificate::from_pem(read(client_ca_path)?)))
+        }
+        None => Ok(tls_config),
+    }
+}
+
+/// Returns the common name of the subject of the client certificate
+/// The certificate is available only with mTLS on, once it has been verified against the client
+/// CA bundle
+pub fn peer_common_name<T>(request: &Request<T>) -> Option<String> {
+    let peer_certs = request.peer_certs()?;
+    // the first certificate of the chain is the one of the client
+    let (_, cert) = X509Certificate::from_der(peer_certs.first()?.get_ref()).ok()?;
+
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()?
+        .as_str()
+        .ok()?
+        .to_string();
+
+    Some(common_name)
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, Tracer},
+    Resource,
+};
+use tracing::{info, Subscriber};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+use self::redact::{JsonFormat, RedactedFields, RedactedJsonFields};
+
+pub mod redact;
+
+// region: LogFormat
+
+#[derive(Debug, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(Error::ConfigInvalidLogFormat(s.to_string())),
+        }
+    }
+}
+
+// endregion: LogFormat
+
+/// Sets up the logs and, when an OTLP endpoint is configured, the export of the spans
+/// Has to be called from a tokio runtime, the spans are exported in the background
+pub fn initialize() -> Result<()> {
+    let env_filter = EnvFilter::try_new(&config().TRACING_FILTER)
+        .map_err(|e| Error::ConfigInvalidTracingFilter(e.to_string()))?;
+
+    // the trace context of the callers is read from the W3C traceparent metadata
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let otel_layer = match &config().OTLP_ENDPOINT {
+        Some(endpoint) => Some(tracing_opentelemetry::layer().with_tracer(otlp_tracer(endpoint)?)),
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::from_level(config().TRACING_MAX_LEVEL))
+        .with(env_filter)
+        .with(log_layer(&config().LOG_FORMAT, std::io::stdout))
+        .with(otel_layer)
+        .try_init()
+        .map_err(|e| Error::Tracing(e.to_string()))?;
+
+    match &config().OTLP_ENDPOINT {
+        Some(endpoint) => info!("Tracing initialized, exporting spans to {endpoint}"),
+        None => info!("Tracing initialized"),
+    }
+
+    Ok(())
+}
+
+/// Returns the layer that writes the logs in the given format, with the sensitive fields redacted
+/// # Arguments
+/// * `format` - The format of the log lines
+/// * `make_writer` - Where the log lines are written (e.g. std::io::stdout)
+pub fn log_layer<S, W>(format: &LogFormat, make_writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => fmt::layer()
+            .fmt_fields(RedactedFields)
+            .with_writer(make_writer)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            // the json format is implemented for the redacted json fields only, they have to be
+            // set first
+            .fmt_fields(RedactedJsonFields)
+            .event_format(JsonFormat)
+            .with_writer(make_writer)
+            .boxed(),
+    }
+}
+
+/// Exports the spans still buffered and stops the export, to be called before the process exits
+/// It blocks until the export is done, so it has to run outside of the tokio runtime threads
+/// (e.g. with spawn_blocking)
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Returns a tracer that exports the spans in batches to the OTLP gRPC endpoint
+fn otlp_tracer(endpoint: &str) -> Result<Tracer> {
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                config().OTLP_SERVICE_NAME.clone(),
+            )])),
+        )
+        .install_batch(runtime::Tokio)?;
+
+    Ok(tracer)
+}
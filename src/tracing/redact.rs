@@ -0,0 +1,272 @@
+use std::{fmt, sync::OnceLock};
+
+use chrono::{SecondsFormat, Utc};
+use regex::Regex;
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{
+    field::RecordFields,
+    fmt::{format::Writer, FmtContext, FormatEvent, FormatFields, FormattedFields},
+    registry::LookupSpan,
+};
+
+/// Replaces the values that are never logged
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Parts of the field names (split on `.` and `_`) whose values are never logged
+const SENSITIVE_FIELD_PARTS: [&str; 9] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "session",
+    "authorization",
+    "cookie",
+    "totp",
+    "recovery",
+];
+
+/// Returns whether the value of a field must not be logged
+/// e.g. password, new_password, session_id, refresh_token, http.authorization
+pub fn is_sensitive_field(name: &str) -> bool {
+    name.to_ascii_lowercase()
+        .split(['.', '_'])
+        .any(|part| SENSITIVE_FIELD_PARTS.contains(&part))
+}
+
+/// Returns whether a field holds the id of a record (e.g. user_id, request_id), logged as it is
+/// to correlate the logs, the ids of the sessions are sensitive fields
+fn is_id_field(name: &str) -> bool {
+    name.rsplit(['.', '_'])
+        .next()
+        .is_some_and(|part| part.eq_ignore_ascii_case("id"))
+}
+
+/// Returns the value of a field as it can be logged
+/// The values of the sensitive fields are replaced, the emails and tokens inside the other values
+/// are masked (the uuids too, except in the id fields)
+pub fn redact_field(name: &str, value: &str) -> String {
+    if is_sensitive_field(name) {
+        REDACTED.to_string()
+    } else if is_id_field(name) {
+        redact_tokens(value)
+    } else {
+        redact_text(value)
+    }
+}
+
+/// Masks the emails, the tokens and the uuids found in a text
+/// * emails keep their domain only: `***@example.com`
+/// * JWTs, bearer credentials, long hex strings (the tokens of the service) and long base32
+///   strings (the TOTP secrets) are replaced
+/// * uuids are replaced, since the session ids are uuids
+pub fn redact_text(text: &str) -> String {
+    static UUID: OnceLock<Regex> = OnceLock::new();
+
+    let uuid = UUID.get_or_init(|| {
+        Regex::new(
+            r"\b[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}\b",
+        )
+        .unwrap()
+    });
+
+    uuid.replace_all(&redact_tokens(text), REDACTED)
+        .into_owned()
+}
+
+/// Masks the emails and the tokens found in a text, keeping the uuids
+fn redact_tokens(text: &str) -> String {
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+
+    let email = EMAIL.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+\-]+@([A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,})")
+            .unwrap()
+    });
+    let token = TOKEN.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?i:bearer\s+\S+)",
+            r"|\beyJ[A-Za-z0-9_\-]*\.[A-Za-z0-9_\-]*\.[A-Za-z0-9_\-]*",
+            r"|\b[0-9A-Fa-f]{32,}\b",
+            r"|\b[A-Z2-7]{32,}\b",
+        ))
+        .unwrap()
+    });
+
+    let text = email.replace_all(text, "***@$1");
+    token.replace_all(&text, REDACTED).into_owned()
+}
+
+/// Collects the redacted fields of an event or a span
+#[derive(Default)]
+struct RedactVisitor {
+    message: Option<String>,
+    fields: Vec<(&'static str, Value)>,
+}
+
+impl RedactVisitor {
+    fn record_value(&mut self, field: &Field, value: Value) {
+        if is_sensitive_field(field.name()) {
+            self.fields.push((field.name(), Value::from(REDACTED)));
+        } else {
+            self.fields.push((field.name(), value));
+        }
+    }
+
+    /// Returns the fields as a json object, the message under the `message` key
+    fn into_json(self) -> Map<String, Value> {
+        let mut object = Map::new();
+        if let Some(message) = self.message {
+            object.insert("message".to_string(), Value::from(message));
+        }
+        for (name, value) in self.fields {
+            object.insert(name.to_string(), value);
+        }
+
+        object
+    }
+}
+
+impl Visit for RedactVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_value(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_value(field, Value::from(redact_field(field.name(), value)));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = redact_field(field.name(), &format!("{value:?}"));
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.record_value(field, Value::from(value));
+        }
+    }
+}
+
+/// Formats the fields of the text logs as `message key=value`, redacted
+#[derive(Debug, Default)]
+pub struct RedactedFields;
+
+impl<'writer> FormatFields<'writer> for RedactedFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = RedactVisitor::default();
+        fields.record(&mut visitor);
+
+        let mut separator = "";
+        if let Some(message) = visitor.message {
+            write!(writer, "{message}")?;
+            separator = " ";
+        }
+        for (name, value) in visitor.fields {
+            match value {
+                Value::String(s) => write!(writer, "{separator}{name}={s}")?,
+                v => write!(writer, "{separator}{name}={v}")?,
+            }
+            separator = " ";
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats the fields of the spans of the json logs as a json object, redacted
+#[derive(Debug, Default)]
+pub struct RedactedJsonFields;
+
+impl<'writer> FormatFields<'writer> for RedactedJsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = RedactVisitor::default();
+        fields.record(&mut visitor);
+
+        write!(writer, "{}", Value::Object(visitor.into_json()))
+    }
+
+    // the fields recorded later are merged in the json object of the span
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = RedactVisitor::default();
+        fields.record(&mut visitor);
+
+        let mut object =
+            serde_json::from_str::<Map<String, Value>>(&current.fields).unwrap_or_default();
+        object.extend(visitor.into_json());
+        current.fields = Value::Object(object).to_string();
+
+        Ok(())
+    }
+}
+
+/// Formats every event as a line of json, with the redacted fields of the event and of its spans
+/// e.g. `{"fields":{"message":"..."},"level":"INFO","spans":[{"name":"grpc_request",...}],"target":"mandos::server","timestamp":"..."}`
+#[derive(Debug, Default)]
+pub struct JsonFormat;
+
+impl<S> FormatEvent<S, RedactedJsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, RedactedJsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = RedactVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Value::from(Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)),
+        );
+        line.insert("level".to_string(), Value::from(metadata.level().as_str()));
+        line.insert("target".to_string(), Value::from(metadata.target()));
+        line.insert("fields".to_string(), Value::Object(visitor.into_json()));
+
+        // the spans the event happened in, from the outermost
+        if let Some(scope) = ctx.event_scope() {
+            let spans = scope
+                .from_root()
+                .map(|span| {
+                    let mut fields = span
+                        .extensions()
+                        .get::<FormattedFields<RedactedJsonFields>>()
+                        .and_then(|f| serde_json::from_str::<Map<String, Value>>(&f.fields).ok())
+                        .unwrap_or_default();
+                    fields.insert("name".to_string(), Value::from(span.name()));
+
+                    Value::Object(fields)
+                })
+                .collect::<Vec<_>>();
+            line.insert("spans".to_string(), Value::from(spans));
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
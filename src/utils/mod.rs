@@ -1,36 +1,205 @@
+use std::sync::OnceLock;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, ParamsBuilder, Version,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::config,
+    error::Error,
+    metrics::{metrics, PasswordHashOperation},
 };
 
-use crate::error::Error;
+pub mod pepper;
+
+/// Returns the Argon2 hasher with the algorithm and the params of the config
+fn argon2(params: Params) -> Argon2<'static> {
+    Argon2::new(config().PASSWORD_HASH_ALGORITHM, Version::V0x13, params)
+}
 
 pub fn hash_password(password: String) -> Result<String, Error> {
-    // Argon2 with default params (Argon2id v19)
-    let argon2 = Argon2::default();
+    // Argon2 with the params of the config, the hashes are tagged with the version of the pepper
+    // in their keyid
+    let current = &config().PASSWORD_HASH_PARAMS;
+    let (argon2, password) = match pepper::current() {
+        Some(pepper) => {
+            let params = ParamsBuilder::new()
+                .m_cost(current.m_cost())
+                .t_cost(current.t_cost())
+                .p_cost(current.p_cost())
+                .keyid(pepper.keyid()?)
+                .build()?;
+            (argon2(params), pepper.apply(password.as_bytes()))
+        }
+        None => (argon2(current.clone()), password.into_bytes()),
+    };
 
     // Generate salt
     let salt = SaltString::generate(&mut OsRng);
 
     // Hash password to PHC string ($argon2id$v=19$...) and return it
-    let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
+    let password_hash = metrics().time_password_hash(PasswordHashOperation::Hash, || {
+        argon2.hash_password(&password, &salt)
+    })?;
 
     Ok(password_hash.to_string())
 }
 
 pub fn verify_password(password: String, password_hash: String) -> Result<(), Error> {
-    // Argon2 with default params (Argon2id v19)
-    let argon2 = Argon2::default();
-
     // Parse PHC string to PasswordHash struct
     let parsed_hash = PasswordHash::new(&password_hash)?;
 
-    // Verify password against hash
-    argon2.verify_password(password.as_bytes(), &parsed_hash)?;
+    // the password is peppered with the version the hash has been computed with, so the hashes
+    // computed before a rotation of the pepper can still be verified
+    let password = match pepper::hash_version(&parsed_hash)? {
+        Some(version) => pepper::get(version)?.apply(password.as_bytes()),
+        None => password.into_bytes(),
+    };
+
+    // Verify password against hash, with the algorithm and the params of its PHC string
+    let argon2 = argon2(config().PASSWORD_HASH_PARAMS.clone());
+    metrics().time_password_hash(PasswordHashOperation::Verify, || {
+        argon2.verify_password(&password, &parsed_hash)
+    })?;
 
     Ok(())
 }
 
+/// Returns true if the hash has been computed with another algorithm, with weaker params than the
+/// ones of the config or with another pepper than the current one, so the password should be
+/// hashed again
+pub fn password_needs_rehash(password_hash: &str) -> Result<bool, Error> {
+    let parsed_hash = PasswordHash::new(password_hash)?;
+    let algorithm = Algorithm::try_from(parsed_hash.algorithm)?;
+    let params = Params::try_from(&parsed_hash)?;
+    let pepper_version = pepper::hash_version(&parsed_hash)?;
+
+    let current = &config().PASSWORD_HASH_PARAMS;
+    Ok(algorithm != config().PASSWORD_HASH_ALGORITHM
+        || params.m_cost() < current.m_cost()
+        || params.t_cost() < current.t_cost()
+        || params.p_cost() < current.p_cost()
+        || pepper_version != pepper::current().map(|p| p.version))
+}
+
+/// Runs a blocking function (e.g. hashing a password) on the blocking threads of tokio, so that
+/// it does not stall the runtime
+pub async fn spawn_blocking<T, F>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Service(e.to_string()))?
+}
+
+/// Returns the hash verified when the user of a login does not exist, so that the login takes as
+/// long as with a wrong password (the hash of a random password, computed on first use)
+pub fn dummy_password_hash() -> &'static str {
+    static INSTANCE: OnceLock<String> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        hash_password(generate_token()).expect("Failed to compute the dummy password hash")
+    })
+}
+
+/// Generates a random token (32 bytes from the OS rng, hex encoded)
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    to_hex(&bytes)
+}
+
+/// Hashes a token with SHA-256 (hex encoded), used to store tokens at rest
+/// Tokens are random and long enough that a slow hash like argon2 is not needed
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Returns the MFA key of the config
+/// Returns MfaNotConfigured if it is not set, MFA is then disabled
+fn mfa_encryption_key() -> Result<&'static [u8; 32], Error> {
+    config()
+        .MFA_ENCRYPTION_KEY
+        .as_ref()
+        .ok_or(Error::MfaNotConfigured)
+}
+
+/// Returns the key of the keyed hashes, derived from the MFA key with HKDF so that the key
+/// that encrypts the secrets is not used as HMAC key too
+/// The secrets are still encrypted with the MFA key itself, the ones stored can be decrypted
+fn keyed_hash_key() -> Result<[u8; 32], Error> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, mfa_encryption_key()?)
+        .expand(b"mandos keyed hash", &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    Ok(key)
+}
+
+/// Returns the HMAC-SHA256 of a value (hex encoded) with a key derived from the MFA key,
+/// separated by context, used to look up the secrets stored with a slow hash without verifying
+/// all of them
+pub fn keyed_hash(context: &str, value: &str) -> Result<String, Error> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&keyed_hash_key()?)
+        .expect("HMAC takes keys of any size");
+    mac.update(context.as_bytes());
+    mac.update(&[0]);
+    mac.update(value.as_bytes());
+
+    Ok(to_hex(&mac.finalize().into_bytes()))
+}
+
+/// Encrypts a secret with AES-256-GCM and the key from the config
+/// Returns the base64 encoded nonce followed by the ciphertext
+pub fn encrypt_secret(secret: &[u8]) -> Result<String, Error> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(mfa_encryption_key()?));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, secret)
+        .map_err(|e| Error::Encryption(e.to_string()))?;
+
+    Ok(BASE64.encode([nonce.as_slice(), &ciphertext].concat()))
+}
+
+/// Decrypts a secret encrypted with `encrypt_secret`
+pub fn decrypt_secret(encrypted_secret: &str) -> Result<Vec<u8>, Error> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(mfa_encryption_key()?));
+
+    let bytes = BASE64
+        .decode(encrypted_secret)
+        .map_err(|e| Error::Encryption(e.to_string()))?;
+    if bytes.len() < 12 {
+        return Err(Error::Encryption(
+            "encrypted secret is too short".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| Error::Encryption(e.to_string()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 pub fn print_app_name(app_name: &str, mut len: usize, border: usize) {
     let mut num_spaces = (len - (border * 2) - app_name.len()) / 2;
     if num_spaces % 2 != 0 {
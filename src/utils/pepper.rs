@@ -0,0 +1,91 @@
+use argon2::{password_hash::PasswordHash, KeyId, Params};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+// the version is written in decimal in the keyid of the PHC strings, that holds up to 8 bytes
+const MAX_VERSION: u32 = 99_999_999;
+const MIN_KEY_LEN: usize = 16;
+
+/// Secret mixed into the passwords before they are hashed, kept out of the database
+pub struct PasswordPepper {
+    pub version: u32,
+    pub key: Vec<u8>,
+}
+
+impl PasswordPepper {
+    /// Parses a version=key entry, the key is base64 encoded
+    pub fn parse(entry: &str) -> Result<PasswordPepper> {
+        let invalid = |reason: &str| {
+            // the entry contains the key, only its version can be shown
+            let version = entry.split('=').next().unwrap_or_default();
+            Error::ConfigInvalidPasswordPepper(format!("{version}: {reason}"))
+        };
+
+        let (version, key) = entry
+            .split_once('=')
+            .ok_or_else(|| invalid("expected version=key"))?;
+        let version = version
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|v| *v <= MAX_VERSION)
+            .ok_or_else(|| invalid("the version is not a number up to 99999999"))?;
+        let key = BASE64
+            .decode(key.trim())
+            .map_err(|_| invalid("the key is not base64 encoded"))?;
+        if key.len() < MIN_KEY_LEN {
+            return Err(invalid("the key is shorter than 16 bytes"));
+        }
+
+        Ok(PasswordPepper { version, key })
+    }
+
+    /// Returns the keyid that tags the hashes computed with the pepper
+    pub fn keyid(&self) -> Result<KeyId> {
+        Ok(KeyId::new(self.version.to_string().as_bytes())?)
+    }
+
+    /// Returns the HMAC-SHA256 of the password, that is hashed instead of the password
+    pub fn apply(&self, password: &[u8]) -> Vec<u8> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes keys of any size");
+        mac.update(password);
+
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Returns the pepper of the new hashes (the first one of the config), None without peppers
+pub fn current() -> Option<&'static PasswordPepper> {
+    config().PASSWORD_PEPPERS.first()
+}
+
+/// Returns the pepper of the config with the version
+pub fn get(version: u32) -> Result<&'static PasswordPepper> {
+    config()
+        .PASSWORD_PEPPERS
+        .iter()
+        .find(|p| p.version == version)
+        .ok_or_else(|| Error::PasswordPepperNotFound(version.to_string()))
+}
+
+/// Returns the version of the pepper the hash has been computed with (from its keyid), None if
+/// the password has not been peppered
+pub fn hash_version(password_hash: &PasswordHash) -> Result<Option<u32>> {
+    let params = Params::try_from(password_hash)?;
+    if params.keyid().is_empty() {
+        return Ok(None);
+    }
+
+    let keyid = String::from_utf8_lossy(params.keyid());
+    keyid
+        .parse::<u32>()
+        .map(Some)
+        .map_err(|_| Error::PasswordPepperNotFound(keyid.to_string()))
+}
@@ -1,24 +1,75 @@
-use std::time::Duration;
+use std::sync::{Arc, OnceLock};
 
 use crate::{
-    config::config,
-    error::Result,
-    mandos_auth::{mandos_auth_client::MandosAuthClient, mandos_auth_server::MandosAuthServer},
+    error::{Error, Result},
+    mailer::{Mailer, MemoryMailer},
+    mandos_auth::mandos_auth_client::MandosAuthClient,
     model::{session, ModelManager},
-    server::{middleware::check_auth, ServiceMandosAuth},
+    server::{
+        self,
+        api_client::{CLIENT_ID_METADATA_KEY, CLIENT_SECRET_METADATA_KEY},
+        ServerHandle,
+    },
 };
 use tonic::{
     metadata::MetadataValue,
     service::interceptor::InterceptedService,
-    transport::{Channel, Server},
+    transport::{Channel, ServerTlsConfig},
     Request, Status,
 };
 
+/// The API client the test client authenticates as, listed in tests/api_clients.json
+pub const TEST_API_CLIENT: &str = "test";
+pub const TEST_API_CLIENT_SECRET: &str = "test-secret";
+
+const TEST_ADDR: &str = "0.0.0.0:50051";
+const TEST_CLIENT_ADDR: &str = "http://0.0.0.0:50051";
+
+/// The mailer used by the test server, read it to get the tokens sent by email
+pub fn test_mailer() -> &'static MemoryMailer {
+    static INSTANCE: OnceLock<MemoryMailer> = OnceLock::new();
+
+    INSTANCE.get_or_init(MemoryMailer::new)
+}
+
+/// Returns the token on the last line of the last mail sent to the given address
+pub fn last_mail_token(to: &str) -> Option<String> {
+    let mail = test_mailer().mails_to(to).pop()?;
+
+    mail.body.lines().last().map(|l| l.to_string())
+}
+
+/// Waits until the given number of mails has been sent to the address (the mails sent in the
+/// background) and returns the token of the last one
+pub async fn wait_mail_token(to: &str, count: usize) -> Option<String> {
+    for _ in 0..200 {
+        if test_mailer().mails_to(to).len() >= count {
+            return last_mail_token(to);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    None
+}
+
 pub async fn clean_all_dbs(model_manager: ModelManager) -> Result<()> {
     sqlx::query("delete from users_auth")
         .execute(model_manager.db())
         .await?;
+    sqlx::query("delete from audit_log")
+        .execute(model_manager.db())
+        .await?;
+    sqlx::query("delete from organizations")
+        .execute(model_manager.db())
+        .await?;
+    sqlx::query("delete from roles")
+        .execute(model_manager.db())
+        .await?;
+    sqlx::query("delete from permissions")
+        .execute(model_manager.db())
+        .await?;
     session::crud::flush_db(model_manager.session_db().clone()).await?;
+    test_mailer().clear();
 
     Ok(())
 }
@@ -35,43 +86,75 @@ pub async fn setup_test_environment() -> Result<(
     // Initialize env variables
     dotenvy::from_filename_override(".env.test").expect("Failed to load .env.test file");
 
-    let addr = "0.0.0.0:50051".to_string();
-    let client_addr = "http://0.0.0.0:50051";
-
-    // Run the server in the background
-    let model_manager = start_background_grpc_server(addr).await?;
+    // Run the server in the background, it keeps running once the handle is dropped
+    let (model_manager, _) = start_test_server(TEST_ADDR, None).await?;
 
     // get the grpc client
-    let client = get_grpc_client(client_addr).await?;
+    let client = get_grpc_client(TEST_API_CLIENT, TEST_API_CLIENT_SECRET).await?;
 
     Ok((model_manager, client))
 }
 
-async fn start_background_grpc_server(addr: String) -> Result<ModelManager> {
+/// Runs a test server with the given TLS config in the background
+/// Returns the model manager, the clients have to be created with the TLS config of the test
+pub async fn setup_tls_test_environment(
+    addr: &str,
+    tls_config: ServerTlsConfig,
+) -> Result<ModelManager> {
+    let (model_manager, _) = start_test_server(addr, Some(tls_config)).await?;
+
+    Ok(model_manager)
+}
+
+/// Runs a test server in the background with the test mailer
+/// Returns the model manager and the handle to get the bound address and stop the server
+pub async fn start_test_server(
+    addr: &str,
+    tls_config: Option<ServerTlsConfig>,
+) -> Result<(ModelManager, ServerHandle)> {
+    start_test_server_with_mailer(addr, tls_config, Arc::new(test_mailer().clone())).await
+}
+
+/// Runs a test server in the background with the given mailer
+/// Returns the model manager and the handle to get the bound address and stop the server
+pub async fn start_test_server_with_mailer(
+    addr: &str,
+    tls_config: Option<ServerTlsConfig>,
+    mailer: Arc<dyn Mailer>,
+) -> Result<(ModelManager, ServerHandle)> {
+    // Initialize env variables
+    dotenvy::from_filename_override(".env.test").expect("Failed to load .env.test file");
+
     // Initialize ModelManager
     let model_manager = ModelManager::new().await?;
 
-    let addr = addr.parse()?;
-    let mandos_auth = ServiceMandosAuth::new(model_manager.clone());
+    let server_handle =
+        server::start_background(model_manager.clone(), mailer, addr.parse()?, tls_config).await?;
 
-    tokio::spawn(async move {
-        let server = Server::builder()
-            .add_service(MandosAuthServer::with_interceptor(mandos_auth, check_auth))
-            .serve(addr)
-            .await;
-        if let Err(e) = server {
-            e.to_string();
-        }
-    });
-
-    // Wait for the server to be ready (optional)
-    tokio::time::sleep(Duration::from_secs(2)).await;
+    Ok((model_manager, server_handle))
+}
 
-    Ok(model_manager)
+/// Returns a client of the test server that authenticates with the given API client credentials
+pub async fn get_grpc_client(
+    client_id: &'static str,
+    client_secret: &'static str,
+) -> Result<
+    MandosAuthClient<
+        InterceptedService<
+            tonic::transport::Channel,
+            impl Fn(Request<()>) -> core::result::Result<Request<()>, Status>,
+        >,
+    >,
+> {
+    get_grpc_client_at(TEST_CLIENT_ADDR.to_string(), client_id, client_secret).await
 }
 
-async fn get_grpc_client(
-    client_addr: &'static str,
+/// Returns a client of the server at the given url (e.g. http://127.0.0.1:50051) that
+/// authenticates with the given API client credentials
+pub async fn get_grpc_client_at(
+    client_addr: String,
+    client_id: &'static str,
+    client_secret: &'static str,
 ) -> Result<
     MandosAuthClient<
         InterceptedService<
@@ -81,14 +164,19 @@ async fn get_grpc_client(
     >,
 > {
     // connect to the server and run the test
-    let channel = Channel::from_static(client_addr).connect().await?;
+    let channel = Channel::from_shared(client_addr)
+        .map_err(|e| Error::Test(e.to_string()))?
+        .connect()
+        .await?;
 
-    let grpc_auth_key = config().GRPC_AUTH_KEY.as_str();
-    let grpc_auth_value: MetadataValue<_> = config().GRPC_AUTH_VALUE.as_str().parse().unwrap();
+    let client_id: MetadataValue<_> = client_id.parse().unwrap();
+    let client_secret: MetadataValue<_> = client_secret.parse().unwrap();
 
     let client = MandosAuthClient::with_interceptor(channel, move |mut req: Request<()>| {
         req.metadata_mut()
-            .insert(grpc_auth_key, grpc_auth_value.clone());
+            .insert(CLIENT_ID_METADATA_KEY, client_id.clone());
+        req.metadata_mut()
+            .insert(CLIENT_SECRET_METADATA_KEY, client_secret.clone());
         Ok(req)
     });
 
@@ -0,0 +1,130 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::AcceptInvitationRequest,
+    model::{
+        db,
+        organization::{
+            model_controller::OrganizationBmc, Organization, OrganizationInvitation,
+            OrganizationRole,
+        },
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the accept_invitation grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create an owner, an invited user and another user in the database
+/// 4. Create an organization and an invitation for the invited user
+/// 5. Call the accept_invitation grpc method as the other user
+/// 6. Call the accept_invitation grpc method as the invited user
+/// 7. Check that the other user has been rejected
+/// 8. Check that the invited user is a member with the invited role
+/// 9. Check that the invitation can't be used again
+/// 10. Clean all databases
+#[tokio::test]
+async fn accept_invitation_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database with a session
+    let mut users = Vec::new();
+    for username in ["owner", "invited", "other"] {
+        let user_auth_for_create = UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: "correct-horse-battery".to_string(),
+        };
+        let user_auth = UserAuth::new(user_auth_for_create)?;
+        let res =
+            db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+        let user_auth_db = UserAuth::from_row(&res)?;
+
+        let session_id = session::crud::create(
+            model_manager.session_db().clone(),
+            Session::new(user_auth_db.id.to_string()),
+            60,
+        )
+        .await?;
+
+        users.push((user_auth_db, session_id));
+    }
+    let (owner, _) = &users[0];
+    let (invited, invited_session_id) = &users[1];
+    let (other, other_session_id) = &users[2];
+
+    // create the organization and the invitation
+    let organization_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Acme".to_string())?,
+        owner.id,
+    )
+    .await?;
+    let token = OrganizationBmc::create_invitation(
+        &model_manager,
+        OrganizationInvitation {
+            organization_id,
+            email: invited.email_normalized.clone(),
+            role: OrganizationRole::Admin,
+        },
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(AcceptInvitationRequest {
+        session_id: other_session_id.clone(),
+        user_id: other.id.to_string(),
+        token: token.clone(),
+    });
+
+    let other_res = client.accept_invitation(request).await;
+
+    let request = tonic::Request::new(AcceptInvitationRequest {
+        session_id: invited_session_id.clone(),
+        user_id: invited.id.to_string(),
+        token: token.clone(),
+    });
+
+    let accept_res = client
+        .accept_invitation(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    let member = OrganizationBmc::get_member(&model_manager, organization_id, invited.id).await?;
+    let other_member =
+        OrganizationBmc::get_member(&model_manager, organization_id, other.id).await?;
+
+    // region: tests
+
+    let status = other_res
+        .err()
+        .ok_or_else(|| Error::Test("invitation accepted by another user".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert!(other_member.is_none());
+
+    assert_eq!(accept_res.organization_id, organization_id.to_string());
+    let member = member.ok_or_else(|| Error::Test("member not added".to_string()))?;
+    assert_eq!(member.role, "admin");
+
+    assert!(OrganizationBmc::get_invitation(&model_manager, token)
+        .await?
+        .is_none());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
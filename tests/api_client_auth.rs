@@ -0,0 +1,91 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{ClearLoginLockoutRequest, HealthCheckRequest, LoginRequest},
+    utils_tests,
+};
+
+/// Test that the calls are authenticated with the credentials of the API clients
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Call the health_check grpc method with the credentials of a client limited to it
+/// 3. Check that both the active secrets of the client are accepted
+/// 4. Check that the client can't call the RPCs outside of its scopes
+/// 5. Check that the admin RPCs need the admin scope, "*" does not include them
+/// 6. Check that wrong secrets and unknown clients are rejected
+#[tokio::test]
+async fn api_client_auth_works() -> Result<()> {
+    // setup test environment
+    utils_tests::setup_test_environment().await?;
+
+    // clients registered in tests/api_clients.json
+    let mut old_secret_client =
+        utils_tests::get_grpc_client("health-checker", "health-secret-old").await?;
+    let mut new_secret_client =
+        utils_tests::get_grpc_client("health-checker", "health-secret-new").await?;
+
+    // region: call grpc method
+
+    let health_check_res = old_secret_client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that both the secrets of the client are active
+    assert!(health_check_res.success);
+    let health_check_res = new_secret_client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(health_check_res.success);
+
+    // check that the client can't call the rpcs outside of its scopes
+    let request = tonic::Request::new(LoginRequest {
+        username: "username".to_string(),
+        email: "".to_string(),
+        password: "correct-horse-battery".to_string(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let status = new_secret_client.login(request).await.unwrap_err();
+    assert!(status.code() == tonic::Code::PermissionDenied);
+
+    // check that the admin rpcs need the admin scope
+    let mut all_scopes_client = utils_tests::get_grpc_client("web", "web-secret").await?;
+    let request = tonic::Request::new(ClearLoginLockoutRequest {
+        username: "username".to_string(),
+        email: "".to_string(),
+        ip: "".to_string(),
+    });
+    let status = all_scopes_client
+        .clear_login_lockout(request)
+        .await
+        .unwrap_err();
+    assert!(status.code() == tonic::Code::PermissionDenied);
+
+    // check that a wrong secret is rejected
+    let mut wrong_secret_client =
+        utils_tests::get_grpc_client("health-checker", "test-secret").await?;
+    let status = wrong_secret_client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // check that an unknown client is rejected
+    let mut unknown_client = utils_tests::get_grpc_client("unknown", "test-secret").await?;
+    let status = unknown_client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // endregion: tests
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use mandos::{
+    config,
+    error::{Error, Result},
+    mandos_auth::{mandos_auth_client::MandosAuthClient, HealthCheckRequest},
+    utils_tests,
+};
+use tonic::{metadata::MetadataValue, transport::Channel, Request};
+
+/// Test that the legacy credentials are accepted and that the API clients are reloaded without a
+/// restart
+/// Steps:
+/// 1. Set the legacy GRPC_AUTH_KEY and GRPC_AUTH_VALUE
+/// 2. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 3. Check that the legacy secret authenticates the caller, and that a wrong one is rejected
+/// 4. Point API_CLIENTS_FILE to a registry where the secret of a client has been rotated and
+///    reload the config
+/// 5. Check that only the new secret of the client is accepted
+/// 6. Check that a failed reload keeps the current clients
+#[tokio::test]
+async fn api_clients_reload_works() -> Result<()> {
+    // the config is loaded by the test server, after the legacy credentials have been set
+    std::env::set_var("GRPC_AUTH_KEY", "x-auth-key");
+    std::env::set_var("GRPC_AUTH_VALUE", "legacy-secret");
+
+    // setup test environment
+    utils_tests::setup_test_environment().await?;
+
+    // region: legacy credentials
+
+    for (secret, accepted) in [("legacy-secret", true), ("wrong-secret", false)] {
+        let channel = Channel::from_static("http://0.0.0.0:50051")
+            .connect()
+            .await?;
+        let secret: MetadataValue<_> = secret.parse().unwrap();
+        let mut legacy_client =
+            MandosAuthClient::with_interceptor(channel, move |mut req: Request<()>| {
+                req.metadata_mut().insert("x-auth-key", secret.clone());
+                Ok(req)
+            });
+        let res = legacy_client
+            .health_check(Request::new(HealthCheckRequest {}))
+            .await;
+        assert_eq!(res.is_ok(), accepted);
+    }
+
+    // endregion: legacy credentials
+
+    // region: reload the clients
+
+    let mut old_secret_client = utils_tests::get_grpc_client("web", "web-secret").await?;
+    let mut new_secret_client = utils_tests::get_grpc_client("web", "web-secret-new").await?;
+    assert!(old_secret_client
+        .health_check(Request::new(HealthCheckRequest {}))
+        .await
+        .is_ok());
+
+    std::env::set_var("API_CLIENTS_FILE", "./tests/api_clients_rotated.json");
+    config::reload()?;
+
+    // endregion: reload the clients
+
+    // region: tests
+
+    // check that only the new secret is accepted
+    let status = old_secret_client
+        .health_check(Request::new(HealthCheckRequest {}))
+        .await
+        .unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+    let health_check_res = new_secret_client
+        .health_check(Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(health_check_res.success);
+
+    // check that the clients are kept when the registry can't be read
+    std::env::set_var("API_CLIENTS_FILE", "./tests/missing.json");
+    assert!(config::reload().is_err());
+    assert!(new_secret_client
+        .health_check(Request::new(HealthCheckRequest {}))
+        .await
+        .is_ok());
+
+    // endregion: tests
+
+    Ok(())
+}
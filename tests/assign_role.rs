@@ -0,0 +1,74 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::AssignRoleRequest,
+    model::{
+        db,
+        rbac::{
+            model_controller::{RoleBmc, UserRoleBmc},
+            Role,
+        },
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the assign_role grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user and a role in the database
+/// 4. Call the assign_role grpc method
+/// 5. Check that the user has the role
+/// 6. Clean all databases
+#[tokio::test]
+async fn assign_role_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the role in the database
+    let role = "editor".to_string();
+    RoleBmc::create(&model_manager, Role::new(role.clone(), "".to_string())?).await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(AssignRoleRequest {
+        user_id: user_auth_db.id.to_string(),
+        role: role.clone(),
+    });
+
+    client
+        .assign_role(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    let roles = UserRoleBmc::get_roles(&model_manager, user_auth_db.id).await?;
+
+    // region: tests
+
+    assert_eq!(roles.len(), 1);
+    assert_eq!(roles[0].name, role);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::CheckPermissionRequest,
+    model::{
+        db,
+        rbac::{
+            model_controller::{PermissionBmc, RoleBmc, UserRoleBmc},
+            Role,
+        },
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the check_permission grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with a role that grants a permission in the database
+/// 4. Create a session for the user
+/// 5. Call the check_permission grpc method with the granted permission and another one
+/// 6. Call the check_permission grpc method with an invalid session
+/// 7. Check that only the granted permission is allowed
+/// 8. Check that the invalid session is rejected
+/// 9. Clean all databases
+#[tokio::test]
+async fn check_permission_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the role with its permission and assign it to the user
+    let role_id = RoleBmc::create(
+        &model_manager,
+        Role::new("editor".to_string(), "".to_string())?,
+    )
+    .await?;
+    let permission_id =
+        PermissionBmc::get_or_create(&model_manager, "documents:write".to_string()).await?;
+    RoleBmc::grant_permission(&model_manager, role_id, permission_id).await?;
+    UserRoleBmc::assign(&model_manager, user_auth_db.id, role_id).await?;
+
+    // create a session for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(CheckPermissionRequest {
+        session_id: session_id.clone(),
+        permission: "documents:write".to_string(),
+    });
+
+    let granted_res = client
+        .check_permission(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(CheckPermissionRequest {
+        session_id: session_id.clone(),
+        permission: "documents:delete".to_string(),
+    });
+
+    let not_granted_res = client
+        .check_permission(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(CheckPermissionRequest {
+        session_id: "invalid_session".to_string(),
+        permission: "documents:write".to_string(),
+    });
+
+    let invalid_session_res = client.check_permission(request).await;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    assert!(granted_res.get_ref().allowed);
+    assert_eq!(granted_res.get_ref().user_id, user_auth_db.id.to_string());
+    assert!(!not_granted_res.get_ref().allowed);
+
+    let status = invalid_session_res
+        .err()
+        .ok_or_else(|| Error::Test("invalid session accepted".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
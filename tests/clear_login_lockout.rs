@@ -0,0 +1,162 @@
+use mandos::{
+    config::config,
+    error::{Error, Result},
+    mandos_auth::{ClearLoginLockoutRequest, LoginRequest},
+    model::{
+        db, session,
+        throttle::{self, LoginAttempt, ThrottleScope},
+        user_auth::{model_controller::UserAuthBmc, UserAuth, UserAuthForCreate},
+        ModelManager,
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+use tonic::Code;
+
+/// Test that failed logins are throttled and that the clear_login_lockout grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Check that after the free attempts the login is blocked, also with the right password
+/// 5. Check that the account is locked out after too many failed attempts
+/// 6. Call the clear_login_lockout grpc method for the account
+/// 7. Check that the user can login again
+/// 8. Check that the lockout of an ip can be cleared as well
+/// 9. Clean all databases
+#[tokio::test]
+async fn clear_login_lockout_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database for login
+    let username = "username".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: username.clone(),
+        email: "email@email.com".to_string(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    let login_request = |password: &str| {
+        tonic::Request::new(LoginRequest {
+            username: username.clone(),
+            email: "".to_string(),
+            password: password.to_string(),
+            remember_me: false,
+            issue_access_token: false,
+        })
+    };
+
+    // check that after the free attempts the login is blocked, also with the right password
+    for _ in 0..=config().LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS {
+        let status = client.login(login_request("wrong")).await.unwrap_err();
+        assert!(status.code() == Code::Unauthenticated);
+    }
+    let status = client.login(login_request(&password)).await.unwrap_err();
+    assert!(status.code() == Code::ResourceExhausted);
+    let retry_after = status
+        .metadata()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    assert!(retry_after == Some(config().LOGIN_THROTTLE_BASE_DELAY));
+
+    // check that the account is locked out after too many failed attempts
+    let mut attempt = LoginAttempt::Counted(0);
+    for _ in config().LOGIN_THROTTLE_ACCOUNT_FREE_ATTEMPTS + 1
+        ..config().LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS
+    {
+        attempt = count_attempt_after_block(
+            &model_manager,
+            ThrottleScope::Account,
+            user_auth_db.id.to_string(),
+        )
+        .await?;
+    }
+    assert!(attempt == LoginAttempt::Counted(config().LOGIN_THROTTLE_ACCOUNT_LOCKOUT_ATTEMPTS));
+    let blocked_for = UserAuthBmc::login_blocked_for(
+        &model_manager,
+        ThrottleScope::Account,
+        user_auth_db.id.to_string(),
+    )
+    .await?;
+    assert!(blocked_for == Some(config().LOGIN_LOCKOUT_DURATION));
+    let status = client.login(login_request(&password)).await.unwrap_err();
+    assert!(status.code() == Code::ResourceExhausted);
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ClearLoginLockoutRequest {
+        username: username.clone(),
+        email: "".to_string(),
+        ip: "".to_string(),
+    });
+
+    client
+        .clear_login_lockout(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the user can login again
+    let login_res = client
+        .login(login_request(&password))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // check that the lockout of an ip can be cleared as well
+    let (_, session) =
+        session::crud::get(model_manager.session_db().clone(), login_res.session_id).await?;
+    let ip = session.ip.unwrap_or_default();
+    for _ in 0..config().LOGIN_THROTTLE_IP_LOCKOUT_ATTEMPTS {
+        count_attempt_after_block(&model_manager, ThrottleScope::Ip, ip.clone()).await?;
+    }
+    let status = client.login(login_request(&password)).await.unwrap_err();
+    assert!(status.code() == Code::ResourceExhausted);
+
+    let request = tonic::Request::new(ClearLoginLockoutRequest {
+        username: "".to_string(),
+        email: "".to_string(),
+        ip,
+    });
+    client
+        .clear_login_lockout(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+    assert!(client.login(login_request(&password)).await.is_ok());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
+
+/// Counts a failed login attempt as if the client had waited for the end of the block set by the
+/// previous one
+async fn count_attempt_after_block(
+    model_manager: &ModelManager,
+    scope: ThrottleScope,
+    id: String,
+) -> Result<LoginAttempt> {
+    let mut session_db_conn = model_manager.session_db().get().await?;
+    redis::cmd("DEL")
+        .arg(throttle::block_key(scope, &id))
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    UserAuthBmc::count_login_attempt(model_manager, scope, id).await
+}
@@ -0,0 +1,181 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{CompleteMfaLoginRequest, LoginRequest},
+    model::{
+        audit_log::{model_controller::AuditLogBmc, AuditEvent},
+        db, session,
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::{self, model_controller::UserMfaBmc, UserMfa},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the complete_mfa_login grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with MFA enabled in the database
+/// 4. Check that login returns a challenge instead of a session
+/// 5. Check that a wrong code is rejected and the challenge is still valid
+/// 6. Call the complete_mfa_login grpc method with a valid code
+/// 7. Check that the session has been created
+/// 8. Check that the challenge and the code can't be used again
+/// 9. Check that a recovery code can be used only once and leaves an audit log entry
+/// 10. Clean all databases
+#[tokio::test]
+async fn complete_mfa_login_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let username = "username".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: username.clone(),
+        email: "email@email.com".to_string(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // enable MFA for the user
+    let secret = user_mfa::generate_totp_secret();
+    let user_mfa = UserMfa {
+        totp_enabled: true,
+        ..UserMfa::new(user_auth_db.id, &secret)?
+    };
+    UserMfaBmc::create(&model_manager, user_mfa).await?;
+    let totp = user_mfa::new_totp(secret, user_auth_db.email.clone())?;
+
+    // check that login returns a challenge instead of a session
+    let request = tonic::Request::new(LoginRequest {
+        username: username.clone(),
+        email: "".to_string(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(login_res.mfa_required);
+    assert!(login_res.session_id.is_empty());
+    assert!(!login_res.mfa_challenge.is_empty());
+
+    // check that a wrong code is rejected and the challenge is still valid
+    let request = tonic::Request::new(CompleteMfaLoginRequest {
+        mfa_challenge: login_res.mfa_challenge.clone(),
+        code: "000000".to_string(),
+        recovery_code: "".to_string(),
+    });
+    assert!(client.complete_mfa_login(request).await.is_err());
+
+    // region: call grpc method
+
+    let code = totp
+        .generate_current()
+        .map_err(|e| Error::Test(e.to_string()))?;
+    let request = tonic::Request::new(CompleteMfaLoginRequest {
+        mfa_challenge: login_res.mfa_challenge.clone(),
+        code: code.clone(),
+        recovery_code: "".to_string(),
+    });
+
+    let complete_res = client
+        .complete_mfa_login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the session has been created
+    let (_, session) =
+        session::crud::get(model_manager.session_db().clone(), complete_res.session_id).await?;
+    assert!(session.user_id == user_auth_db.id.to_string());
+    assert!(complete_res.expires_at > chrono::Utc::now().timestamp());
+
+    // check that the challenge can't be used again
+    let request = tonic::Request::new(CompleteMfaLoginRequest {
+        mfa_challenge: login_res.mfa_challenge,
+        code: code.clone(),
+        recovery_code: "".to_string(),
+    });
+    assert!(client.complete_mfa_login(request).await.is_err());
+
+    // check that the code can't be used again with a new challenge
+    let request = tonic::Request::new(LoginRequest {
+        username: username.clone(),
+        email: "".to_string(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let request = tonic::Request::new(CompleteMfaLoginRequest {
+        mfa_challenge: login_res.mfa_challenge,
+        code,
+        recovery_code: "".to_string(),
+    });
+    assert!(client.complete_mfa_login(request).await.is_err());
+
+    // check that a recovery code can be used once in place of the TOTP code
+    let mut recovery_code_results = Vec::new();
+    let recovery_codes =
+        UserMfaBmc::replace_recovery_codes(&model_manager, user_auth_db.id).await?;
+    for _ in 0..2 {
+        let request = tonic::Request::new(LoginRequest {
+            username: username.clone(),
+            email: "".to_string(),
+            password: password.clone(),
+            remember_me: false,
+            issue_access_token: false,
+        });
+        let login_res = client
+            .login(request)
+            .await
+            .map_err(|s| Error::Test(s.to_string()))?
+            .into_inner();
+        let request = tonic::Request::new(CompleteMfaLoginRequest {
+            mfa_challenge: login_res.mfa_challenge,
+            code: "".to_string(),
+            // the code is accepted also when typed without the dash and in upper case
+            recovery_code: recovery_codes[0].replace('-', "").to_uppercase(),
+        });
+        recovery_code_results.push(client.complete_mfa_login(request).await.is_ok());
+    }
+    assert!(recovery_code_results == vec![true, false]);
+    let recovery_codes_left =
+        UserMfaBmc::get_recovery_codes(&model_manager, user_auth_db.id).await?;
+    assert!(recovery_codes_left.len() == recovery_codes.len() - 1);
+    // check that the codes are stored with the lookup hash used to find them
+    assert!(recovery_codes_left
+        .iter()
+        .all(|rc| rc.lookup_hash.is_some()));
+
+    // check that the use of the recovery code is in the audit log
+    let audit_logs = AuditLogBmc::get_all_for_user(&model_manager, user_auth_db.id).await?;
+    assert!(audit_logs.len() == 1);
+    assert!(audit_logs[0].event == AuditEvent::RecoveryCodeUsed.as_ref());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
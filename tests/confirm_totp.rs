@@ -0,0 +1,114 @@
+use mandos::{
+    config::config,
+    error::{Error, Result},
+    mandos_auth::{ConfirmTotpRequest, EnrollTotpRequest},
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::model_controller::UserMfaBmc,
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+use totp_rs::TOTP;
+
+/// Test that the confirm_totp grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user and a session in the database and enroll a TOTP secret
+/// 4. Check that a wrong code does not enable MFA
+/// 5. Call the confirm_totp grpc method with a valid code
+/// 6. Check that MFA has been enabled
+/// 7. Check that the recovery codes have been generated
+/// 8. Clean all databases
+#[tokio::test]
+async fn confirm_totp_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the session
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // enroll the TOTP secret
+    let request = tonic::Request::new(EnrollTotpRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+    let enroll_res = client
+        .enroll_totp(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let totp = TOTP::from_url(enroll_res.otpauth_uri).map_err(|e| Error::Test(e.to_string()))?;
+
+    // check that a wrong code does not enable MFA
+    let request = tonic::Request::new(ConfirmTotpRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        code: "000000".to_string(),
+    });
+    assert!(client.confirm_totp(request).await.is_err());
+    let user_mfa = UserMfaBmc::get(&model_manager, user_auth_db.id).await?;
+    assert!(!user_mfa.totp_enabled);
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ConfirmTotpRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+        code: totp
+            .generate_current()
+            .map_err(|e| Error::Test(e.to_string()))?,
+    });
+
+    let confirm_res = client
+        .confirm_totp(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that MFA has been enabled
+    assert!(confirm_res.success);
+    let user_mfa = UserMfaBmc::get(&model_manager, user_auth_db.id).await?;
+    assert!(user_mfa.totp_enabled);
+
+    // check that the recovery codes have been generated and stored hashed
+    assert!(confirm_res.recovery_codes.len() == config().MFA_RECOVERY_CODES_COUNT);
+    let recovery_codes = UserMfaBmc::get_recovery_codes(&model_manager, user_auth_db.id).await?;
+    assert!(recovery_codes.len() == config().MFA_RECOVERY_CODES_COUNT);
+    assert!(recovery_codes
+        .iter()
+        .all(|rc| !confirm_res.recovery_codes.contains(&rc.code_hash)));
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::CountRecoveryCodesRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::{self, model_controller::UserMfaBmc, UserMfa},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the count_recovery_codes grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with MFA enabled, recovery codes and a session in the database
+/// 4. Use one of the recovery codes
+/// 5. Call the count_recovery_codes grpc method
+/// 6. Check that the used code is not counted
+/// 7. Clean all databases
+#[tokio::test]
+async fn count_recovery_codes_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // enable MFA for the user
+    let user_mfa = UserMfa {
+        totp_enabled: true,
+        ..UserMfa::new(user_auth_db.id, &user_mfa::generate_totp_secret())?
+    };
+    UserMfaBmc::create(&model_manager, user_mfa).await?;
+    let recovery_codes =
+        UserMfaBmc::replace_recovery_codes(&model_manager, user_auth_db.id).await?;
+
+    // use one of the recovery codes
+    UserMfaBmc::use_recovery_code(&model_manager, user_auth_db.id, recovery_codes[0].clone())
+        .await?;
+
+    // create the session
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(CountRecoveryCodesRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+    });
+
+    let count_res = client
+        .count_recovery_codes(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the used code is not counted
+    assert!(count_res.remaining == recovery_codes.len() as u64 - 1);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
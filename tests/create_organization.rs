@@ -0,0 +1,87 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::CreateOrganizationRequest,
+    model::{
+        db,
+        organization::model_controller::OrganizationBmc,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Test that the create_organization grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Create a session for the user
+/// 5. Call the create_organization grpc method
+/// 6. Check that the organization has been created with the user as its only owner
+/// 7. Clean all databases
+#[tokio::test]
+async fn create_organization_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create a session for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    let name = "Acme".to_string();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(CreateOrganizationRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+        name: name.clone(),
+    });
+
+    let create_res = client
+        .create_organization(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    let organization_id = Uuid::parse_str(create_res.organization_id.as_str())
+        .map_err(|e| Error::Test(e.to_string()))?;
+    let organization = OrganizationBmc::get(&model_manager, organization_id).await?;
+    let members = OrganizationBmc::get_members(&model_manager, organization_id).await?;
+
+    // region: tests
+
+    assert_eq!(organization.name, name);
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].user_id, user_auth_db.id);
+    assert_eq!(members[0].role, "owner");
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
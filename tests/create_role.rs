@@ -0,0 +1,70 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::CreateRoleRequest,
+    model::rbac::model_controller::RoleBmc,
+    utils_tests,
+};
+use uuid::Uuid;
+
+/// Test that the create_role grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Call the create_role grpc method
+/// 4. Call the create_role grpc method again with the same name
+/// 5. Check that the role has been created
+/// 6. Check that the second role has been rejected
+/// 7. Clean all databases
+#[tokio::test]
+async fn create_role_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let name = "editor".to_string();
+    let description = "Can edit documents".to_string();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(CreateRoleRequest {
+        name: name.clone(),
+        description: description.clone(),
+    });
+
+    let res = client
+        .create_role(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(CreateRoleRequest {
+        name: name.clone(),
+        description: description.clone(),
+    });
+
+    let duplicate_res = client.create_role(request).await;
+
+    // endregion: call grpc method
+
+    let role_id =
+        Uuid::parse_str(res.get_ref().role_id.as_str()).map_err(|e| Error::Test(e.to_string()))?;
+    let role = RoleBmc::get(&model_manager, role_id).await?;
+
+    // region: tests
+
+    assert_eq!(role.name, name);
+    assert_eq!(role.description, description);
+
+    let status = duplicate_res
+        .err()
+        .ok_or_else(|| Error::Test("duplicate role created".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -2,7 +2,8 @@ use mandos::{
     error::{Error, Result},
     mandos_auth::DeleteAccountRequest,
     model::{
-        db, session,
+        db,
+        session::{self, Session},
         user_auth::{UserAuth, UserAuthForCreate},
     },
     utils_tests,
@@ -14,10 +15,10 @@ use sqlx::FromRow;
 /// 1. Setup test environment (Env variables, run server in the backgroung, get client)
 /// 2. Clean all databases
 /// 3. Create a user in the database
-/// 4. Create a session for the user
+/// 4. Create two sessions for the user
 /// 5. Call the delete_account grpc method
 /// 6. Check that the user has been deleted
-/// 7. Check that the session has been deleted
+/// 7. Check that all the sessions have been deleted
 /// 8. Clean all databases
 #[tokio::test]
 async fn delete_account_works() -> Result<()> {
@@ -30,7 +31,7 @@ async fn delete_account_works() -> Result<()> {
     // create the user in the database for login
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
+    let password = "correct-horse-battery".to_string();
     let user_auth_for_create = UserAuthForCreate {
         username: username.clone(),
         email: email.clone(),
@@ -41,10 +42,16 @@ async fn delete_account_works() -> Result<()> {
     // newly created user
     let user_auth_db = UserAuth::from_row(&res)?;
 
-    // create a session for the user
+    // create two sessions for the user
     let session_id = session::crud::create(
         model_manager.session_db().clone(),
-        user_auth_db.id.to_string().clone(),
+        Session::new(user_auth_db.id.to_string().clone()),
+        60,
+    )
+    .await?;
+    let other_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string().clone()),
         60,
     )
     .await?;
@@ -73,11 +80,16 @@ async fn delete_account_works() -> Result<()> {
             .is_ok();
     assert!(!user_still_exists);
 
-    // check that the session has been deleted
+    // check that the sessions have been deleted
     let session_still_exists = session::crud::get(model_manager.session_db().clone(), session_id)
         .await
         .is_ok();
     assert!(!session_still_exists);
+    let other_session_still_exists =
+        session::crud::get(model_manager.session_db().clone(), other_session_id)
+            .await
+            .is_ok();
+    assert!(!other_session_still_exists);
 
     // endregion: tests
 
@@ -0,0 +1,49 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::DeleteRoleRequest,
+    model::rbac::{model_controller::RoleBmc, Role},
+    utils_tests,
+};
+
+/// Test that the delete_role grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a role in the database
+/// 4. Call the delete_role grpc method
+/// 5. Check that the role has been deleted
+/// 6. Clean all databases
+#[tokio::test]
+async fn delete_role_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the role in the database
+    let name = "editor".to_string();
+    let role_id = RoleBmc::create(&model_manager, Role::new(name.clone(), "".to_string())?).await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(DeleteRoleRequest { name: name.clone() });
+
+    client
+        .delete_role(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    assert!(RoleBmc::get(&model_manager, role_id).await.is_err());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::DisableTotpRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::{self, model_controller::UserMfaBmc, UserMfa},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the disable_totp grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with MFA enabled and a session in the database
+/// 4. Check that a wrong code does not disable MFA
+/// 5. Call the disable_totp grpc method with a valid code
+/// 6. Check that MFA has been disabled
+/// 7. Clean all databases
+#[tokio::test]
+async fn disable_totp_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // enable MFA for the user
+    let secret = user_mfa::generate_totp_secret();
+    let user_mfa = UserMfa {
+        totp_enabled: true,
+        ..UserMfa::new(user_auth_db.id, &secret)?
+    };
+    UserMfaBmc::create(&model_manager, user_mfa).await?;
+    let totp = user_mfa::new_totp(secret, user_auth_db.email.clone())?;
+
+    // create the session
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // check that a wrong code does not disable MFA
+    let request = tonic::Request::new(DisableTotpRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        code: "000000".to_string(),
+    });
+    assert!(client.disable_totp(request).await.is_err());
+    assert!(UserMfaBmc::is_enabled(&model_manager, user_auth_db.id).await?);
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(DisableTotpRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+        code: totp
+            .generate_current()
+            .map_err(|e| Error::Test(e.to_string()))?,
+    });
+
+    let disable_res = client
+        .disable_totp(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that MFA has been disabled
+    assert!(disable_res.success);
+    assert!(!UserMfaBmc::is_enabled(&model_manager, user_auth_db.id).await?);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
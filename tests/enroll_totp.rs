@@ -0,0 +1,108 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::EnrollTotpRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::{model_controller::UserMfaBmc, UserMfaForUpdate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the enroll_totp grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user and a session in the database
+/// 4. Call the enroll_totp grpc method
+/// 5. Check that the secret has been stored encrypted and MFA is not enabled yet
+/// 6. Check that enrolling again replaces the secret
+/// 7. Check that enrolling fails when MFA is already enabled
+/// 8. Clean all databases
+#[tokio::test]
+async fn enroll_totp_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the session
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(EnrollTotpRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+
+    let enroll_res = client
+        .enroll_totp(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the uri contains the secret
+    assert!(!enroll_res.secret.is_empty());
+    assert!(enroll_res.otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(enroll_res
+        .otpauth_uri
+        .contains(&format!("secret={}", enroll_res.secret)));
+
+    // check that the secret has been stored encrypted and MFA is not enabled yet
+    let user_mfa = UserMfaBmc::get(&model_manager, user_auth_db.id).await?;
+    assert!(!user_mfa.totp_enabled);
+    assert!(user_mfa.totp_secret != enroll_res.secret);
+
+    // check that enrolling again replaces the secret
+    let request = tonic::Request::new(EnrollTotpRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+    let enroll_res_again = client
+        .enroll_totp(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(enroll_res_again.secret != enroll_res.secret);
+
+    // check that enrolling fails when MFA is already enabled
+    let mut user_mfa_for_update = UserMfaForUpdate::new();
+    user_mfa_for_update.totp_enabled = Some(true);
+    UserMfaBmc::update(&model_manager, user_mfa_for_update, user_auth_db.id).await?;
+    let request = tonic::Request::new(EnrollTotpRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+    });
+    assert!(client.enroll_totp(request).await.is_err());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
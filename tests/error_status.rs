@@ -0,0 +1,169 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{EnrollTotpRequest, LogoutRequest, RegisterRequest},
+    model::{
+        rbac::Role,
+        session::{self, Session},
+    },
+    server::error_details::{self, ERROR_DOMAIN},
+    utils_tests,
+};
+use uuid::Uuid;
+
+/// Test that the errors are mapped to their status codes with stable reasons and without the
+/// details of the database
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Call the register grpc method
+/// 4. Call the register grpc method with the same username, with the same email and with an
+///    invalid username
+/// 5. Check that the duplicates are already_exists with the USERNAME_TAKEN and EMAIL_TAKEN reasons
+/// 6. Check that the invalid username is invalid_argument with the BadRequest details
+/// 7. Check that the messages don't contain the database errors
+/// 8. Call the logout grpc method with empty fields, an unknown session and another user_id
+/// 9. Check that the request errors have their own reasons
+/// 10. Call the enroll_totp grpc method with the session of a user that does not exist
+/// 11. Check that the missing user is session_not_found and not a generic not_found
+/// 12. Check that an invalid role name is not echoed back in the BadRequest details
+/// 13. Clean all databases
+#[tokio::test]
+async fn error_status_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let password = "correct-horse-battery".to_string();
+
+    // region: call grpc method
+
+    client
+        .register(tonic::Request::new(RegisterRequest {
+            username: "username".to_string(),
+            email: "email@email.com".to_string(),
+            password: password.clone(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let mut statuses = Vec::new();
+    for (username, email) in [
+        ("username", "other@email.com"),
+        ("other", "email@email.com"),
+        ("not a username!", "another@email.com"),
+    ] {
+        let status = client
+            .register(tonic::Request::new(RegisterRequest {
+                username: username.to_string(),
+                email: email.to_string(),
+                password: password.clone(),
+            }))
+            .await
+            .err()
+            .ok_or_else(|| Error::Test(format!("{username} / {email} registered")))?;
+        statuses.push(status);
+    }
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    let expected = [
+        (tonic::Code::AlreadyExists, "USERNAME_TAKEN"),
+        (tonic::Code::AlreadyExists, "EMAIL_TAKEN"),
+        (tonic::Code::InvalidArgument, "INVALID_USERNAME"),
+    ];
+    for (status, (code, reason)) in statuses.iter().zip(expected) {
+        assert_eq!(status.code(), code);
+
+        let error_info = error_details::error_info_details(status)
+            .ok_or_else(|| Error::Test("missing error info".to_string()))?;
+        assert_eq!(error_info.reason, reason);
+        assert_eq!(error_info.domain, ERROR_DOMAIN);
+
+        // the database errors are not sent to the clients
+        assert!(!status.message().contains("duplicate key"));
+        assert!(!status.message().contains("users_auth"));
+    }
+
+    let details = error_details::bad_request_details(&statuses[2])
+        .ok_or_else(|| Error::Test("missing bad request details".to_string()))?;
+    assert_eq!(details.field_violations.len(), 1);
+    assert_eq!(details.field_violations[0].field, "username");
+    assert_eq!(details.field_violations[0].reason, "INVALID_USERNAME");
+
+    // check that the request errors have their own reasons
+    let missing_user_id = Uuid::new_v4().to_string();
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(missing_user_id.clone()),
+        60,
+    )
+    .await?;
+    let unknown_session_id = Uuid::new_v4().to_string();
+    let expected = [
+        ("", "", tonic::Code::InvalidArgument, "FIELDS_EMPTY"),
+        (
+            unknown_session_id.as_str(),
+            "user_id",
+            tonic::Code::Unauthenticated,
+            "SESSION_NOT_FOUND",
+        ),
+        (
+            session_id.as_str(),
+            "user_id",
+            tonic::Code::InvalidArgument,
+            "USER_ID_MISMATCH",
+        ),
+    ];
+    for (session_id, user_id, code, reason) in expected {
+        let status = client
+            .logout(tonic::Request::new(LogoutRequest {
+                session_id: session_id.to_string(),
+                user_id: user_id.to_string(),
+            }))
+            .await
+            .err()
+            .ok_or_else(|| Error::Test(format!("{reason} not returned")))?;
+        assert_eq!(status.code(), code);
+        let error_info = error_details::error_info_details(&status)
+            .ok_or_else(|| Error::Test("missing error info".to_string()))?;
+        assert_eq!(error_info.reason, reason);
+    }
+
+    // check that the missing rows are mapped by the route, the user of the session does not exist
+    let status = client
+        .enroll_totp(tonic::Request::new(EnrollTotpRequest {
+            session_id: session_id.clone(),
+            user_id: missing_user_id,
+        }))
+        .await
+        .err()
+        .ok_or_else(|| Error::Test("enroll_totp of a missing user succeeded".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    let error_info = error_details::error_info_details(&status)
+        .ok_or_else(|| Error::Test("missing error info".to_string()))?;
+    assert_eq!(error_info.reason, "SESSION_NOT_FOUND");
+
+    // check that the invalid names are not echoed back
+    let role_name = "<script>alert(1)</script>";
+    let status = tonic::Status::from(
+        Role::new(role_name.to_string(), "".to_string())
+            .err()
+            .ok_or_else(|| Error::Test("invalid role name accepted".to_string()))?,
+    );
+    let details = error_details::bad_request_details(&status)
+        .ok_or_else(|| Error::Test("missing bad request details".to_string()))?;
+    assert_eq!(details.field_violations[0].reason, "INVALID_ROLE_NAME");
+    assert!(!details.field_violations[0].description.contains(role_name));
+    assert!(!status.message().contains(role_name));
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::GetJwksRequest,
+    utils_tests,
+};
+
+/// Test that the get_jwks grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Call the get_jwks grpc method
+/// 3. Check that all the configured keys are published with their public components
+#[tokio::test]
+async fn get_jwks_works() -> Result<()> {
+    // setup test environment
+    let (_, mut client) = utils_tests::setup_test_environment().await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(GetJwksRequest {});
+
+    let jwks_res = client
+        .get_jwks(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // the keys configured in .env.test
+    assert_eq!(jwks_res.keys.len(), 2);
+
+    let ed25519_key = &jwks_res.keys[0];
+    assert_eq!(ed25519_key.kid, "test-ed25519");
+    assert_eq!(ed25519_key.kty, "OKP");
+    assert_eq!(ed25519_key.alg, "EdDSA");
+    assert_eq!(ed25519_key.crv, "Ed25519");
+    assert!(!ed25519_key.x.is_empty());
+
+    let rsa_key = &jwks_res.keys[1];
+    assert_eq!(rsa_key.kid, "test-rsa");
+    assert_eq!(rsa_key.kty, "RSA");
+    assert_eq!(rsa_key.alg, "RS256");
+    assert!(!rsa_key.n.is_empty());
+    assert_eq!(rsa_key.e, "AQAB");
+
+    assert!(jwks_res.keys.iter().all(|k| k.r#use == "sig"));
+
+    // endregion: tests
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::GrantPermissionRequest,
+    model::rbac::{model_controller::RoleBmc, Role},
+    utils_tests,
+};
+
+/// Test that the grant_permission grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a role in the database
+/// 4. Call the grant_permission grpc method twice with the same permission
+/// 5. Check that the role has the permission once
+/// 6. Clean all databases
+#[tokio::test]
+async fn grant_permission_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the role in the database
+    let role = "editor".to_string();
+    let permission = "documents:write".to_string();
+    let role_id = RoleBmc::create(&model_manager, Role::new(role.clone(), "".to_string())?).await?;
+
+    // region: call grpc method
+
+    // granting a permission twice does nothing
+    for _ in 0..2 {
+        let request = tonic::Request::new(GrantPermissionRequest {
+            role: role.clone(),
+            permission: permission.clone(),
+        });
+
+        client
+            .grant_permission(request)
+            .await
+            .map_err(|s| Error::Test(s.to_string()))?;
+    }
+
+    // endregion: call grpc method
+
+    let permissions = RoleBmc::get_permissions(&model_manager, role_id).await?;
+
+    // region: tests
+
+    assert_eq!(permissions.len(), 1);
+    assert_eq!(permissions[0].name, permission);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
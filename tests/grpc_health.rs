@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::HealthCheckRequest,
+    server::health::SERVICE_NAME,
+    utils_tests,
+};
+use tonic::transport::Channel;
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient,
+    HealthCheckRequest as GrpcHealthCheckRequest,
+};
+
+/// Returns the status of the service from the grpc.health.v1 service
+async fn serving_status(
+    client: &mut HealthClient<Channel>,
+    service: &str,
+) -> Result<ServingStatus> {
+    let res = client
+        .check(tonic::Request::new(GrpcHealthCheckRequest {
+            service: service.to_string(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    Ok(res.status())
+}
+
+/// Test that the grpc.health.v1 service follows the status of the dependencies
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung on a free port)
+/// 2. Call the grpc.health.v1 check method without API client credentials
+/// 3. Check that the server and the mandos_auth service are serving
+/// 4. Close the connection pool of the session db
+/// 5. Check that the server becomes not serving
+/// 6. Check that the health_check grpc method reports the failing dependency
+#[tokio::test]
+async fn grpc_health_works() -> Result<()> {
+    // setup test environment, on its own server since the test breaks its session db pool
+    let (model_manager, server_handle) =
+        utils_tests::start_test_server("127.0.0.1:0", None).await?;
+    let client_addr = format!("http://{}", server_handle.local_addr());
+
+    let channel = Channel::from_shared(client_addr.clone())
+        .map_err(|e| Error::Test(e.to_string()))?
+        .connect()
+        .await?;
+    let mut health_client = HealthClient::new(channel);
+
+    // region: call grpc method
+
+    // the first check of the dependencies runs as soon as the server starts
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let server_status = serving_status(&mut health_client, "").await?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the server and the service are serving
+    assert!(server_status == ServingStatus::Serving);
+    assert!(serving_status(&mut health_client, SERVICE_NAME).await? == ServingStatus::Serving);
+
+    // check that the status changes once a dependency is not available
+    model_manager.session_db().close();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while serving_status(&mut health_client, SERVICE_NAME).await? != ServingStatus::NotServing {
+        assert!(Instant::now() < deadline, "status still serving");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    assert!(serving_status(&mut health_client, "").await? == ServingStatus::NotServing);
+
+    // check that the custom health check reports the failing dependency
+    let mut client = utils_tests::get_grpc_client_at(
+        client_addr,
+        utils_tests::TEST_API_CLIENT,
+        utils_tests::TEST_API_CLIENT_SECRET,
+    )
+    .await?;
+    let health_check_res = client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(!health_check_res.success);
+    let redis_status = health_check_res
+        .dependencies
+        .iter()
+        .find(|d| d.name == "redis")
+        .ok_or_else(|| Error::Test("redis status missing".to_string()))?;
+    assert!(!redis_status.serving && !redis_status.error.is_empty());
+
+    // endregion: tests
+
+    server_handle.shutdown().await?;
+
+    Ok(())
+}
@@ -5,18 +5,43 @@ use mandos::{
 };
 
 /// Test that the health_check grpc method works
+/// Steps:
 /// 1. Setup test environment (Env variables, run server in the backgroung, get client)
-/// 4. Call the health_check grpc method
+/// 2. Call the health_check grpc method
+/// 3. Check that both the dependencies are serving
 #[tokio::test]
 async fn health_check_works() -> Result<()> {
     // setup test environment
     let (_, mut client) = utils_tests::setup_test_environment().await?;
 
+    // region: call grpc method
+
     let request = tonic::Request::new(HealthCheckRequest {});
 
-    let response = client.health_check(request).await;
-    match response {
-        Ok(_) => Ok(()),
-        Err(e) => Err(Error::Service(e.to_string())),
-    }
+    let health_check_res = client
+        .health_check(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    assert!(health_check_res.success);
+
+    let names: Vec<&str> = health_check_res
+        .dependencies
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    assert!(names == vec!["postgres", "redis"]);
+    assert!(health_check_res
+        .dependencies
+        .iter()
+        .all(|d| d.serving && d.error.is_empty() && d.latency_micros > 0));
+
+    // endregion: tests
+
+    Ok(())
 }
@@ -0,0 +1,84 @@
+use mandos::{
+    error::Result,
+    model::{
+        session::{self, Session},
+        user_auth::model_controller::UserAuthBmc,
+    },
+    utils_tests,
+};
+use uuid::Uuid;
+
+/// Test that the sessions created before the user's session indexes are indexed
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Store a session as a plain user id, without adding it to the user's session index
+/// 4. Index the legacy sessions
+/// 5. Check that the session is listed with the sessions of the user
+/// 6. Check that indexing again does not duplicate the sessions
+/// 7. Check that revoking all the sessions of the user deletes it
+/// 8. Clean all databases
+#[tokio::test]
+async fn index_legacy_sessions_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, _) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // store a legacy session, outside of the user's session index
+    let user_id = Uuid::new_v4();
+    let legacy_session_id = Uuid::new_v4().to_string();
+    let mut session_db_conn = model_manager.session_db().get().await?;
+    redis::cmd("SET")
+        .arg(&[
+            legacy_session_id.clone(),
+            user_id.to_string(),
+            "EX".to_string(),
+            "60".to_string(),
+        ])
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+
+    // a session already in the index, that is indexed again without being duplicated
+    session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: index legacy sessions
+
+    let indexed = session::crud::index_legacy_sessions(model_manager.session_db().clone()).await?;
+    let indexed_again =
+        session::crud::index_legacy_sessions(model_manager.session_db().clone()).await?;
+
+    // endregion: index legacy sessions
+
+    // region: tests
+
+    // check that the legacy session is listed with the other session of the user, only once
+    // even if indexed again
+    assert_eq!(indexed, 2);
+    assert_eq!(indexed_again, 2);
+    let sessions = UserAuthBmc::list_sessions(&model_manager, user_id).await?;
+    assert_eq!(sessions.len(), 2);
+    assert!(sessions.iter().any(|(id, _)| *id == legacy_session_id));
+
+    // check that the legacy session is revoked with the others
+    let revoked = UserAuthBmc::delete_all_sessions(&model_manager, user_id, None).await?;
+    assert_eq!(revoked, 2);
+    let exists: bool = redis::cmd("EXISTS")
+        .arg(&legacy_session_id)
+        .query_async(&mut session_db_conn)
+        .await?;
+    assert!(!exists);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
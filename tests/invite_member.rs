@@ -0,0 +1,125 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::InviteMemberRequest,
+    model::{
+        db,
+        organization::{
+            model_controller::OrganizationBmc, Organization, OrganizationMember, OrganizationRole,
+        },
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the invite_member grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create an owner and a member of an organization in the database
+/// 4. Create a session for both users
+/// 5. Call the invite_member grpc method as the owner
+/// 6. Call the invite_member grpc method as the member
+/// 7. Check that the invitation has been sent by email
+/// 8. Check that the member is not allowed to invite
+/// 9. Clean all databases
+#[tokio::test]
+async fn invite_member_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database with a session
+    let mut users = Vec::new();
+    for username in ["owner", "member"] {
+        let user_auth_for_create = UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: "correct-horse-battery".to_string(),
+        };
+        let user_auth = UserAuth::new(user_auth_for_create)?;
+        let res =
+            db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+        let user_auth_db = UserAuth::from_row(&res)?;
+
+        let session_id = session::crud::create(
+            model_manager.session_db().clone(),
+            Session::new(user_auth_db.id.to_string()),
+            60,
+        )
+        .await?;
+
+        users.push((user_auth_db, session_id));
+    }
+    let (owner, owner_session_id) = &users[0];
+    let (member, member_session_id) = &users[1];
+
+    // create the organization
+    let organization_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Acme".to_string())?,
+        owner.id,
+    )
+    .await?;
+    OrganizationBmc::add_member(
+        &model_manager,
+        OrganizationMember::new(organization_id, member.id, OrganizationRole::Member),
+    )
+    .await?;
+
+    let invited_email = "Invited@Email.com".to_string();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(InviteMemberRequest {
+        session_id: owner_session_id.clone(),
+        user_id: owner.id.to_string(),
+        organization_id: organization_id.to_string(),
+        email: invited_email.clone(),
+        role: "admin".to_string(),
+    });
+
+    client
+        .invite_member(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(InviteMemberRequest {
+        session_id: member_session_id.clone(),
+        user_id: member.id.to_string(),
+        organization_id: organization_id.to_string(),
+        email: "other@email.com".to_string(),
+        role: "member".to_string(),
+    });
+
+    let member_res = client.invite_member(request).await;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    let token = utils_tests::last_mail_token(&invited_email)
+        .ok_or_else(|| Error::Test("invitation not sent".to_string()))?;
+    let invitation = OrganizationBmc::get_invitation(&model_manager, token)
+        .await?
+        .ok_or_else(|| Error::Test("invitation not stored".to_string()))?;
+    assert_eq!(invitation.organization_id, organization_id);
+    assert_eq!(invitation.email, "invited@email.com");
+    assert_eq!(invitation.role, OrganizationRole::Admin);
+
+    let status = member_res
+        .err()
+        .ok_or_else(|| Error::Test("member allowed to invite".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert!(utils_tests::last_mail_token("other@email.com").is_none());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use mandos::{
+    config,
+    error::{Error, Result},
+    jwt,
+    mandos_auth::GetJwksRequest,
+    utils_tests,
+};
+
+/// Test that the JWT signing keys are reloaded without a restart
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Issue an access token with the key configured in .env.test
+/// 3. Move the keys to a keys file with a new first key and reload the config
+/// 4. Check that the new tokens are signed with the new key and the old token is still valid
+/// 5. Check that both keys are published by the get_jwks grpc method
+/// 6. Check that a failed reload keeps the current keys
+#[tokio::test]
+async fn jwt_keys_reload_works() -> Result<()> {
+    // setup test environment
+    let (_, mut client) = utils_tests::setup_test_environment().await?;
+
+    let (old_token, _) = jwt::issue_access_token(
+        "user_id".to_string(),
+        "session_id",
+        vec!["editor".to_string()],
+    )?;
+
+    // region: reload the keys
+
+    std::env::remove_var("JWT_KEYS");
+    std::env::set_var("JWT_KEYS_FILE", "./tests/keys/jwt_keys");
+    config::reload()?;
+
+    // endregion: reload the keys
+
+    // region: tests
+
+    let (new_token, _) = jwt::issue_access_token(
+        "user_id".to_string(),
+        "session_id",
+        vec!["editor".to_string()],
+    )?;
+    let header = jsonwebtoken::decode_header(&new_token).map_err(|e| Error::Test(e.to_string()))?;
+    assert_eq!(header.kid.as_deref(), Some("test-rsa"));
+    assert!(jwt::verify_access_token(&new_token).is_ok());
+    assert!(jwt::verify_access_token(&old_token).is_ok());
+
+    let jwks_res = client
+        .get_jwks(tonic::Request::new(GetJwksRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let kids: Vec<&str> = jwks_res.keys.iter().map(|k| k.kid.as_str()).collect();
+    assert_eq!(kids, vec!["test-rsa", "test-ed25519"]);
+
+    // check that the keys are kept when the keys file can't be read
+    std::env::set_var("JWT_KEYS_FILE", "./tests/keys/missing");
+    assert!(config::reload().is_err());
+    assert_eq!(jwt::jwks().len(), 2);
+    assert!(jwt::verify_access_token(&new_token).is_ok());
+
+    // endregion: tests
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::ListOrganizationsRequest,
+    model::{
+        db,
+        organization::{
+            model_controller::OrganizationBmc, Organization, OrganizationMember, OrganizationRole,
+        },
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the list_organizations grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create two users in the database
+/// 4. Create an organization owned by the user and one where the user is a member
+/// 5. Create a session for the user
+/// 6. Call the list_organizations grpc method
+/// 7. Check that both organizations are returned with the role of the user
+/// 8. Clean all databases
+#[tokio::test]
+async fn list_organizations_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database
+    let mut users = Vec::new();
+    for username in ["username", "other"] {
+        let user_auth_for_create = UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: "correct-horse-battery".to_string(),
+        };
+        let user_auth = UserAuth::new(user_auth_for_create)?;
+        let res =
+            db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+        users.push(UserAuth::from_row(&res)?);
+    }
+    let user = &users[0];
+    let other = &users[1];
+
+    // create the organizations
+    OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Owned".to_string())?,
+        user.id,
+    )
+    .await?;
+    let joined_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Joined".to_string())?,
+        other.id,
+    )
+    .await?;
+    OrganizationBmc::add_member(
+        &model_manager,
+        OrganizationMember::new(joined_id, user.id, OrganizationRole::Member),
+    )
+    .await?;
+
+    // create a session for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ListOrganizationsRequest {
+        session_id,
+        user_id: user.id.to_string(),
+    });
+
+    let list_res = client
+        .list_organizations(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    let mut organizations: Vec<(String, String)> = list_res
+        .organizations
+        .into_iter()
+        .map(|o| (o.name, o.role))
+        .collect();
+    organizations.sort();
+    assert_eq!(
+        organizations,
+        vec![
+            ("Joined".to_string(), "member".to_string()),
+            ("Owned".to_string(), "owner".to_string())
+        ]
+    );
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
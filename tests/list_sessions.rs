@@ -0,0 +1,88 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::ListSessionsRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the list_sessions grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Create two sessions for the user
+/// 5. Call the list_sessions grpc method
+/// 6. Check that both sessions are returned and that the current one is flagged
+/// 7. Clean all databases
+#[tokio::test]
+async fn list_sessions_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create two sessions for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+    let other_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ListSessionsRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+
+    let list_res = client
+        .list_sessions(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    assert!(list_res.sessions.len() == 2);
+    assert!(list_res
+        .sessions
+        .iter()
+        .any(|s| s.session_id == session_id && s.current));
+    assert!(list_res
+        .sessions
+        .iter()
+        .any(|s| s.session_id == other_session_id && !s.current));
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use mandos::{
+    error::{Error, Result},
+    tracing::{
+        log_layer,
+        redact::{self, REDACTED},
+        LogFormat,
+    },
+};
+use serde_json::Value;
+use tracing::{info, info_span};
+use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, Registry};
+use uuid::Uuid;
+
+const EMAIL: &str = "someone@example.com";
+const PASSWORD: &str = "correct-horse-battery";
+const SESSION_ID: &str = "5f0c2ac4-0e55-4c39-9a4b-8c1e1a0c2b7e";
+const TOKEN: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+/// Keeps the log lines in memory
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Buffer {
+    type Writer = Buffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Logs an event with sensitive data inside a span with sensitive fields
+fn log_sensitive_event() {
+    let span = info_span!(
+        "grpc_request",
+        request_id = "test-request-id",
+        session_id = SESSION_ID
+    );
+    let _guard = span.enter();
+
+    info!(
+        password = PASSWORD,
+        user_id = 42,
+        "Mail to: {EMAIL} - link: https://mandos.local/verify?token={TOKEN}"
+    );
+}
+
+/// Test that the json logs are parseable and that the sensitive data is redacted
+/// Steps:
+/// 1. Log an event with the json format into a buffer
+/// 2. Check that the line is a json object with the level, the target, the fields and the spans
+/// 3. Check that the password, the session id, the email and the token are not logged
+#[test]
+fn json_log_redaction_works() -> Result<()> {
+    let buffer = Buffer::default();
+    let subscriber = Registry::default().with(log_layer(&LogFormat::Json, buffer.clone()));
+    tracing::subscriber::with_default(subscriber, log_sensitive_event);
+
+    let lines = buffer.lines();
+    assert!(lines.len() == 1);
+    let line: Value = serde_json::from_str(&lines[0]).map_err(|e| Error::Test(e.to_string()))?;
+
+    // check the structure of the line
+    assert!(line["level"] == "INFO");
+    assert!(line["target"] == "log_redaction");
+    assert!(line["timestamp"].is_string());
+    assert!(line["fields"]["user_id"] == 42);
+    assert!(line["spans"][0]["name"] == "grpc_request");
+    assert!(line["spans"][0]["request_id"] == "test-request-id");
+
+    // check that the sensitive data is redacted
+    assert!(line["fields"]["password"] == REDACTED);
+    assert!(line["spans"][0]["session_id"] == REDACTED);
+    assert!(
+        line["fields"]["message"]
+            == format!(
+                "Mail to: ***@example.com - link: https://mandos.local/verify?token={REDACTED}"
+            )
+            .as_str()
+    );
+    for secret in [EMAIL, PASSWORD, SESSION_ID, TOKEN] {
+        assert!(!lines[0].contains(secret));
+    }
+
+    Ok(())
+}
+
+/// Test that the sensitive data is redacted from the text logs
+/// Steps:
+/// 1. Log an event with the text format into a buffer
+/// 2. Check that the password, the session id, the email and the token are not logged
+#[test]
+fn text_log_redaction_works() -> Result<()> {
+    let buffer = Buffer::default();
+    let subscriber = Registry::default().with(log_layer(&LogFormat::Text, buffer.clone()));
+    tracing::subscriber::with_default(subscriber, log_sensitive_event);
+
+    let lines = buffer.lines();
+    assert!(lines.len() == 1);
+    assert!(lines[0].contains("***@example.com"));
+    assert!(lines[0].contains(&format!("password={REDACTED}")));
+    assert!(lines[0].contains(&format!("session_id={REDACTED}")));
+    assert!(lines[0].contains("request_id=test-request-id"));
+    for secret in [EMAIL, PASSWORD, SESSION_ID, TOKEN] {
+        assert!(!lines[0].contains(secret));
+    }
+
+    Ok(())
+}
+
+/// Test the redaction of single values
+#[test]
+fn redact_values_works() -> Result<()> {
+    assert!(redact::is_sensitive_field("new_password"));
+    assert!(redact::is_sensitive_field("refresh_token"));
+    assert!(redact::is_sensitive_field("http.authorization"));
+    assert!(!redact::is_sensitive_field("user_id"));
+    assert!(!redact::is_sensitive_field("rpc.grpc.status_code"));
+
+    assert!(redact::redact_field("session_id", SESSION_ID) == REDACTED);
+    assert!(redact::redact_field("to", EMAIL) == "***@example.com");
+    assert!(
+        redact::redact_text("Authorization: Bearer abc.def")
+            == format!("Authorization: {REDACTED}")
+    );
+    assert!(redact::redact_text("user 42 logged in") == "user 42 logged in");
+    assert!(redact::redact_text(&format!("session {SESSION_ID}")) == format!("session {REDACTED}"));
+    assert!(redact::redact_field("user_id", SESSION_ID) == SESSION_ID);
+
+    Ok(())
+}
+
+/// Test that the session ids are redacted from the messages, while the ids of the records are kept
+/// Steps:
+/// 1. Log an event with a session id generated like the ones of the server in the message and in
+///    a field that is not sensitive, and with a user id
+/// 2. Check that the session id is not logged and that the user id is
+#[test]
+fn session_id_redaction_works() -> Result<()> {
+    let session_id = Uuid::new_v4().to_string();
+    let user_id = Uuid::new_v4().to_string();
+
+    let buffer = Buffer::default();
+    let subscriber = Registry::default().with(log_layer(&LogFormat::Text, buffer.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        info!(
+            user_id = user_id.as_str(),
+            key = session_id.as_str(),
+            "session {session_id} expired"
+        );
+    });
+
+    let lines = buffer.lines();
+    assert!(lines.len() == 1);
+    assert!(lines[0].contains(&format!("session {REDACTED} expired")));
+    assert!(lines[0].contains(&format!("key={REDACTED}")));
+    assert!(lines[0].contains(&format!("user_id={user_id}")));
+    assert!(!lines[0].contains(&session_id));
+    assert!(!lines[0].contains(&session_id.to_uppercase()));
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use mandos::{
+    config::config,
     error::{Error, Result},
     mandos_auth::LoginRequest,
     model::{
@@ -17,8 +18,9 @@ use uuid::Uuid;
 /// 3. Create a user in the database
 /// 4. Call the login grpc method
 /// 5. Check that the last login field has been updated
-/// 6. Check that the session has been created
-/// 7. Clean all databases
+/// 6. Check that the session has been created with its metadata
+/// 7. Check that the session lifetime depends on remember_me
+/// 8. Clean all databases
 #[tokio::test]
 async fn login_works() -> Result<()> {
     // setup test environment
@@ -30,7 +32,7 @@ async fn login_works() -> Result<()> {
     // create the user in the database for login
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
+    let password = "correct-horse-battery".to_string();
     let user_auth_for_create = UserAuthForCreate {
         username: username.clone(),
         email: email.clone(),
@@ -43,11 +45,16 @@ async fn login_works() -> Result<()> {
 
     // region: call grpc method
 
-    let request = tonic::Request::new(LoginRequest {
+    let mut request = tonic::Request::new(LoginRequest {
         username: "".to_string(),
         email: email.clone(),
         password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
     });
+    request
+        .metadata_mut()
+        .insert("x-device-label", "test device".parse().unwrap());
 
     let login_res = client
         .login(request)
@@ -63,12 +70,60 @@ async fn login_works() -> Result<()> {
     assert!(user_auth_db.last_login != user_auth.last_login);
 
     // check that the session_id exists in the database and matches the user_id
-    let (_, session_user_id) =
-        session::crud::get(model_manager.session_db().clone(), login_res.session_id).await?;
+    let (_, session) = session::crud::get(
+        model_manager.session_db().clone(),
+        login_res.session_id.clone(),
+    )
+    .await?;
     let session_user_uuid =
-        Uuid::parse_str(&session_user_id).map_err(|s| Error::Test(s.to_string()))?;
+        Uuid::parse_str(&session.user_id).map_err(|s| Error::Test(s.to_string()))?;
     assert!(session_user_uuid == user_auth_db.id);
 
+    // check that the session metadata has been stored
+    assert!(session.ip.is_some() && session.user_agent.is_some());
+    assert!(session.device_label == Some("test device".to_string()));
+
+    // check that the session expires after the short lifetime, or earlier if not used
+    let now = chrono::Utc::now().timestamp();
+    assert!(
+        login_res.expires_at <= now + config().SESSION_LIFETIME as i64
+            && login_res.expires_at + 5 >= now + config().SESSION_LIFETIME as i64
+    );
+    let expected_expiration = config().SESSION_LIFETIME.min(config().SESSION_IDLE_TIMEOUT);
+    assert!(
+        login_res.idle_expires_at <= now + expected_expiration as i64
+            && login_res.idle_expires_at + 5 >= now + expected_expiration as i64
+    );
+    let ttl = session::crud::ttl(model_manager.session_db().clone(), login_res.session_id)
+        .await?
+        .unwrap_or_default();
+    assert!(ttl <= expected_expiration && ttl + 5 >= expected_expiration);
+
+    // check that remember_me picks the long lifetime
+    let request = tonic::Request::new(LoginRequest {
+        username: username.clone(),
+        email: "".to_string(),
+        password: password.clone(),
+        remember_me: true,
+        issue_access_token: false,
+    });
+    let login_res_remember = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let (_, session_remember) = session::crud::get(
+        model_manager.session_db().clone(),
+        login_res_remember.session_id,
+    )
+    .await?;
+    let remember_lifetime = session_remember
+        .expires_at
+        .map(|e| (e - session_remember.created_at).num_seconds())
+        .unwrap_or_default();
+    assert!(remember_lifetime == config().SESSION_REMEMBER_ME_LIFETIME as i64);
+    assert!(login_res_remember.expires_at > login_res.expires_at);
+
     // endregion: tests
 
     // clean al databases after running the test
@@ -0,0 +1,135 @@
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use mandos::{
+    config::config,
+    error::{Error, Result},
+    jwt::{self, AccessTokenClaims},
+    mandos_auth::{GetJwksRequest, ListSessionsRequest, LoginRequest},
+    model::{
+        db,
+        rbac::{
+            model_controller::{RoleBmc, UserRoleBmc},
+            Role,
+        },
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the login grpc method issues access tokens
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with a role in the database
+/// 4. Call the login grpc method asking for an access token
+/// 5. Call the get_jwks grpc method
+/// 6. Check that the access token can be verified with the published key
+/// 7. Check the claims of the access token
+/// 8. Check that the session listed by the list_sessions grpc method has the sid of the token
+/// 9. Clean all databases
+#[tokio::test]
+async fn login_access_token_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database for login
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // assign a role to the user
+    let role_id = RoleBmc::create(
+        &model_manager,
+        Role::new("editor".to_string(), "".to_string())?,
+    )
+    .await?;
+    UserRoleBmc::assign(&model_manager, user_auth_db.id, role_id).await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(LoginRequest {
+        username: "".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: true,
+    });
+
+    let login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    let jwks_res = client
+        .get_jwks(tonic::Request::new(GetJwksRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // verify the token offline with the published key of its kid
+    let header = jsonwebtoken::decode_header(&login_res.access_token)
+        .map_err(|e| Error::Test(e.to_string()))?;
+    let jwk = jwks_res
+        .keys
+        .iter()
+        .find(|k| Some(&k.kid) == header.kid.as_ref())
+        .ok_or_else(|| Error::Test("kid not published".to_string()))?;
+    assert_eq!(header.alg, Algorithm::EdDSA);
+    let decoding_key =
+        DecodingKey::from_ed_components(&jwk.x).map_err(|e| Error::Test(e.to_string()))?;
+    let claims = jsonwebtoken::decode::<AccessTokenClaims>(
+        &login_res.access_token,
+        &decoding_key,
+        &Validation::new(Algorithm::EdDSA),
+    )
+    .map_err(|e| Error::Test(e.to_string()))?
+    .claims;
+
+    assert_eq!(claims, jwt::verify_access_token(&login_res.access_token)?);
+    assert_eq!(claims.iss, config().JWT_ISSUER);
+    assert_eq!(claims.sub, user_auth_db.id.to_string());
+    assert_eq!(claims.sid, jwt::session_sid(&login_res.session_id));
+    assert_eq!(claims.roles, vec!["editor".to_string()]);
+    assert_eq!(claims.exp, login_res.access_token_expires_at);
+    assert_eq!(
+        claims.exp - claims.iat,
+        config().JWT_ACCESS_TOKEN_LIFETIME as i64
+    );
+
+    // check that the session of the token can be found by its sid
+    let list_res = client
+        .list_sessions(tonic::Request::new(ListSessionsRequest {
+            session_id: login_res.session_id.clone(),
+            user_id: user_auth_db.id.to_string(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(list_res
+        .sessions
+        .iter()
+        .any(|s| s.sid == claims.sid && s.session_id == login_res.session_id));
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
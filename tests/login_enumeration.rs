@@ -0,0 +1,108 @@
+use std::time::Instant;
+
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::LoginRequest,
+    model::{
+        db,
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    server::error_details,
+    utils, utils_tests,
+};
+
+/// Test that the failed logins can't tell which accounts exist
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a verified user, a blocked user and an unverified user in the database
+/// 4. Call the login grpc method with an unknown user, with a wrong password, with the blocked
+///    user and with the unverified user
+/// 5. Check that all the failures have the same code, message and details
+/// 6. Check that the login of the unknown user verifies a password too
+/// 7. Clean all databases
+#[tokio::test]
+async fn login_enumeration_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database
+    let password = "correct-horse-battery".to_string();
+    let new_user = |username: &str| {
+        UserAuth::new(UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: password.clone(),
+        })
+    };
+    let verified = new_user("verified")?;
+    let blocked = UserAuth {
+        is_blocked: true,
+        ..new_user("blocked")?
+    };
+    let unverified = UserAuth {
+        needs_verify: true,
+        ..new_user("unverified")?
+    };
+    for user_auth in [verified, blocked, unverified] {
+        db::crud::create(model_manager.db().clone(), "users_auth", user_auth).await?;
+    }
+
+    // region: call grpc method
+
+    let mut statuses = Vec::new();
+    for (username, password) in [
+        ("unknown", password.as_str()),
+        ("verified", "wrong-horse-battery"),
+        ("blocked", password.as_str()),
+        ("unverified", password.as_str()),
+    ] {
+        let started = Instant::now();
+        let status = client
+            .login(tonic::Request::new(LoginRequest {
+                username: username.to_string(),
+                email: "".to_string(),
+                password: password.to_string(),
+                remember_me: false,
+                issue_access_token: false,
+            }))
+            .await
+            .err()
+            .ok_or_else(|| Error::Test(format!("{username} logged in")))?;
+        statuses.push((status, started.elapsed()));
+    }
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the failures can't be told apart
+    let (expected, _) = &statuses[0];
+    assert_eq!(expected.code(), tonic::Code::Unauthenticated);
+    let error_info = error_details::error_info_details(expected)
+        .ok_or_else(|| Error::Test("missing error info".to_string()))?;
+    assert_eq!(error_info.reason, "INVALID_CREDENTIALS");
+    for (status, _) in &statuses[1..] {
+        assert_eq!(status.code(), expected.code());
+        assert_eq!(status.message(), expected.message());
+        assert_eq!(status.details(), expected.details());
+    }
+
+    // check that the unknown user took the time of a password verification
+    let hash = utils::hash_password(password.clone())?;
+    let started = Instant::now();
+    utils::verify_password(password, hash)?;
+    let verify_duration = started.elapsed();
+    let (_, unknown_duration) = statuses[0];
+    assert!(unknown_duration >= verify_duration / 2);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
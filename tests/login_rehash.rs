@@ -0,0 +1,93 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use mandos::{
+    config::config,
+    error::{Error, Result},
+    mandos_auth::LoginRequest,
+    model::{
+        db,
+        user_auth::{model_controller::UserAuthBmc, UserAuth, UserAuthForCreate},
+    },
+    utils, utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the login hashes again the passwords hashed with weaker params than the config
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database with a password hashed with weaker params
+/// 4. Call the login grpc method
+/// 5. Check that the password has been hashed again with the params of the config
+/// 6. Check that the new hash still verifies the password
+/// 7. Clean all databases
+#[tokio::test]
+async fn login_rehash_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database with a weak hash
+    let password = "correct-horse-battery".to_string();
+    let weak_params = Params::new(Params::MIN_M_COST * 8, 1, 1, None)?;
+    let weak_hash = Argon2::new(Algorithm::Argon2i, Version::V0x13, weak_params)
+        .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))?
+        .to_string();
+    let user_auth = UserAuth {
+        password: weak_hash.clone(),
+        ..UserAuth::new(UserAuthForCreate {
+            username: "username".to_string(),
+            email: "email@email.com".to_string(),
+            password: password.clone(),
+        })?
+    };
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth).await?;
+    let user_auth_db = UserAuth::from_row(&res)?;
+    assert!(utils::password_needs_rehash(&weak_hash)?);
+
+    // region: call grpc method
+
+    client
+        .login(tonic::Request::new(LoginRequest {
+            username: "username".to_string(),
+            email: "".to_string(),
+            password: password.clone(),
+            remember_me: false,
+            issue_access_token: false,
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the password has been hashed again with the params of the config
+    let user_auth_updated = UserAuthBmc::get(&model_manager, user_auth_db.id).await?;
+    assert!(user_auth_updated.password != weak_hash);
+    assert!(!utils::password_needs_rehash(&user_auth_updated.password)?);
+
+    let parsed_hash = PasswordHash::new(&user_auth_updated.password)?;
+    let params = Params::try_from(&parsed_hash)?;
+    assert_eq!(
+        Algorithm::try_from(parsed_hash.algorithm)?,
+        config().PASSWORD_HASH_ALGORITHM
+    );
+    assert_eq!(params.m_cost(), config().PASSWORD_HASH_PARAMS.m_cost());
+    assert_eq!(params.t_cost(), config().PASSWORD_HASH_PARAMS.t_cost());
+    assert_eq!(params.p_cost(), config().PASSWORD_HASH_PARAMS.p_cost());
+
+    // check that the new hash still verifies the password
+    utils::verify_password(password, user_auth_updated.password)?;
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
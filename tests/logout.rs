@@ -2,7 +2,9 @@ use mandos::{
     error::{Error, Result},
     mandos_auth::LogoutRequest,
     model::{
-        db, session,
+        db,
+        refresh_token::model_controller::RefreshTokenBmc,
+        session::{self, Session},
         user_auth::{UserAuth, UserAuthForCreate},
     },
     utils_tests,
@@ -14,9 +16,9 @@ use sqlx::FromRow;
 /// 1. Setup test environment (Env variables, run server in the backgroung, get client)
 /// 2. Clean all databases
 /// 3. Create a user in the database
-/// 4. Create a session for the user
+/// 4. Create a session for the user with a refresh token
 /// 5. Call the logout grpc method
-/// 6. Check that the session has been deleted
+/// 6. Check that the session and its refresh token have been deleted
 /// 7. Clean all databases
 #[tokio::test]
 async fn logout_works() -> Result<()> {
@@ -29,7 +31,7 @@ async fn logout_works() -> Result<()> {
     // create the user in the database for login
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
+    let password = "correct-horse-battery".to_string();
     let user_auth_for_create = UserAuthForCreate {
         username: username.clone(),
         email: email.clone(),
@@ -43,10 +45,12 @@ async fn logout_works() -> Result<()> {
     // create a session for the user
     let session_id = session::crud::create(
         model_manager.session_db().clone(),
-        user_auth_db.id.to_string().clone(),
+        Session::new(user_auth_db.id.to_string().clone()),
         60,
     )
     .await?;
+    let (refresh_token, _) =
+        RefreshTokenBmc::create_family(&model_manager, user_auth_db.id, session_id.clone()).await?;
 
     // region: call grpc method
 
@@ -70,6 +74,10 @@ async fn logout_works() -> Result<()> {
         .is_ok();
     assert!(!session_still_exists);
 
+    // check that the refresh token has been revoked with the session
+    let family = RefreshTokenBmc::get_family(&model_manager, refresh_token).await?;
+    assert!(family.is_none());
+
     // endregion: tests
 
     // clean al databases after running the test
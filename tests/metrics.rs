@@ -0,0 +1,151 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::LoginRequest,
+    metrics,
+    model::{
+        db, session,
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+
+/// Test that the metrics are served in the Prometheus text format
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Call the login grpc method with a wrong and with the right password
+/// 5. Start the metrics server on a free port and scrape it
+/// 6. Check that the requests, the logins, the sessions and the pools are reported
+/// 7. Check that the deleted sessions are not counted anymore
+/// 8. Check that the other paths are not found
+/// 9. Clean all databases
+#[tokio::test]
+async fn metrics_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database for login
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    db::crud::create(model_manager.db().clone(), "users_auth", user_auth).await?;
+
+    // region: call grpc method
+
+    let login_failure_res = client
+        .login(tonic::Request::new(LoginRequest {
+            username: "".to_string(),
+            email: email.clone(),
+            password: "wrong-password".to_string(),
+            remember_me: false,
+            issue_access_token: false,
+        }))
+        .await;
+
+    let login_res = client
+        .login(tonic::Request::new(LoginRequest {
+            username: "".to_string(),
+            email: email.clone(),
+            password: password.clone(),
+            remember_me: false,
+            issue_access_token: false,
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    assert!(login_failure_res.is_err());
+
+    // start the metrics server
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let metrics_addr = listener.local_addr()?;
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let metrics_task = tokio::spawn(metrics::serve(listener, model_manager.clone(), async {
+        let _ = shutdown_rx.await;
+    }));
+
+    // check that the metrics are reported
+    let (status_line, body) = http_get(&metrics_addr.to_string(), "/metrics").await?;
+    assert!(status_line.contains("200"));
+    assert!(body
+        .lines()
+        .any(|l| l.starts_with("mandos_rpc_requests_total{")
+            && l.contains("method=\"Login\"")
+            && l.contains("code=\"Ok\"")));
+    assert!(body
+        .lines()
+        .any(|l| l.starts_with("mandos_rpc_requests_total{")
+            && l.contains("method=\"Login\"")
+            && l.contains("code=\"Unauthenticated\"")));
+    assert!(body.contains("mandos_rpc_duration_seconds_bucket{"));
+    assert!(body.contains("mandos_login_successes_total{method=\"password\"}"));
+    assert!(body.contains("mandos_login_failures_total{reason=\"invalid_password\"}"));
+    assert!(body.contains("mandos_password_hash_duration_seconds_count{operation=\"verify\"}"));
+    let active_sessions = body
+        .lines()
+        .find_map(|l| l.strip_prefix("mandos_active_sessions "))
+        .and_then(|v| v.parse::<i64>().ok())
+        .ok_or_else(|| Error::Test("active sessions missing".to_string()))?;
+    assert!(active_sessions >= 1);
+    assert!(body.contains("mandos_pool_connections{pool=\"postgres\",state=\"in_use\"}"));
+    assert!(body.contains("mandos_pool_max_connections{pool=\"redis\"}"));
+
+    // check that the deleted sessions are not counted anymore
+    session::crud::delete(model_manager.session_db().clone(), login_res.session_id).await?;
+    let active_sessions_after_delete =
+        session::crud::count_active(model_manager.session_db().clone()).await?;
+    assert!(active_sessions_after_delete as i64 == active_sessions - 1);
+
+    // check that only /metrics is served
+    let (status_line, _) = http_get(&metrics_addr.to_string(), "/other").await?;
+    assert!(status_line.contains("404"));
+
+    // endregion: tests
+
+    let _ = shutdown_tx.send(());
+    metrics_task
+        .await
+        .map_err(|e| Error::Test(e.to_string()))??;
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
+
+/// Sends a GET request and returns the status line and the body of the response
+async fn http_get(addr: &str, path: &str) -> Result<(String, String)> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream
+        .write_all(format!("GET {path} HTTP/1.0\r\nHost: {addr}\r\n\r\n").as_bytes())
+        .await?;
+
+    // HTTP/1.0 closes the connection once the response has been sent
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| Error::Test("invalid http response".to_string()))?;
+    let status_line = head.lines().next().unwrap_or_default().to_string();
+
+    Ok((status_line, body.to_string()))
+}
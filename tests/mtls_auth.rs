@@ -0,0 +1,162 @@
+use std::fs;
+
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{mandos_auth_client::MandosAuthClient, HealthCheckRequest, LoginRequest},
+    server::tls,
+    utils_tests,
+};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+};
+use tonic::transport::{self, Channel, ClientTlsConfig, Identity};
+
+const TLS_ADDR: &str = "0.0.0.0:50052";
+const TLS_CLIENT_ADDR: &str = "https://localhost:50052";
+
+/// Generates a throwaway certificate authority
+fn generate_ca(common_name: &str) -> Result<Certificate> {
+    let mut params = CertificateParams::new(vec![]);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, common_name);
+
+    Certificate::from_params(params).map_err(|e| Error::Test(e.to_string()))
+}
+
+/// Generates a certificate signed by the CA
+/// Returns the certificate and the private key in PEM
+fn generate_cert(
+    ca: &Certificate,
+    common_name: &str,
+    usage: ExtendedKeyUsagePurpose,
+) -> Result<(String, String)> {
+    let mut params = CertificateParams::new(vec!["localhost".to_string()]);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, common_name);
+    params.extended_key_usages = vec![usage];
+
+    let cert = Certificate::from_params(params).map_err(|e| Error::Test(e.to_string()))?;
+    let cert_pem = cert
+        .serialize_pem_with_signer(ca)
+        .map_err(|e| Error::Test(e.to_string()))?;
+
+    Ok((cert_pem, cert.serialize_private_key_pem()))
+}
+
+/// Returns a client of the TLS test server that authenticates with the client certificate only
+async fn get_mtls_client(
+    ca_pem: &str,
+    (cert_pem, key_pem): &(String, String),
+) -> std::result::Result<MandosAuthClient<Channel>, transport::Error> {
+    let tls_config = ClientTlsConfig::new()
+        .ca_certificate(transport::Certificate::from_pem(ca_pem))
+        .identity(Identity::from_pem(cert_pem, key_pem))
+        .domain_name("localhost");
+
+    let channel = Channel::from_static(TLS_CLIENT_ADDR)
+        .tls_config(tls_config)?
+        .connect()
+        .await?;
+
+    Ok(MandosAuthClient::new(channel))
+}
+
+/// Test that with mTLS on the client certificate authenticates the API client
+/// Steps:
+/// 1. Generate a CA, a server certificate and client certificates
+/// 2. Setup test environment (Env variables, run the TLS server in the backgroung)
+/// 3. Call the health_check grpc method with the certificate of a registered client
+/// 4. Check that the client can't call the RPCs outside of its scopes
+/// 5. Check that certificates of unknown clients are rejected
+/// 6. Check that certificates not signed by the client CA can't connect
+#[tokio::test]
+async fn mtls_auth_works() -> Result<()> {
+    // generate the throwaway certificates
+    let ca = generate_ca("Mandos Test CA")?;
+    let ca_pem = ca.serialize_pem().map_err(|e| Error::Test(e.to_string()))?;
+    let server_cert = generate_cert(&ca, "localhost", ExtendedKeyUsagePurpose::ServerAuth)?;
+    let client_cert = generate_cert(&ca, "mtls-service", ExtendedKeyUsagePurpose::ClientAuth)?;
+    let unknown_client_cert =
+        generate_cert(&ca, "unknown-service", ExtendedKeyUsagePurpose::ClientAuth)?;
+    let untrusted_ca = generate_ca("Untrusted CA")?;
+    let untrusted_client_cert = generate_cert(
+        &untrusted_ca,
+        "mtls-service",
+        ExtendedKeyUsagePurpose::ClientAuth,
+    )?;
+
+    // write them where the server config loads them from
+    let dir = std::env::temp_dir().join(format!("mandos-mtls-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).map_err(|e| Error::Test(e.to_string()))?;
+    let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+    for (name, content) in [
+        ("ca.pem", &ca_pem),
+        ("server.pem", &server_cert.0),
+        ("server.key", &server_cert.1),
+    ] {
+        fs::write(path(name), content).map_err(|e| Error::Test(e.to_string()))?;
+    }
+    let tls_config = tls::load_server_tls_config(
+        &path("server.pem"),
+        &path("server.key"),
+        Some(&path("ca.pem")),
+    )?;
+
+    // setup test environment
+    utils_tests::setup_tls_test_environment(TLS_ADDR, tls_config).await?;
+
+    let mut client = get_mtls_client(&ca_pem, &client_cert).await?;
+
+    // region: call grpc method
+
+    let health_check_res = client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the certificate authenticated the client
+    assert!(health_check_res.success);
+
+    // check that the client can't call the rpcs outside of its scopes
+    let request = tonic::Request::new(LoginRequest {
+        username: "username".to_string(),
+        email: "".to_string(),
+        password: "correct-horse-battery".to_string(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let status = client.login(request).await.unwrap_err();
+    assert!(status.code() == tonic::Code::PermissionDenied);
+
+    // check that a certificate of an unknown client is rejected
+    let mut unknown_client = get_mtls_client(&ca_pem, &unknown_client_cert).await?;
+    let status = unknown_client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // check that a certificate not signed by the client CA can't connect
+    let untrusted_res = match get_mtls_client(&ca_pem, &untrusted_client_cert).await {
+        Ok(mut untrusted_client) => untrusted_client
+            .health_check(tonic::Request::new(HealthCheckRequest {}))
+            .await
+            .map(|_| ()),
+        Err(e) => Err(tonic::Status::unavailable(e.to_string())),
+    };
+    assert!(untrusted_res.is_err());
+
+    // endregion: tests
+
+    fs::remove_dir_all(&dir).map_err(|e| Error::Test(e.to_string()))?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use mandos::{
+    error::Result,
+    model::{
+        db,
+        user_auth::{model_controller::UserAuthBmc, UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::{Executor, FromRow};
+use uuid::Uuid;
+
+// the migration that recomputes the normalized usernames and emails, already applied to the test
+// database at startup, it is run again on the users created by the test
+const MIGRATION: &str = include_str!("../migrations/0009_users_auth_canonical_identifiers.sql");
+
+/// Test that the migration recomputes the normalized usernames and emails with the canonical
+/// form of the lookups
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create users with the normalized username lowercased only in ASCII, like Postgres does
+/// 4. Check that the migration fails listing the users with the same canonical username
+/// 5. Run the migration again once the users have been merged
+/// 6. Check that the user can be found by its username, regardless of its case
+/// 7. Clean all databases
+#[tokio::test]
+async fn normalize_identifiers_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, _) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users, with the normalized usernames computed before the canonical form
+    let mut user_ids = Vec::new();
+    for (username, username_normalized) in
+        [("ÀNGEL", "Àngel"), ("ÉMILE", "Émile"), ("émile", "émile")]
+    {
+        let user_auth = UserAuth {
+            username_normalized: username_normalized.to_string(),
+            ..UserAuth::new(UserAuthForCreate {
+                username: username.to_string(),
+                email: format!("{}@email.com", Uuid::new_v4()),
+                password: "correct-horse-battery".to_string(),
+            })?
+        };
+        let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth).await?;
+        user_ids.push(UserAuth::from_row(&res)?.id);
+    }
+
+    // region: run migration
+
+    let conflict_res = model_manager.db().execute(MIGRATION).await;
+    UserAuthBmc::delete(&model_manager, user_ids[1]).await?;
+    model_manager.db().execute(MIGRATION).await?;
+
+    // endregion: run migration
+
+    // region: tests
+
+    // check that the migration fails listing the conflicting users, without changing them
+    let conflict_error = conflict_res.unwrap_err().to_string();
+    assert!(conflict_error.contains(&user_ids[1].to_string()));
+    assert!(conflict_error.contains(&user_ids[2].to_string()));
+    assert!(!conflict_error.contains(&user_ids[0].to_string()));
+
+    // check that the user can be found by its username
+    let user_auth = UserAuthBmc::get_from_username(&model_manager, "àngel".to_string()).await?;
+    assert_eq!(user_auth.id, user_ids[0]);
+    assert_eq!(user_auth.username, "ÀNGEL");
+    let user_auth = UserAuthBmc::get_from_username(&model_manager, "ÉMILE".to_string()).await?;
+    assert_eq!(user_auth.id, user_ids[2]);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::sync::{Arc, Mutex};
+
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::HealthCheckRequest,
+    server::request_trace::REQUEST_ID_METADATA_KEY,
+    utils_tests,
+};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::{
+        trace_service_server::{TraceService, TraceServiceServer},
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    },
+    common::v1::any_value::Value,
+    trace::v1::Span,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// OTLP collector that keeps the exported spans in memory
+#[derive(Clone, Default)]
+struct CollectorStub {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+#[tonic_otlp::async_trait]
+impl TraceService for CollectorStub {
+    async fn export(
+        &self,
+        request: tonic_otlp::Request<ExportTraceServiceRequest>,
+    ) -> core::result::Result<tonic_otlp::Response<ExportTraceServiceResponse>, tonic_otlp::Status>
+    {
+        let spans = request
+            .into_inner()
+            .resource_spans
+            .into_iter()
+            .flat_map(|rs| rs.scope_spans)
+            .flat_map(|ss| ss.spans);
+        self.spans.lock().unwrap().extend(spans);
+
+        Ok(tonic_otlp::Response::new(ExportTraceServiceResponse {
+            partial_success: None,
+        }))
+    }
+}
+
+/// Returns the string value of an attribute of the span
+fn string_attribute(span: &Span, key: &str) -> Option<String> {
+    span.attributes
+        .iter()
+        .find(|a| a.key == key)
+        .and_then(|a| a.value.as_ref()?.value.clone())
+        .and_then(|v| match v {
+            Value::StringValue(s) => Some(s),
+            _ => None,
+        })
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Test that the spans are exported to the OTLP collector and that the requests are correlated
+/// Steps:
+/// 1. Start the OTLP collector stub and enable the export
+/// 2. Setup test environment (Env variables, run server in the backgroung on a free port)
+/// 3. Call the health_check grpc method with a traceparent and a request id
+/// 4. Check that the request id is echoed back, or generated when missing
+/// 5. Export the spans
+/// 6. Check that the span of the request continues the trace of the caller
+/// 7. Check that the queries of the databases are child spans of the request
+#[tokio::test(flavor = "multi_thread")]
+async fn otlp_tracing_works() -> Result<()> {
+    // start the collector stub
+    let collector = CollectorStub::default();
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let collector_addr = listener.local_addr()?;
+    tokio::spawn(
+        tonic_otlp::transport::Server::builder()
+            .add_service(TraceServiceServer::new(collector.clone()))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    // the config is loaded by the test server, after the endpoint has been set
+    std::env::set_var("OTLP_ENDPOINT", format!("http://{collector_addr}"));
+
+    // setup test environment, on its own server to get its spans only
+    let (_, server_handle) = utils_tests::start_test_server("127.0.0.1:0", None).await?;
+    mandos::tracing::initialize()?;
+    let mut client = utils_tests::get_grpc_client_at(
+        format!("http://{}", server_handle.local_addr()),
+        utils_tests::TEST_API_CLIENT,
+        utils_tests::TEST_API_CLIENT_SECRET,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let mut request = tonic::Request::new(HealthCheckRequest {});
+    request.metadata_mut().insert(
+        "traceparent",
+        format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01")
+            .parse()
+            .unwrap(),
+    );
+    request
+        .metadata_mut()
+        .insert(REQUEST_ID_METADATA_KEY, "test-request-id".parse().unwrap());
+    let health_check_res = client
+        .health_check(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let generated_id_res = client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the request id is echoed back
+    assert!(
+        health_check_res
+            .metadata()
+            .get(REQUEST_ID_METADATA_KEY)
+            .and_then(|v| v.to_str().ok())
+            == Some("test-request-id")
+    );
+
+    // check that a request id is generated when missing
+    let generated_id = generated_id_res
+        .metadata()
+        .get(REQUEST_ID_METADATA_KEY)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| Error::Test("request id missing".to_string()))?;
+    assert!(uuid::Uuid::parse_str(generated_id).is_ok());
+
+    // export the spans still buffered
+    tokio::task::spawn_blocking(mandos::tracing::shutdown)
+        .await
+        .map_err(|e| Error::Test(e.to_string()))?;
+    let spans = collector.spans.lock().unwrap().clone();
+
+    // check that the span of the request continues the trace of the caller
+    let request_span = spans
+        .iter()
+        .find(|s| string_attribute(s, "request_id").as_deref() == Some("test-request-id"))
+        .ok_or_else(|| Error::Test("request span missing".to_string()))?;
+    assert!(request_span.name == "mandos_auth.MandosAuth/HealthCheck");
+    assert!(request_span.trace_id == from_hex(TRACE_ID));
+    assert!(request_span.parent_span_id == from_hex(PARENT_SPAN_ID));
+    assert!(string_attribute(request_span, "rpc.method").as_deref() == Some("HealthCheck"));
+
+    // check that the queries of both databases are children of the request span
+    for db_system in ["postgresql", "redis"] {
+        assert!(spans
+            .iter()
+            .any(|s| s.parent_span_id == request_span.span_id
+                && s.trace_id == request_span.trace_id
+                && string_attribute(s, "db.system").as_deref() == Some(db_system)));
+    }
+
+    // endregion: tests
+
+    server_handle.shutdown().await?;
+
+    Ok(())
+}
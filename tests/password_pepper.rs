@@ -0,0 +1,117 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString},
+    Argon2, ParamsBuilder, PasswordVerifier,
+};
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::LoginRequest,
+    model::{
+        db,
+        user_auth::{model_controller::UserAuthBmc, UserAuth, UserAuthForCreate},
+    },
+    utils::{self, pepper},
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the passwords are peppered and that the hashes of the older peppers are upgraded on
+/// login
+/// Steps:
+/// 1. Set the peppers (version 2 in the env variable, version 1 in the key file)
+/// 2. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 3. Clean all databases
+/// 4. Check that the new hashes are tagged with the current pepper and need it to be verified
+/// 5. Create a user with a hash of the old pepper and a user with a hash without pepper
+/// 6. Call the login grpc method for both users
+/// 7. Check that their passwords have been hashed again with the current pepper
+/// 8. Clean all databases
+#[tokio::test]
+async fn password_pepper_works() -> Result<()> {
+    // the config is loaded by the test server, after the peppers have been set
+    std::env::set_var(
+        "PASSWORD_PEPPERS",
+        "2=3q2+7wABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhs=",
+    );
+    std::env::set_var("PASSWORD_PEPPERS_FILE", "./tests/keys/password_peppers");
+
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let password = "correct-horse-battery".to_string();
+
+    // check that the new hashes are peppered with the current pepper
+    let new_hash = utils::hash_password(password.clone())?;
+    let parsed_hash = PasswordHash::new(&new_hash)?;
+    assert_eq!(pepper::current().map(|p| p.version), Some(2));
+    assert_eq!(pepper::hash_version(&parsed_hash)?, Some(2));
+    utils::verify_password(password.clone(), new_hash.clone())?;
+    assert!(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_err());
+
+    // create a user with a hash of the old pepper and a user with a hash without pepper
+    let old_pepper = pepper::get(1)?;
+    let old_params = ParamsBuilder::new().keyid(old_pepper.keyid()?).build()?;
+    let old_hash = Argon2::new(Default::default(), Default::default(), old_params)
+        .hash_password(
+            &old_pepper.apply(password.as_bytes()),
+            &SaltString::generate(&mut OsRng),
+        )?
+        .to_string();
+    let plain_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))?
+        .to_string();
+
+    let mut user_ids = Vec::new();
+    for (username, password_hash) in [("old_pepper", &old_hash), ("no_pepper", &plain_hash)] {
+        let user_auth = UserAuth {
+            password: password_hash.clone(),
+            ..UserAuth::new(UserAuthForCreate {
+                username: username.to_string(),
+                email: format!("{username}@email.com"),
+                password: password.clone(),
+            })?
+        };
+        let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth).await?;
+        user_ids.push(UserAuth::from_row(&res)?.id);
+        assert!(utils::password_needs_rehash(password_hash)?);
+    }
+
+    // region: call grpc method
+
+    for username in ["old_pepper", "no_pepper"] {
+        client
+            .login(tonic::Request::new(LoginRequest {
+                username: username.to_string(),
+                email: "".to_string(),
+                password: password.clone(),
+                remember_me: false,
+                issue_access_token: false,
+            }))
+            .await
+            .map_err(|s| Error::Test(s.to_string()))?;
+    }
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the passwords have been hashed again with the current pepper
+    for user_id in user_ids {
+        let user_auth = UserAuthBmc::get(&model_manager, user_id).await?;
+        let parsed_hash = PasswordHash::new(&user_auth.password)?;
+        assert_eq!(pepper::hash_version(&parsed_hash)?, Some(2));
+        assert!(!utils::password_needs_rehash(&user_auth.password)?);
+        utils::verify_password(password.clone(), user_auth.password)?;
+    }
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use mandos::{
+    config::config,
+    error::{Error, Result},
+    jwt,
+    mandos_auth::{LoginRequest, RefreshSessionRequest},
+    model::{
+        db,
+        refresh_token::model_controller::RefreshTokenBmc,
+        session,
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the refresh_session grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Call the login grpc method to get a refresh token
+/// 5. Call the refresh_session grpc method
+/// 6. Check that a new refresh token and access token have been issued for the same session
+/// 7. Check that the new refresh token can be rotated again
+/// 8. Check that the refresh tokens are revoked with their session
+/// 9. Clean all databases
+#[tokio::test]
+async fn refresh_session_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database for login
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    let request = tonic::Request::new(LoginRequest {
+        username: "".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: login_res.refresh_token.clone(),
+        issue_access_token: true,
+    });
+
+    let refresh_session_res = client
+        .refresh_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the login issued a refresh token for the whole lifetime of the family
+    assert!(!login_res.refresh_token.is_empty());
+    let now = chrono::Utc::now().timestamp();
+    let expected_expiration = now + config().REFRESH_TOKEN_LIFETIME as i64;
+    assert!(
+        login_res.refresh_token_expires_at <= expected_expiration
+            && login_res.refresh_token_expires_at + 5 >= expected_expiration
+    );
+
+    // check that the refresh token has been rotated, without extending the family
+    assert!(!refresh_session_res.refresh_token.is_empty());
+    assert!(refresh_session_res.refresh_token != login_res.refresh_token);
+    assert!(refresh_session_res.refresh_token_expires_at == login_res.refresh_token_expires_at);
+
+    // check that the credentials are for the session created at login
+    assert!(refresh_session_res.session_id == login_res.session_id);
+    // the refresh extends the session by its idle timeout, never past its absolute expiration
+    assert!(refresh_session_res.expires_at == login_res.expires_at);
+    assert!(refresh_session_res.idle_expires_at >= login_res.idle_expires_at);
+    let claims = jwt::verify_access_token(&refresh_session_res.access_token)?;
+    assert!(claims.sub == user_auth_db.id.to_string());
+    assert!(claims.exp == refresh_session_res.access_token_expires_at);
+
+    // check that the new refresh token can be rotated again
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: refresh_session_res.refresh_token.clone(),
+        issue_access_token: false,
+    });
+    let refresh_session_res_2 = client
+        .refresh_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(refresh_session_res_2.refresh_token != refresh_session_res.refresh_token);
+    assert!(refresh_session_res_2.access_token.is_empty());
+
+    // check that the refresh tokens can't be used once their session is gone
+    session::crud::delete(
+        model_manager.session_db().clone(),
+        login_res.session_id.clone(),
+    )
+    .await?;
+    let family =
+        RefreshTokenBmc::get_family(&model_manager, refresh_session_res_2.refresh_token.clone())
+            .await?;
+    assert!(family.is_none());
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: refresh_session_res_2.refresh_token.clone(),
+        issue_access_token: false,
+    });
+    let status = client.refresh_session(request).await.unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // check that an invalid refresh token is rejected
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: "invalid".to_string(),
+        issue_access_token: false,
+    });
+    let status = client.refresh_session(request).await.unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{LoginRequest, RefreshSessionRequest},
+    model::{
+        audit_log::{model_controller::AuditLogBmc, AuditEvent},
+        db, session,
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the refresh_session grpc method revokes the token family when a token is reused
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Call the login grpc method to get a refresh token
+/// 5. Call the refresh_session grpc method to rotate the refresh token
+/// 6. Call the refresh_session grpc method again with the rotated refresh token
+/// 7. Check that the reuse is rejected and the session is revoked
+/// 8. Check that the last refresh token of the family has been revoked too
+/// 9. Check that the reuse is in the audit log
+/// 10. Clean all databases
+#[tokio::test]
+async fn refresh_session_reuse_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database for login
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    let request = tonic::Request::new(LoginRequest {
+        username: "".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // a second session of the same user, not part of the family
+    let request = tonic::Request::new(LoginRequest {
+        username: "".to_string(),
+        email: email.clone(),
+        password: password.clone(),
+        remember_me: false,
+        issue_access_token: false,
+    });
+    let other_login_res = client
+        .login(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: login_res.refresh_token.clone(),
+        issue_access_token: false,
+    });
+    let refresh_session_res = client
+        .refresh_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: login_res.refresh_token.clone(),
+        issue_access_token: false,
+    });
+
+    let status = client.refresh_session(request).await.unwrap_err();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the reused token is rejected
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // check that the session of the family has been revoked, but not the other one
+    let sessions = session::crud::list(
+        model_manager.session_db().clone(),
+        user_auth_db.id.to_string(),
+    )
+    .await?;
+    assert!(sessions.len() == 1);
+    assert!(sessions[0].0 == other_login_res.session_id);
+
+    // check that the token the family was rotated into has been revoked too
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: refresh_session_res.refresh_token.clone(),
+        issue_access_token: false,
+    });
+    let status = client.refresh_session(request).await.unwrap_err();
+    assert!(status.code() == tonic::Code::Unauthenticated);
+
+    // check that the refresh token of the other session still works
+    let request = tonic::Request::new(RefreshSessionRequest {
+        refresh_token: other_login_res.refresh_token.clone(),
+        issue_access_token: false,
+    });
+    client
+        .refresh_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // check that the reuse is in the audit log
+    let audit_logs = AuditLogBmc::get_all_for_user(&model_manager, user_auth_db.id).await?;
+    assert!(audit_logs.len() == 1);
+    assert!(audit_logs[0].event == AuditEvent::RefreshTokenReused.as_ref());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RegenerateRecoveryCodesRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+        user_mfa::{self, model_controller::UserMfaBmc, UserMfa},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the regenerate_recovery_codes grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with MFA enabled, recovery codes and a session in the database
+/// 4. Check that a wrong code does not replace the recovery codes
+/// 5. Call the regenerate_recovery_codes grpc method with a valid code
+/// 6. Check that the old recovery codes have been replaced by the new ones
+/// 7. Clean all databases
+#[tokio::test]
+async fn regenerate_recovery_codes_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // enable MFA for the user
+    let secret = user_mfa::generate_totp_secret();
+    let user_mfa = UserMfa {
+        totp_enabled: true,
+        ..UserMfa::new(user_auth_db.id, &secret)?
+    };
+    UserMfaBmc::create(&model_manager, user_mfa).await?;
+    let totp = user_mfa::new_totp(secret, user_auth_db.email.clone())?;
+    let old_recovery_codes =
+        UserMfaBmc::replace_recovery_codes(&model_manager, user_auth_db.id).await?;
+
+    // create the session
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // check that a wrong code does not replace the recovery codes
+    let request = tonic::Request::new(RegenerateRecoveryCodesRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        code: "000000".to_string(),
+    });
+    assert!(client.regenerate_recovery_codes(request).await.is_err());
+    let recovery_codes = UserMfaBmc::get_recovery_codes(&model_manager, user_auth_db.id).await?;
+    assert!(recovery_codes
+        .iter()
+        .any(|c| c.matches(&old_recovery_codes[0])));
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RegenerateRecoveryCodesRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+        code: totp
+            .generate_current()
+            .map_err(|e| Error::Test(e.to_string()))?,
+    });
+
+    let regenerate_res = client
+        .regenerate_recovery_codes(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the old recovery codes have been replaced by the new ones
+    assert!(regenerate_res.recovery_codes.len() == old_recovery_codes.len());
+    assert!(
+        !UserMfaBmc::use_recovery_code(
+            &model_manager,
+            user_auth_db.id,
+            old_recovery_codes[0].clone()
+        )
+        .await?
+    );
+    assert!(
+        UserMfaBmc::use_recovery_code(
+            &model_manager,
+            user_auth_db.id,
+            regenerate_res.recovery_codes[0].clone()
+        )
+        .await?
+    );
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
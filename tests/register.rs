@@ -15,7 +15,8 @@ use uuid::Uuid;
 /// 4. Call the register grpc method
 /// 5. Check that the user has been created
 /// 6. Check that the user password is correct
-/// 7. Clean all databases
+/// 7. Check that the user needs verification and the verification email has been sent
+/// 8. Clean all databases
 #[tokio::test]
 async fn register_works() -> Result<()> {
     // setup test environment
@@ -26,7 +27,7 @@ async fn register_works() -> Result<()> {
 
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
+    let password = "correct-horse-battery".to_string();
 
     // region: call grpc method
 
@@ -61,6 +62,10 @@ async fn register_works() -> Result<()> {
     // check that the passwords match
     utils::verify_password(password, user_auth.password)?;
 
+    // check that the user needs verification and that the token has been sent
+    assert!(user_auth.needs_verify);
+    assert!(utils_tests::last_mail_token(&email).is_some());
+
     // endregion: tests
 
     // clean all databases after running the test
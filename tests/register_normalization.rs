@@ -0,0 +1,94 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RegisterRequest,
+    model::user_auth::model_controller::UserAuthBmc,
+    server::error_details,
+    utils_tests,
+};
+
+/// Test that the register grpc method normalizes and validates usernames and emails
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Call the register grpc method
+/// 4. Call the register grpc method with the same username and email in a different form
+/// 5. Call the register grpc method with an invalid email
+/// 6. Check that the user is stored NFKC normalized and can be found regardless of case
+/// 7. Check that the second registration has been rejected
+/// 8. Check that the invalid email has been rejected with the error details
+/// 9. Clean all databases
+#[tokio::test]
+async fn register_normalization_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // fullwidth letters are NFKC normalized to their ascii form
+    let username = " Ｂob.Smith ".to_string();
+    let email = "Bob@Example.com".to_string();
+    let password = "correct-horse-battery".to_string();
+
+    // region: call grpc method
+
+    client
+        .register(tonic::Request::new(RegisterRequest {
+            username: username.clone(),
+            email: email.clone(),
+            password: password.clone(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let duplicate_res = client
+        .register(tonic::Request::new(RegisterRequest {
+            username: "bob.smith".to_string(),
+            email: "BOB@example.COM".to_string(),
+            password: password.clone(),
+        }))
+        .await;
+
+    let invalid_email_status = match client
+        .register(tonic::Request::new(RegisterRequest {
+            username: "alice".to_string(),
+            email: "alice@@example.com".to_string(),
+            password: password.clone(),
+        }))
+        .await
+    {
+        Ok(_) => return Err(Error::Test("invalid email accepted".to_string())),
+        Err(s) => s,
+    };
+
+    // endregion: call grpc method
+
+    let user_from_username =
+        UserAuthBmc::get_from_username(&model_manager, "BOB.SMITH".to_string()).await?;
+    let user_from_email =
+        UserAuthBmc::get_from_email(&model_manager, "bob@example.com".to_string()).await?;
+    let users = UserAuthBmc::get_all(&model_manager).await?;
+
+    // region: tests
+
+    assert_eq!(user_from_username.username, "Bob.Smith");
+    assert_eq!(user_from_username.email, email);
+    assert_eq!(user_from_username.id, user_from_email.id);
+
+    assert!(duplicate_res.is_err());
+    assert_eq!(users.len(), 1);
+
+    assert_eq!(invalid_email_status.code(), tonic::Code::InvalidArgument);
+    let details = error_details::bad_request_details(&invalid_email_status)
+        .ok_or_else(|| Error::Test("missing error details".to_string()))?;
+    assert_eq!(details.field_violations.len(), 1);
+    assert_eq!(details.field_violations[0].field, "email");
+    assert_eq!(details.field_violations[0].reason, "INVALID_EMAIL");
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RegisterRequest,
+    server::error_details,
+    utils_tests,
+};
+
+/// Test that the register grpc method rejects passwords that do not respect the password policy
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Call the register grpc method with a password that is too short and contains the username
+/// 4. Check that the request is rejected with the violated rules in the error details
+/// 5. Check that the user has not been created
+/// 6. Clean all databases
+#[tokio::test]
+async fn register_password_policy_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let username = "bob".to_string();
+    let email = "email@email.com".to_string();
+    let password = "bob123".to_string();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RegisterRequest {
+        username: username.clone(),
+        email: email.clone(),
+        password: password.clone(),
+    });
+
+    let status = match client.register(request).await {
+        Ok(_) => return Err(Error::Test("weak password accepted".to_string())),
+        Err(s) => s,
+    };
+
+    // endregion: call grpc method
+
+    let users: i64 = sqlx::query_scalar("select count(*) from users_auth where username = $1")
+        .bind(username.clone())
+        .fetch_one(model_manager.db())
+        .await?;
+
+    // region: tests
+
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let details = error_details::bad_request_details(&status)
+        .ok_or_else(|| Error::Test("missing error details".to_string()))?;
+    let mut reasons: Vec<String> = details
+        .field_violations
+        .iter()
+        .map(|v| v.reason.clone())
+        .collect();
+    reasons.sort();
+    assert_eq!(reasons, vec!["CONTAINS_USERNAME", "TOO_SHORT"]);
+    assert!(details
+        .field_violations
+        .iter()
+        .all(|v| v.field == "password" && !v.description.is_empty()));
+
+    assert_eq!(users, 0);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
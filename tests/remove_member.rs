@@ -0,0 +1,115 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RemoveMemberRequest,
+    model::{
+        db,
+        organization::{
+            model_controller::OrganizationBmc, Organization, OrganizationMember, OrganizationRole,
+        },
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the remove_member grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create an owner and a member of an organization in the database
+/// 4. Create a session for the owner
+/// 5. Call the remove_member grpc method to remove the member
+/// 6. Call the remove_member grpc method to remove the owner
+/// 7. Check that the member has been removed
+/// 8. Check that the last owner can't be removed
+/// 9. Clean all databases
+#[tokio::test]
+async fn remove_member_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database
+    let mut users = Vec::new();
+    for username in ["owner", "member"] {
+        let user_auth_for_create = UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: "correct-horse-battery".to_string(),
+        };
+        let user_auth = UserAuth::new(user_auth_for_create)?;
+        let res =
+            db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+        users.push(UserAuth::from_row(&res)?);
+    }
+    let owner = &users[0];
+    let member = &users[1];
+
+    // create the organization
+    let organization_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Acme".to_string())?,
+        owner.id,
+    )
+    .await?;
+    OrganizationBmc::add_member(
+        &model_manager,
+        OrganizationMember::new(organization_id, member.id, OrganizationRole::Member),
+    )
+    .await?;
+
+    // create a session for the owner
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(owner.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RemoveMemberRequest {
+        session_id: session_id.clone(),
+        user_id: owner.id.to_string(),
+        organization_id: organization_id.to_string(),
+        member_id: member.id.to_string(),
+    });
+
+    client
+        .remove_member(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(RemoveMemberRequest {
+        session_id: session_id.clone(),
+        user_id: owner.id.to_string(),
+        organization_id: organization_id.to_string(),
+        member_id: owner.id.to_string(),
+    });
+
+    let last_owner_res = client.remove_member(request).await;
+
+    // endregion: call grpc method
+
+    let members = OrganizationBmc::get_members(&model_manager, organization_id).await?;
+
+    // region: tests
+
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].user_id, owner.id);
+
+    let status = last_owner_res
+        .err()
+        .ok_or_else(|| Error::Test("last owner removed".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RequestPasswordResetRequest,
+    model::{
+        db,
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+
+/// Test that the request_password_reset grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Call the request_password_reset grpc method
+/// 5. Check that the reset token has been sent by email
+/// 6. Check that the response is the same for a user that does not exist
+/// 7. Clean all databases
+#[tokio::test]
+async fn request_password_reset_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let username = "username".to_string();
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: username.clone(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RequestPasswordResetRequest {
+        username: username.clone(),
+        email: "".to_string(),
+    });
+
+    let res = client
+        .request_password_reset(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the reset token has been sent
+    assert!(res.success);
+    assert!(utils_tests::wait_mail_token(&email, 1).await.is_some());
+
+    // check that the response is the same for a user that does not exist
+    let request = tonic::Request::new(RequestPasswordResetRequest {
+        username: "".to_string(),
+        email: "unknown@email.com".to_string(),
+    });
+    let res_unknown = client
+        .request_password_reset(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(res_unknown == res);
+    assert!(utils_tests::last_mail_token("unknown@email.com").is_none());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
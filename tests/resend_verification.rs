@@ -0,0 +1,81 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{RegisterRequest, ResendVerificationRequest},
+    utils_tests,
+};
+
+/// Test that the resend_verification grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Register a user with the register grpc method
+/// 4. Call the resend_verification grpc method
+/// 5. Check that a new verification token has been sent
+/// 6. Check that the response is the same for an email that is not registered
+/// 7. Clean all databases
+#[tokio::test]
+async fn resend_verification_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let username = "username".to_string();
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+
+    // register the user
+    let request = tonic::Request::new(RegisterRequest {
+        username: username.clone(),
+        email: email.clone(),
+        password: password.clone(),
+    });
+    client
+        .register(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let first_token = utils_tests::last_mail_token(&email);
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ResendVerificationRequest {
+        email: email.clone(),
+    });
+
+    let res = client
+        .resend_verification(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that a new token has been sent
+    assert!(res.success);
+    let second_token = utils_tests::wait_mail_token(&email, 2).await;
+    assert!(second_token.is_some() && second_token != first_token);
+
+    // check that the response is the same for an email that is not registered
+    let unknown_email = "unknown@email.com".to_string();
+    let request = tonic::Request::new(ResendVerificationRequest {
+        email: unknown_email.clone(),
+    });
+    let res = client
+        .resend_verification(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(res.success);
+    assert!(utils_tests::last_mail_token(&unknown_email).is_none());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
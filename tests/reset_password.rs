@@ -0,0 +1,110 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{RequestPasswordResetRequest, ResetPasswordRequest},
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils, utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the reset_password grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Create a session for the user
+/// 5. Request a password reset and get the token from the mailer outbox
+/// 6. Call the reset_password grpc method
+/// 7. Check that the new password matches the one in the database
+/// 8. Check that the session of the user has been revoked
+/// 9. Check that the token cannot be used twice
+/// 10. Clean all databases
+#[tokio::test]
+async fn reset_password_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let username = "username".to_string();
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+    let new_password = "new-correct-horse-battery".to_string();
+    let user_auth_for_create = UserAuthForCreate {
+        username: username.clone(),
+        email: email.clone(),
+        password: password.clone(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create a session for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // request the reset token
+    let request = tonic::Request::new(RequestPasswordResetRequest {
+        username: "".to_string(),
+        email: email.clone(),
+    });
+    client
+        .request_password_reset(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+    let token = utils_tests::wait_mail_token(&email, 1)
+        .await
+        .ok_or(Error::Test("password reset email not sent".to_string()))?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(ResetPasswordRequest {
+        token: token.clone(),
+        new_password: new_password.clone(),
+    });
+
+    client
+        .reset_password(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // get the updated user from the database
+    let res_upd =
+        db::crud::get_one_by_id(model_manager.db().clone(), "users_auth", user_auth_db.id).await?;
+    let user_auth_db_updated = UserAuth::from_row(&res_upd)?;
+
+    // region: tests
+
+    // check that the new password matches the one in the database
+    utils::verify_password(new_password.clone(), user_auth_db_updated.password)?;
+
+    // check that the session has been revoked
+    let session_res = session::crud::get(model_manager.session_db().clone(), session_id).await;
+    assert!(session_res.is_err());
+
+    // check that the token cannot be used twice
+    let request = tonic::Request::new(ResetPasswordRequest {
+        token,
+        new_password,
+    });
+    assert!(client.reset_password(request).await.is_err());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RevokeAllSessionsRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the revoke_all_sessions grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Create three sessions for the user
+/// 5. Call the revoke_all_sessions grpc method keeping the current session
+/// 6. Check that only the current session is left
+/// 7. Call the revoke_all_sessions grpc method without keeping the current session
+/// 8. Check that no session is left
+/// 9. Clean all databases
+#[tokio::test]
+async fn revoke_all_sessions_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create three sessions for the user
+    let mut session_ids = Vec::new();
+    for _ in 0..3 {
+        let session_id = session::crud::create(
+            model_manager.session_db().clone(),
+            Session::new(user_auth_db.id.to_string()),
+            60,
+        )
+        .await?;
+        session_ids.push(session_id);
+    }
+    let session_id = session_ids[0].clone();
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RevokeAllSessionsRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        keep_current: true,
+    });
+
+    let revoke_res = client
+        .revoke_all_sessions(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that only the current session is left
+    assert!(revoke_res.revoked == 2);
+    let sessions_left = session::crud::list(
+        model_manager.session_db().clone(),
+        user_auth_db.id.to_string(),
+    )
+    .await?;
+    assert!(sessions_left.len() == 1 && sessions_left[0].0 == session_id);
+
+    // revoke also the current session
+    let request = tonic::Request::new(RevokeAllSessionsRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        keep_current: false,
+    });
+    let revoke_res = client
+        .revoke_all_sessions(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(revoke_res.revoked == 1);
+    assert!(
+        session::crud::get(model_manager.session_db().clone(), session_id)
+            .await
+            .is_err()
+    );
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RevokePermissionRequest,
+    model::rbac::{
+        model_controller::{PermissionBmc, RoleBmc},
+        Role,
+    },
+    utils_tests,
+};
+
+/// Test that the revoke_permission grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a role with two permissions in the database
+/// 4. Call the revoke_permission grpc method
+/// 5. Check that only the revoked permission has been removed from the role
+/// 6. Clean all databases
+#[tokio::test]
+async fn revoke_permission_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the role with its permissions in the database
+    let role = "editor".to_string();
+    let role_id = RoleBmc::create(&model_manager, Role::new(role.clone(), "".to_string())?).await?;
+    for permission in ["documents:read", "documents:write"] {
+        let permission_id =
+            PermissionBmc::get_or_create(&model_manager, permission.to_string()).await?;
+        RoleBmc::grant_permission(&model_manager, role_id, permission_id).await?;
+    }
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RevokePermissionRequest {
+        role: role.clone(),
+        permission: "documents:write".to_string(),
+    });
+
+    client
+        .revoke_permission(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    let permissions = RoleBmc::get_permissions(&model_manager, role_id).await?;
+
+    // region: tests
+
+    assert_eq!(permissions.len(), 1);
+    assert_eq!(permissions[0].name, "documents:read");
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::RevokeSessionRequest,
+    model::{
+        db,
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Test that the revoke_session grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user in the database
+/// 4. Create two sessions for the user and one for another user
+/// 5. Call the revoke_session grpc method
+/// 6. Check that only the revoked session has been deleted
+/// 7. Check that the session of another user cannot be revoked
+/// 8. Clean all databases
+#[tokio::test]
+async fn revoke_session_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the sessions
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+    let other_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()),
+        60,
+    )
+    .await?;
+    let other_user_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(Uuid::new_v4().to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(RevokeSessionRequest {
+        session_id: session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+        revoke_session_id: other_session_id.clone(),
+    });
+
+    client
+        .revoke_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that only the revoked session has been deleted
+    assert!(
+        session::crud::get(model_manager.session_db().clone(), other_session_id)
+            .await
+            .is_err()
+    );
+    assert!(
+        session::crud::get(model_manager.session_db().clone(), session_id.clone())
+            .await
+            .is_ok()
+    );
+
+    // check that the session of another user cannot be revoked
+    let request = tonic::Request::new(RevokeSessionRequest {
+        session_id,
+        user_id: user_auth_db.id.to_string(),
+        revoke_session_id: other_user_session_id.clone(),
+    });
+    assert!(client.revoke_session(request).await.is_err());
+    assert!(
+        session::crud::get(model_manager.session_db().clone(), other_user_session_id)
+            .await
+            .is_ok()
+    );
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
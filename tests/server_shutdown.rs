@@ -0,0 +1,129 @@
+use std::{sync::Arc, time::Duration};
+
+use mandos::{
+    error::{Error, Result},
+    mailer::{Mail, Mailer},
+    mandos_auth::{HealthCheckRequest, RegisterRequest},
+    utils_tests,
+};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Mailer that holds every mail until it is released, so that the request sending it stays in
+/// flight for as long as the test needs
+struct BlockingMailer {
+    sending_tx: mpsc::UnboundedSender<()>,
+    release: Arc<Semaphore>,
+}
+
+#[tonic::async_trait]
+impl Mailer for BlockingMailer {
+    async fn send(&self, _: Mail) -> Result<()> {
+        let _ = self.sending_tx.send(());
+        self.release
+            .acquire()
+            .await
+            .map_err(|e| Error::Test(e.to_string()))?
+            .forget();
+
+        Ok(())
+    }
+}
+
+/// Test that the server started in the background can be shut down gracefully
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung on a free port, with
+///    a mailer that blocks until released)
+/// 2. Clean all databases
+/// 3. Call the register grpc method, which stays in flight while its mail is blocked
+/// 4. Shut down the server and check that it stops accepting new connections
+/// 5. Check that the shutdown waits for the in-flight register
+/// 6. Release the mail and check that the register and the shutdown complete
+/// 7. Clean all databases
+#[tokio::test]
+async fn server_shutdown_works() -> Result<()> {
+    // setup test environment, port 0 picks a free port
+    let (sending_tx, mut sending_rx) = mpsc::unbounded_channel();
+    let release = Arc::new(Semaphore::new(0));
+    let mailer = BlockingMailer {
+        sending_tx,
+        release: release.clone(),
+    };
+    let (model_manager, server_handle) =
+        utils_tests::start_test_server_with_mailer("127.0.0.1:0", None, Arc::new(mailer)).await?;
+    let client_addr = format!("http://{}", server_handle.local_addr());
+    assert!(server_handle.local_addr().port() != 0);
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let mut client = utils_tests::get_grpc_client_at(
+        client_addr.clone(),
+        utils_tests::TEST_API_CLIENT,
+        utils_tests::TEST_API_CLIENT_SECRET,
+    )
+    .await?;
+    client
+        .health_check(tonic::Request::new(HealthCheckRequest {}))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // region: call grpc method
+
+    // the register stays in flight until its verification mail is released
+    let register = tokio::spawn(async move {
+        let request = tonic::Request::new(RegisterRequest {
+            username: "username".to_string(),
+            email: "email@email.com".to_string(),
+            password: "correct-horse-battery".to_string(),
+        });
+        client.register(request).await
+    });
+    sending_rx
+        .recv()
+        .await
+        .ok_or_else(|| Error::Test("the mail has not been sent".to_string()))?;
+
+    let shutdown = tokio::spawn(server_handle.shutdown());
+
+    // endregion: call grpc method
+
+    // region: tests
+
+    // check that the server stops accepting connections while the register is in flight
+    let mut accepting = true;
+    for _ in 0..200 {
+        accepting = utils_tests::get_grpc_client_at(
+            client_addr.clone(),
+            utils_tests::TEST_API_CLIENT,
+            utils_tests::TEST_API_CLIENT_SECRET,
+        )
+        .await
+        .is_ok();
+        if !accepting {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(!accepting);
+
+    // check that the shutdown is waiting for the in-flight register
+    assert!(!shutdown.is_finished());
+    assert!(!register.is_finished());
+
+    // check that the register and the shutdown complete once the mail is released
+    release.add_permits(1);
+    let register_res = register
+        .await
+        .map_err(|e| Error::Test(e.to_string()))?
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    assert!(register_res.success);
+    shutdown.await.map_err(|e| Error::Test(e.to_string()))??;
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
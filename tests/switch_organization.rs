@@ -0,0 +1,140 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{SwitchOrganizationRequest, ValidateRequest},
+    model::{
+        db,
+        organization::{model_controller::OrganizationBmc, Organization},
+        session::{self, Session},
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the switch_organization grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create two users in the database, each owning an organization
+/// 4. Create a session for the user
+/// 5. Call the switch_organization grpc method with the organization of the user
+/// 6. Call the switch_organization grpc method with the organization of the other user
+/// 7. Check that validate_session returns the active organization
+/// 8. Check that the user can't switch to an organization it is not a member of
+/// 9. Check that the active organization is unset once the user is no longer a member
+/// 10. Clean all databases
+#[tokio::test]
+async fn switch_organization_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the users in the database
+    let mut users = Vec::new();
+    for username in ["username", "other"] {
+        let user_auth_for_create = UserAuthForCreate {
+            username: username.to_string(),
+            email: format!("{username}@email.com"),
+            password: "correct-horse-battery".to_string(),
+        };
+        let user_auth = UserAuth::new(user_auth_for_create)?;
+        let res =
+            db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+        users.push(UserAuth::from_row(&res)?);
+    }
+    let user = &users[0];
+    let other = &users[1];
+
+    // create the organizations
+    let organization_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Acme".to_string())?,
+        user.id,
+    )
+    .await?;
+    let other_organization_id = OrganizationBmc::create(
+        &model_manager,
+        Organization::new("Other".to_string())?,
+        other.id,
+    )
+    .await?;
+
+    // create a session for the user
+    let session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user.id.to_string()),
+        60,
+    )
+    .await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(SwitchOrganizationRequest {
+        session_id: session_id.clone(),
+        user_id: user.id.to_string(),
+        organization_id: organization_id.to_string(),
+    });
+
+    client
+        .switch_organization(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(SwitchOrganizationRequest {
+        session_id: session_id.clone(),
+        user_id: user.id.to_string(),
+        organization_id: other_organization_id.to_string(),
+    });
+
+    let other_res = client.switch_organization(request).await;
+
+    // endregion: call grpc method
+
+    let validate_res = client
+        .validate_session(tonic::Request::new(ValidateRequest {
+            session_id: session_id.clone(),
+            user_id: user.id.to_string(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+
+    // region: tests
+
+    let session_info = validate_res
+        .session
+        .ok_or_else(|| Error::Test("missing session".to_string()))?;
+    assert_eq!(
+        session_info.active_organization_id,
+        organization_id.to_string()
+    );
+
+    let status = other_res
+        .err()
+        .ok_or_else(|| Error::Test("switched to another organization".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    // once the organization is deleted the session has no active organization
+    OrganizationBmc::delete(&model_manager, organization_id).await?;
+    let validate_res = client
+        .validate_session(tonic::Request::new(ValidateRequest {
+            session_id: session_id.clone(),
+            user_id: user.id.to_string(),
+        }))
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let session_info = validate_res
+        .session
+        .ok_or_else(|| Error::Test("missing session".to_string()))?;
+    assert!(session_info.active_organization_id.is_empty());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::UnassignRoleRequest,
+    model::{
+        db,
+        rbac::{
+            model_controller::{RoleBmc, UserRoleBmc},
+            Role,
+        },
+        user_auth::{UserAuth, UserAuthForCreate},
+    },
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the unassign_role grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Create a user with a role in the database
+/// 4. Call the unassign_role grpc method twice
+/// 5. Check that the user does not have the role anymore
+/// 6. Check that the second call failed with not_found
+/// 7. Clean all databases
+#[tokio::test]
+async fn unassign_role_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    // create the user in the database
+    let user_auth_for_create = UserAuthForCreate {
+        username: "username".to_string(),
+        email: "email@email.com".to_string(),
+        password: "correct-horse-battery".to_string(),
+    };
+    let user_auth = UserAuth::new(user_auth_for_create)?;
+    let res = db::crud::create(model_manager.db().clone(), "users_auth", user_auth.clone()).await?;
+    // newly created user
+    let user_auth_db = UserAuth::from_row(&res)?;
+
+    // create the role and assign it to the user
+    let role = "editor".to_string();
+    let role_id = RoleBmc::create(&model_manager, Role::new(role.clone(), "".to_string())?).await?;
+    UserRoleBmc::assign(&model_manager, user_auth_db.id, role_id).await?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(UnassignRoleRequest {
+        user_id: user_auth_db.id.to_string(),
+        role: role.clone(),
+    });
+
+    client
+        .unassign_role(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    let request = tonic::Request::new(UnassignRoleRequest {
+        user_id: user_auth_db.id.to_string(),
+        role: role.clone(),
+    });
+
+    let second_res = client.unassign_role(request).await;
+
+    // endregion: call grpc method
+
+    let roles = UserRoleBmc::get_roles(&model_manager, user_auth_db.id).await?;
+
+    // region: tests
+
+    assert!(roles.is_empty());
+
+    let status = second_res
+        .err()
+        .ok_or_else(|| Error::Test("role unassigned twice".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::NotFound);
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}
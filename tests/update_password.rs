@@ -2,7 +2,8 @@ use mandos::{
     error::{Error, Result},
     mandos_auth::UpdatePasswordRequest,
     model::{
-        db, session,
+        db,
+        session::{self, Session},
         user_auth::{UserAuth, UserAuthForCreate},
     },
     utils, utils_tests,
@@ -30,8 +31,8 @@ async fn update_password_works() -> Result<()> {
     // create the user in the database for login
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
-    let new_password = "new_secret".to_string();
+    let password = "correct-horse-battery".to_string();
+    let new_password = "new-correct-horse-battery".to_string();
     let user_auth_for_create = UserAuthForCreate {
         username: username.clone(),
         email: email.clone(),
@@ -45,7 +46,7 @@ async fn update_password_works() -> Result<()> {
     // create a session for the user
     let session_id = session::crud::create(
         model_manager.session_db().clone(),
-        user_auth_db.id.to_string().clone(),
+        Session::new(user_auth_db.id.to_string().clone()),
         60,
     )
     .await?;
@@ -2,7 +2,8 @@ use mandos::{
     error::{Error, Result},
     mandos_auth::ValidateRequest,
     model::{
-        db, session,
+        db,
+        session::{self, Session},
         user_auth::{UserAuth, UserAuthForCreate},
     },
     utils_tests,
@@ -17,7 +18,10 @@ use sqlx::FromRow;
 /// 4. Create a session for the user
 /// 5. Call the validate_session grpc method
 /// 6. Check that the session has been validated
-/// 7. Clean all databases
+/// 7. Check that the session has been extended by its idle timeout
+/// 8. Check that a session stored as a plain user id (before metadata) is still valid
+/// 9. Check that a session with less than a second left is deleted instead of being extended
+/// 10. Clean all databases
 #[tokio::test]
 async fn validate_session_works() -> Result<()> {
     // setup test environment
@@ -29,7 +33,7 @@ async fn validate_session_works() -> Result<()> {
     // create the user in the database for login
     let username = "username".to_string();
     let email = "email@email.com".to_string();
-    let password = "secret".to_string();
+    let password = "correct-horse-battery".to_string();
     let user_auth_for_create = UserAuthForCreate {
         username: username.clone(),
         email: email.clone(),
@@ -43,7 +47,7 @@ async fn validate_session_works() -> Result<()> {
     // create a session for the user
     let session_id = session::crud::create(
         model_manager.session_db().clone(),
-        user_auth_db.id.to_string().clone(),
+        Session::new(user_auth_db.id.to_string().clone()),
         60,
     )
     .await?;
@@ -55,19 +59,92 @@ async fn validate_session_works() -> Result<()> {
         user_id: user_auth_db.id.to_string().clone(),
     });
 
-    client
+    let validate_res = client
         .validate_session(request)
         .await
-        .map_err(|s| Error::Test(s.to_string()))?;
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
 
     // endregion: call grpc method
 
     // region: tests
 
     // check that the session was effectively validated
-    let (_, session_res_user_id) =
-        session::crud::get(model_manager.session_db().clone(), session_id).await?;
-    assert!(session_res_user_id == user_auth_db.id.to_string());
+    let (_, session_res) =
+        session::crud::get(model_manager.session_db().clone(), session_id.clone()).await?;
+    assert!(session_res.user_id == user_auth_db.id.to_string());
+
+    // check that the session metadata is returned and last_seen_at has been updated
+    let session_info = validate_res
+        .session
+        .ok_or(Error::Test("session metadata not returned".to_string()))?;
+    assert!(session_info.session_id == session_id && session_info.current);
+    assert!(session_info.last_seen_at == session_res.last_seen_at.timestamp());
+    assert!(session_res.last_seen_at >= session_res.created_at);
+
+    // check that the session has been extended by its idle timeout without going past the
+    // absolute lifetime
+    let sliding_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()).with_lifetime(100, 50),
+        10,
+    )
+    .await?;
+    let request = tonic::Request::new(ValidateRequest {
+        session_id: sliding_session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+    let sliding_res = client
+        .validate_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?
+        .into_inner();
+    let ttl = session::crud::ttl(model_manager.session_db().clone(), sliding_session_id)
+        .await?
+        .unwrap_or_default();
+    assert!(ttl > 10 && ttl <= 50);
+    let now = chrono::Utc::now().timestamp();
+    assert!(sliding_res.expires_at > now + 10 && sliding_res.expires_at <= now + 50);
+
+    // check that a session stored as a plain user id is still valid
+    let legacy_session_id = "legacy-session".to_string();
+    let mut session_db_conn = model_manager.session_db().get().await?;
+    redis::cmd("SET")
+        .arg(&[legacy_session_id.clone(), user_auth_db.id.to_string()])
+        .query_async::<_, ()>(&mut session_db_conn)
+        .await?;
+    let request = tonic::Request::new(ValidateRequest {
+        session_id: legacy_session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+    client
+        .validate_session(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+    let (_, legacy_session) =
+        session::crud::get(model_manager.session_db().clone(), legacy_session_id).await?;
+    assert!(legacy_session.user_id == user_auth_db.id.to_string());
+
+    // check that a session at the end of its lifetime is deleted instead of being extended
+    let ending_session_id = session::crud::create(
+        model_manager.session_db().clone(),
+        Session::new(user_auth_db.id.to_string()).with_lifetime(0, 50),
+        10,
+    )
+    .await?;
+    let request = tonic::Request::new(ValidateRequest {
+        session_id: ending_session_id.clone(),
+        user_id: user_auth_db.id.to_string(),
+    });
+    let status = client
+        .validate_session(request)
+        .await
+        .err()
+        .ok_or_else(|| Error::Test("ending session validated".to_string()))?;
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    let ending_session =
+        session::crud::get(model_manager.session_db().clone(), ending_session_id).await;
+    assert!(matches!(ending_session, Err(Error::SessionNotFound)));
 
     // endregion: tests
 
@@ -0,0 +1,83 @@
+use mandos::{
+    error::{Error, Result},
+    mandos_auth::{RegisterRequest, VerifyEmailRequest},
+    model::{db, user_auth::UserAuth},
+    utils_tests,
+};
+use sqlx::FromRow;
+
+/// Test that the verify_email grpc method works
+/// Steps:
+/// 1. Setup test environment (Env variables, run server in the backgroung, get client)
+/// 2. Clean all databases
+/// 3. Register a user with the register grpc method
+/// 4. Get the verification token from the mailer outbox
+/// 5. Call the verify_email grpc method
+/// 6. Check that the user does not need verification anymore
+/// 7. Check that the token cannot be used twice
+/// 8. Clean all databases
+#[tokio::test]
+async fn verify_email_works() -> Result<()> {
+    // setup test environment
+    let (model_manager, mut client) = utils_tests::setup_test_environment().await?;
+
+    // clean all databases before running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    let username = "username".to_string();
+    let email = "email@email.com".to_string();
+    let password = "correct-horse-battery".to_string();
+
+    // register the user
+    let request = tonic::Request::new(RegisterRequest {
+        username: username.clone(),
+        email: email.clone(),
+        password: password.clone(),
+    });
+    client
+        .register(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // get the verification token sent by email
+    let token = utils_tests::last_mail_token(&email)
+        .ok_or(Error::Test("verification email not sent".to_string()))?;
+
+    // region: call grpc method
+
+    let request = tonic::Request::new(VerifyEmailRequest {
+        token: token.clone(),
+    });
+
+    client
+        .verify_email(request)
+        .await
+        .map_err(|s| Error::Test(s.to_string()))?;
+
+    // endregion: call grpc method
+
+    // get the user from the database
+    let row = sqlx::query("select * from users_auth where username = $1")
+        .bind(username.clone())
+        .fetch_one(model_manager.db())
+        .await?;
+    let user_auth = UserAuth::from_row(&row)?;
+
+    // region: tests
+
+    // check that the user does not need verification anymore
+    let res =
+        db::crud::get_one_by_id(model_manager.db().clone(), "users_auth", user_auth.id).await?;
+    assert!(!UserAuth::from_row(&res)?.needs_verify);
+
+    // check that the token cannot be used twice
+    let request = tonic::Request::new(VerifyEmailRequest { token });
+    assert!(client.verify_email(request).await.is_err());
+
+    // endregion: tests
+
+    // clean all databases after running the test
+    utils_tests::clean_all_dbs(model_manager.clone()).await?;
+
+    Ok(())
+}